@@ -7,7 +7,7 @@ use serde::{
 use strum::Display;
 
 use crate::{
-  database::{DbError, Rows},
+  database::{DbError, Headers, Rows},
   focus::Focus,
 };
 
@@ -18,6 +18,15 @@ pub enum MenuPreview {
   Constraints,
   Indexes,
   Policies,
+  Definition,
+}
+
+// a row from the results along with the table it was selected from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectedRow {
+  pub table: String,
+  pub headers: Headers,
+  pub values: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
@@ -32,9 +41,32 @@ pub enum Action {
   Help,
   SubmitEditorQuery,
   Query(Vec<String>, bool),                 // (query_lines, execution_confirmed)
+  CopyFromFile(String, String),             // (query, path)
   MenuPreview(MenuPreview, String, String), // (preview, schema, table)
+  PreviewSettings(Option<String>),          // (search)
+  PreviewActivity,
   HistoryToEditor(Vec<String>),
+  OpenExternalEditor,
+  FormatQuery,
+  ExplainEditorQuery,
+  DryRunEditorQuery,
+  DryRun(Vec<String>), // (query_lines)
+  InsertFromClipboard,
+  ShowDiagnostics,
+  OpenFinder,
+  OpenObjectSearch,
+  SearchObjects(String), // (search)
+  OpenAttachDatabase,
+  AttachDatabase(String), // (path)
+  DetachDatabase(String), // (schema)
+  ExportSchema(String),   // (schema)
+  OpenReconnect,
+  Reconnect(Vec<(String, String)>), // (name, value) connection params
+  ToggleSplit,
+  EditInExternalEditor(Vec<String>), // (query_lines)
+  PipeToPager(String),               // (results)
   ClearHistory,
+  TogglePinHistory(usize), // (history_index)
   AbortQuery,
   FocusMenu,
   FocusEditor,
@@ -44,4 +76,25 @@ pub enum Action {
   CycleFocusBackwards,
   LoadMenu,
   CopyData(String),
+  InsertIntoEditor(String),
+  ConfirmPaste(String), // (text)
+  ShowRegisters,
+  NewBuffer,
+  NextBuffer,
+  PrevBuffer,
+  CloseBuffer,
+  OpenRegisters(Vec<(char, String)>), // (name, text)
+  EditCell(SelectedRow, usize),       // (row, column_index)
+  DeleteRow(SelectedRow),
+  ViewJson(String),
+  ViewCell(String),
+  ShowHistogram(String, Vec<String>),        // (column, values)
+  OpenFilter(String),                        // (table)
+  OpenColumnPicker(Vec<String>, Vec<usize>), // (headers, visible_columns)
+  SetVisibleColumns(Vec<usize>),
+  RecordSearch(Focus, String), // (search_box, search)
+  OpenJumpToRow(usize),        // (row_count)
+  JumpToRow(usize),            // (row_index)
+  PreviewFiltered(String),     // (query)
+  KillSession(String, bool),   // (session_id, cancel_only)
 }
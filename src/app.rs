@@ -1,4 +1,4 @@
-use std::{borrow::Borrow, fmt::format, sync::Arc};
+use std::{borrow::Borrow, collections::HashMap, fmt::format, sync::Arc};
 
 #[cfg(not(feature = "termux"))]
 use arboard::Clipboard;
@@ -10,7 +10,7 @@ use ratatui::{
   layout::{Constraint, Direction, Layout, Position},
   prelude::Rect,
   style::{Color, Style, Stylize},
-  text::Line,
+  text::{Line, Span},
   widgets::{Block, Borders, Clear, Padding, Paragraph, Tabs, Wrap},
   Frame,
 };
@@ -37,28 +37,83 @@ use crate::{
   components::{
     data::{Data, DataComponent},
     editor::Editor,
-    history::History,
+    history::{self, History},
     menu::{Menu, MenuComponent},
     Component,
   },
-  config::Config,
-  database::{self, get_dialect, statement_type_string, DatabaseQueries, DbError, DbPool, ExecutionType, Rows},
+  config::{is_pending_sequence, key_event_to_string, ClipboardBackend, Config, QueryNotification, Split},
+  database::{
+    self, get_dialect, statement_type_string, DatabaseQueries, DbError, DbPool, ExecutionType, Rows, SchemaCache,
+  },
+  erd,
   focus::Focus,
-  popups::{confirm_query::ConfirmQuery, confirm_tx::ConfirmTx, PopUp, PopUpPayload},
+  popups::{
+    attach_database::AttachDatabase,
+    cell_viewer::CellViewer,
+    column_picker::ColumnPicker,
+    confirm_kill::ConfirmKill,
+    confirm_paste::ConfirmPaste,
+    confirm_preview::ConfirmPreview,
+    confirm_query::ConfirmQuery,
+    confirm_quit::ConfirmQuit,
+    confirm_tx::ConfirmTx,
+    copy_from_file::CopyFromFile,
+    edit_cell::EditCell,
+    filter_rows::FilterRows,
+    fuzzy_finder::{FinderItem, FuzzyFinder},
+    histogram::Histogram,
+    insert_rows::InsertRows,
+    json_viewer::JsonViewer,
+    jump_to_row::JumpToRow,
+    message::Message,
+    object_search::ObjectSearch,
+    reconnect::Reconnect,
+    registers::RegisterViewer,
+    PopUp, PopUpPayload,
+  },
   tui,
   ui::center,
+  utils,
 };
 
+// below this, the panes are too cramped to be usable, so only a placeholder is drawn
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 15;
+const SEARCH_HISTORY_LIMIT: usize = 20;
+// how many of the rows a pending write changes can be previewed before committing
+pub const AFFECTED_PREVIEW_ROWS: u64 = 100;
+
+#[allow(clippy::large_enum_variant)]
 pub enum DbTask<'a, DB: sqlx::Database> {
   Query(tokio::task::JoinHandle<QueryResultsWithMetadata>),
+  // a read's row estimate, before it runs: (estimate, query as written, query as it runs, statement)
+  Estimate(tokio::task::JoinHandle<Option<u64>>, String, String, Statement),
   TxStart(tokio::task::JoinHandle<(QueryResultsWithMetadata, Transaction<'a, DB>)>),
   TxPending(Transaction<'a, DB>, QueryResultsWithMetadata),
   TxCommit(tokio::task::JoinHandle<QueryResultsWithMetadata>),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteOutcome {
+  Committed,
+  Pending,
+  RolledBack,
+  Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteRecord {
+  pub statement_type: String,
+  pub rows_affected: Option<u64>,
+  pub outcome: WriteOutcome,
+}
+
 pub struct HistoryEntry {
   pub query_lines: Vec<String>,
-  pub timestamp: chrono::DateTime<chrono::Local>,
+  pub timestamp: chrono::DateTime<chrono::Local>, // of the latest run
+  pub write: Option<WriteRecord>,
+  pub runs: usize,
+  pub pinned: bool,
 }
 
 pub struct AppState<'a, DB: Database> {
@@ -66,9 +121,54 @@ pub struct AppState<'a, DB: Database> {
   pub dialect: Arc<dyn Dialect + Send + Sync>,
   pub focus: Focus,
   pub query_task: Option<DbTask<'a, DB>>,
+  // loads the schema that the menu lists and other components look things up in
+  pub schema_task: Option<JoinHandle<Result<SchemaCache, DbError>>>,
+  // the last schema that loaded, kept while a refresh is in flight
+  pub schema: Option<SchemaCache>,
   pub history: Vec<HistoryEntry>,
   pub last_query_start: Option<chrono::DateTime<chrono::Utc>>,
   pub last_query_end: Option<chrono::DateTime<chrono::Utc>>,
+  pub pool_waits: Arc<std::sync::Mutex<database::PoolWaits>>,
+  pub menu_loaded_at: Option<chrono::DateTime<chrono::Utc>>,
+  pub current_schema: Option<String>,
+  pub last_result_rows: Option<usize>,
+  // the LIMIT that the auto_limit setting added to the last query, if any
+  pub auto_limited: Option<u64>,
+  // each search box's earlier searches, oldest first
+  pub search_history: HashMap<Focus, Vec<String>>,
+  // set when replacing the marked rows with a preview has been confirmed
+  pub preview_confirmed: bool,
+  // the host that ran the last query, when there are replicas to choose from
+  pub served_by: Option<String>,
+  // the last query's connection was reset, and it was run again on a fresh one
+  pub query_retried: bool,
+}
+
+impl<DB: Database> AppState<'_, DB> {
+  // repeats move back to the end, like in a shell's history
+  pub fn record_search(&mut self, search_box: Focus, search: &str) {
+    let search = search.trim();
+    if search.is_empty() {
+      return;
+    }
+    let searches = self.search_history.entry(search_box).or_default();
+    searches.retain(|s| s != search);
+    searches.push(search.to_owned());
+    if searches.len() > SEARCH_HISTORY_LIMIT {
+      searches.remove(0);
+    }
+  }
+
+  // only one query runs at a time and it is always the most recently run history
+  // entry, so that is where the outcome of a write belongs
+  pub fn record_write(&mut self, statement_type: &Statement, rows_affected: Option<u64>, outcome: WriteOutcome) {
+    if !database::is_write_statement(statement_type) {
+      return;
+    }
+    if let Some(entry) = self.history.iter_mut().max_by_key(|entry| entry.timestamp) {
+      entry.write = Some(WriteRecord { statement_type: statement_type_string(statement_type), rows_affected, outcome });
+    }
+  }
 }
 
 pub struct Components<'a, DB> {
@@ -82,24 +182,43 @@ pub struct Components<'a, DB> {
 pub struct QueryResultsWithMetadata {
   pub results: Result<Rows, DbError>,
   pub statement_type: Statement,
+  // for writes run in a transaction, the rows they change as they were before
+  pub affected_preview: Option<Rows>,
+  // set when a read sent to a replica was served by the primary instead
+  pub failed_over_to: Option<String>,
+  // the connection was reset while it ran, and it was run again on a fresh one
+  pub retried: bool,
 }
 
 pub struct App<'a, DB: sqlx::Database> {
   pub mouse_mode_override: Option<bool>,
+  pub connection_name: String,
   pub config: Config,
   pub components: Components<'static, DB>,
   pub should_quit: bool,
   pub last_tick_key_events: Vec<KeyEvent>,
+  pub last_key_event_at: Option<std::time::Instant>,
   pub last_frame_mouse_event: Option<MouseEvent>,
   pub pool: Option<database::DbPool<DB>>,
   pub state: AppState<'a, DB>,
   last_focused_tab: Focus,
+  last_menu_request: Option<std::time::Instant>,
+  split: Split,
   popup: Option<Box<dyn PopUp<DB>>>,
+  // where focus goes back to when the popup is cancelled
+  popup_opener: Focus,
+  initial_query: Option<(Vec<String>, bool)>, // (query_lines, run)
+  attached: Vec<(String, String)>,            // (path, alias)
+  // assumed until the terminal says otherwise, since not every terminal reports focus
+  terminal_focused: bool,
+  // a second quit while the confirmation is open quits anyway
+  confirming_quit: bool,
+  replica: Option<(database::DbPool<DB>, String)>, // (pool, name)
 }
 
 impl<DB> App<'_, DB>
 where
-  DB: Database + database::ValueParser + database::DatabaseQueries,
+  DB: Database + database::BuildConnectionOptions + database::ValueParser + database::DatabaseQueries,
   DB::QueryResult: database::HasRowsAffected,
   for<'c> <DB as sqlx::Database>::Arguments<'c>: sqlx::IntoArguments<'c, DB>,
   for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
@@ -107,13 +226,16 @@ where
   pub fn new(
     connection_opts: <DB::Connection as Connection>::Options,
     mouse_mode_override: Option<bool>,
+    connection_name: String,
+    config: Config,
   ) -> Result<Self> {
     let focus = Focus::Menu;
     let menu = Menu::new();
     let editor = Editor::new();
     let history = History::new();
     let data = Data::new();
-    let config = Config::new()?;
+    database::set_display_timezone(config.display_timezone());
+    let split = config.settings.split.unwrap_or_default();
     Ok(Self {
       components: Components {
         menu: Box::new(menu),
@@ -123,8 +245,10 @@ where
       },
       should_quit: false,
       mouse_mode_override,
+      connection_name,
       config,
       last_tick_key_events: Vec::new(),
+      last_key_event_at: None,
       last_frame_mouse_event: None,
       pool: None,
       state: AppState {
@@ -132,34 +256,277 @@ where
         dialect: get_dialect(DB::NAME),
         focus,
         query_task: None,
+        schema_task: None,
+        schema: None,
         history: vec![],
         last_query_start: None,
         last_query_end: None,
+        pool_waits: Arc::new(std::sync::Mutex::new(database::PoolWaits::default())),
+        menu_loaded_at: None,
+        current_schema: None,
+        last_result_rows: None,
+        auto_limited: None,
+        search_history: HashMap::new(),
+        preview_confirmed: false,
+        served_by: None,
+        query_retried: false,
       },
       last_focused_tab: Focus::Editor,
+      last_menu_request: None,
+      split,
       popup: None,
+      popup_opener: Focus::Data,
+      initial_query: None,
+      attached: vec![],
+      terminal_focused: true,
+      confirming_quit: false,
+      replica: None,
     })
   }
 
+  pub fn initial_query(mut self, query: Option<String>, run: bool) -> Self {
+    self.initial_query = query.map(|q| (q.lines().map(|l| l.to_owned()).collect(), run));
+    self
+  }
+
   fn add_to_history(&mut self, query_lines: Vec<String>) {
-    self.state.history.insert(0, HistoryEntry { query_lines, timestamp: chrono::Local::now() });
-    if self.state.history.len() > 50 {
-      self.state.history.pop();
+    // 0 keeps every entry
+    let max_entries = match self.config.settings.history_max_entries {
+      Some(0) => None,
+      max_entries => Some(max_entries.unwrap_or(history::DEFAULT_MAX_HISTORY)),
+    };
+    history::record_run(&mut self.state.history, query_lines, chrono::Local::now(), max_entries);
+  }
+
+  // every new connection gets the session settings and the attached databases
+  fn session_setup(&self) -> Vec<String> {
+    let mut session_setup = vec![];
+    session_setup.extend(
+      self.config.settings.statement_timeout.filter(|timeout| *timeout > 0).and_then(DB::statement_timeout_statement),
+    );
+    session_setup.extend(self.attached.iter().filter_map(|(path, alias)| DB::attach_database_statement(path, alias)));
+    session_setup
+  }
+
+  async fn connect(&self) -> Result<DbPool<DB>, sqlx::Error> {
+    database::init_pool::<DB>(
+      self.state.connection_opts.clone(),
+      self.config.settings.max_connections.unwrap_or(3),
+      self.session_setup(),
+    )
+    .await
+  }
+
+  // the first of the replicas setting's hosts that accepts a connection. when none do,
+  // everything runs on the primary
+  async fn connect_replica(&self) -> Option<(DbPool<DB>, String)> {
+    let replicas = self.config.settings.replicas.as_ref().filter(|replicas| !replicas.trim().is_empty())?;
+    // only connections made to a host can have replicas
+    if !DB::connection_params(&self.state.connection_opts).iter().any(|(name, _)| name == "host") {
+      log::warn!("replicas are ignored for {}", DB::NAME);
+      return None;
+    }
+    let candidates = match database::replica_opts::<DB>(&self.state.connection_opts, replicas) {
+      Ok(candidates) => candidates,
+      Err(e) => {
+        log::error!("replicas: {}", e);
+        return None;
+      },
+    };
+    for opts in candidates {
+      let name = DB::describe_connection(&opts);
+      match database::init_pool::<DB>(opts, self.config.settings.max_connections.unwrap_or(3), self.session_setup())
+        .await
+      {
+        Ok(pool) => return Some((pool, name)),
+        Err(e) => log::warn!("could not connect to replica {}: {}", name, e),
+      }
+    }
+    None
+  }
+
+  // only while the terminal is in the background; otherwise the results are right there
+  fn notify_query_finished(&self, message: String) {
+    let notification = self.config.settings.query_notification.unwrap_or_default();
+    if self.terminal_focused || notification == QueryNotification::Off {
+      return;
+    }
+    let bell = matches!(notification, QueryNotification::Bell | QueryNotification::Both);
+    let desktop = matches!(notification, QueryNotification::Desktop | QueryNotification::Both);
+    if let Err(e) = utils::notify(&format!("rainfrog: {}", message), bell, desktop) {
+      log::error!("{}", e);
     }
   }
 
   fn clear_history(&mut self) {
-    self.state.history = vec![];
+    self.state.history.retain(|entry| entry.pinned);
+  }
+
+  async fn primary_key_columns(&self, table: &str) -> Vec<String> {
+    let (Some(pool), Some((schema, table))) =
+      (&self.pool, database::parse_table_name(table, self.state.dialect.as_ref()))
+    else {
+      return vec![];
+    };
+    if let Some(columns) = self.state.schema.as_ref().and_then(|cache| cache.columns(schema.as_deref(), &table)) {
+      return columns.iter().filter(|column| column.primary_key).map(|column| column.name.clone()).collect();
+    }
+    let query = DB::primary_key_query(schema.as_deref(), &table);
+    match database::query(query, self.state.dialect.as_ref(), pool).await {
+      Ok(rows) => rows.rows.into_iter().filter_map(|r| r.into_iter().next()).collect(),
+      Err(e) => {
+        log::error!("{}", e);
+        vec![]
+      },
+    }
+  }
+
+  // (name, type) pairs for each of the table's columns
+  async fn table_columns(&self, table: &str) -> Result<Vec<(String, String)>, String> {
+    let dialect = self.state.dialect.as_ref();
+    let (Some(pool), Some((schema, table_name))) = (&self.pool, database::parse_table_name(table, dialect)) else {
+      return Err(format!("{} is not a valid table name.", table));
+    };
+    if let Some(columns) = self.state.schema.as_ref().and_then(|cache| cache.columns(schema.as_deref(), &table_name)) {
+      return Ok(columns.iter().map(|column| (column.name.clone(), column.type_name.clone())).collect());
+    }
+    let schema = match schema {
+      Some(schema) => schema,
+      None => {
+        database::query(DB::current_schema_query(), dialect, pool)
+          .await
+          .ok()
+          .and_then(|current| current.rows.into_iter().next()?.into_iter().next())
+          .unwrap_or_default()
+      },
+    };
+    let columns = database::query(DB::preview_columns_query(&schema, &table_name), dialect, pool)
+      .await
+      .map_err(|e| e.to_string())?;
+    Ok(database::column_types(&columns))
+  }
+
+  // writes the schema's er diagram to a new file named after the schema and the time, returning
+  // what was written where. it's drawn from the cached schema, so nothing is queried
+  fn export_schema(&self, schema: &str) -> Result<String, String> {
+    let cache = self.state.schema.as_ref().ok_or("The schema is still loading; try again once the menu has loaded.")?;
+    let tables = cache.schema_tables(schema);
+    let foreign_keys = erd::ForeignKey::from_tables(&tables);
+    let format = self.config.settings.erd_format.unwrap_or_default();
+    let name = format!(
+      "{}-{}.{}",
+      if schema.is_empty() { "main" } else { schema },
+      chrono::Local::now().format("%Y%m%d-%H%M%S"),
+      format.extension()
+    );
+    let path = match &self.config.settings.erd_directory {
+      Some(directory) => std::path::Path::new(directory).join(name),
+      None => std::path::PathBuf::from(name),
+    };
+    // an earlier export is never overwritten
+    let mut file = std::fs::OpenOptions::new()
+      .write(true)
+      .create_new(true)
+      .open(&path)
+      .map_err(|e| format!("{}: {}", path.display(), e))?;
+    std::io::Write::write_all(&mut file, erd::render(format, schema, &tables, &foreign_keys).as_bytes())
+      .map_err(|e| e.to_string())?;
+    Ok(format!("Wrote {} tables and {} foreign key columns to {}", tables.len(), foreign_keys.len(), path.display()))
+  }
+
+  async fn insert_rows_statement(&self, table: &str, rows: &[Vec<String>]) -> Result<String, String> {
+    database::insert_rows_statement::<DB>(table, &self.table_columns(table).await?, rows)
+  }
+
+  // gives up quickly, like count_rows, since a slow plan only delays the query
+  fn estimate_rows(&self, pool: &Pool<DB>, query: &str) -> tokio::task::JoinHandle<Option<u64>> {
+    let explain = format!("{} {}", DB::explain_prefix(false), query);
+    let (pool, dialect) = (pool.clone(), self.state.dialect.clone());
+    tokio::spawn(async move {
+      let plan = database::query(explain, dialect.as_ref(), &pool);
+      let plan = tokio::time::timeout(std::time::Duration::from_secs(2), plan).await.ok()?.ok()?;
+      database::estimated_rows(&plan)
+    })
+  }
+
+  // runs in the background, on a replica when it only reads
+  fn spawn_query(&mut self, pool: DbPool<DB>, query_string: String, statement_type: Statement) {
+    self.components.data.set_loading();
+    // only plain reads go to a replica; writes and transactions stay on the primary
+    let (pool, served_by, primary) = match &self.replica {
+      Some((replica, name)) if database::reads_only(&statement_type) => {
+        (replica.clone(), name.clone(), Some((pool, self.connection_name.clone())))
+      },
+      _ => (pool, self.connection_name.clone(), None),
+    };
+    self.state.served_by = self.replica.as_ref().map(|_| served_by);
+    let dialect = self.state.dialect.clone();
+    let pool_waits = self.state.pool_waits.clone();
+    self.state.query_task = Some(DbTask::Query(tokio::spawn(async move {
+      let (mut results, mut retried) =
+        database::query_timed(query_string.clone(), dialect.as_ref(), &pool, &pool_waits).await;
+      // a replica that went away mid-session shouldn't fail a read the primary can serve
+      let mut failed_over_to = None;
+      let replica_failed = results.as_ref().is_err_and(database::is_connection_error);
+      if let (true, Some((primary, name))) = (replica_failed, primary) {
+        log::warn!("replica failed, retrying on the primary: {:?}", results.as_ref().err());
+        (results, retried) = database::query_timed(query_string.clone(), dialect.as_ref(), &primary, &pool_waits).await;
+        failed_over_to = Some(name);
+      }
+      match &results {
+        Ok(rows) => {
+          log::info!("{:?} rows, {:?} affected", rows.rows.len(), rows.rows_affected);
+        },
+        Err(e) => {
+          log::error!("{e:?}");
+        },
+      };
+
+      QueryResultsWithMetadata { results, statement_type, affected_preview: None, failed_over_to, retried }
+    })));
+    self.state.last_query_start = Some(chrono::Utc::now());
+    self.state.last_query_end = None;
+  }
+
+  // counted in the background and filled in when it's done. it gives up quickly on
+  // big tables, since it only makes a warning more specific
+  fn count_rows(&self, pool: &Pool<DB>, table: String) -> Arc<std::sync::Mutex<Option<String>>> {
+    let row_count = Arc::new(std::sync::Mutex::new(None));
+    let (pool, dialect, count) = (pool.clone(), self.state.dialect.clone(), row_count.clone());
+    tokio::spawn(async move {
+      let query = database::query(database::count_rows_query(&table), dialect.as_ref(), &pool);
+      if let Ok(Ok(rows)) = tokio::time::timeout(std::time::Duration::from_secs(2), query).await {
+        *count.lock().unwrap() = rows.rows.into_iter().next().and_then(|row| row.into_iter().next());
+      }
+    });
+    row_count
+  }
+
+  async fn refresh_current_schema(&mut self) {
+    let Some(pool) = &self.pool else {
+      return;
+    };
+    self.state.current_schema = database::query(DB::current_schema_query(), self.state.dialect.as_ref(), pool)
+      .await
+      .ok()
+      .and_then(|current| current.rows.into_iter().next()?.into_iter().next());
   }
 
   pub async fn run(&mut self) -> Result<()> {
     let (action_tx, mut action_rx) = mpsc::unbounded_channel();
-    let connection_opts = self.state.connection_opts.clone();
-    let pool = database::init_pool::<DB>(connection_opts).await?;
+    let pool = self.connect().await?;
     log::info!("{pool:?}");
     self.pool = Some(pool);
+    self.replica = self.connect_replica().await;
+    self.refresh_current_schema().await;
 
-    let mut tui = tui::Tui::new()?.mouse(self.mouse_mode_override.or(self.config.settings.mouse_mode));
+    let title =
+      self.config.settings.terminal_title.unwrap_or(false).then(|| format!("rainfrog – {}", self.connection_name));
+    let notification = self.config.settings.query_notification.unwrap_or_default();
+    let mut tui = tui::Tui::new()?
+      .mouse(self.mouse_mode_override.or(self.config.settings.mouse_mode))
+      .focus_change(notification != QueryNotification::Off)
+      .title(title);
     tui.enter()?;
 
     #[allow(unused_mut)]
@@ -183,6 +550,13 @@ where
     self.components.data.init(Rect { width: size.width, height: size.height, x: 0, y: 0 })?;
 
     action_tx.send(Action::LoadMenu)?;
+    if let Some((query_lines, run)) = self.initial_query.take() {
+      action_tx.send(Action::HistoryToEditor(query_lines))?;
+      action_tx.send(Action::FocusEditor)?;
+      if run {
+        action_tx.send(Action::SubmitEditorQuery)?;
+      }
+    }
 
     loop {
       if let Some(popup) = &mut self.popup {
@@ -193,20 +567,75 @@ where
           if task.is_finished() {
             let results = task.await?;
             self.state.query_task = None;
+            if let Some(primary) = &results.failed_over_to {
+              self.state.served_by = Some(primary.clone());
+            }
+            self.state.query_retried = results.retried;
+            match &results.results {
+              Ok(rows) => self.state.record_write(&results.statement_type, rows.rows_affected, WriteOutcome::Committed),
+              Err(_) => self.state.record_write(&results.statement_type, None, WriteOutcome::Failed),
+            }
+            self.state.last_result_rows = results.results.as_ref().ok().map(|rows| rows.rows.len());
+            // the status bar shows the schema, which these can change
+            if matches!(results.statement_type, Statement::Use(_) | Statement::SetVariable { .. }) {
+              self.refresh_current_schema().await;
+            }
+            // the menu and the cached schema would otherwise miss the change until the next refresh
+            if results.results.is_ok()
+              && matches!(
+                results.statement_type,
+                Statement::CreateTable(_)
+                  | Statement::CreateView { .. }
+                  | Statement::AlterTable { .. }
+                  | Statement::Drop { .. }
+              )
+            {
+              action_tx.send(Action::LoadMenu)?;
+            }
+            self.notify_query_finished(match &results.results {
+              Ok(rows) if !rows.rows.is_empty() => format!("query finished ({} rows)", rows.rows.len()),
+              Ok(_) => "query finished".to_owned(),
+              Err(_) => "query failed".to_owned(),
+            });
             self.components.data.set_data_state(Some(results.results), Some(results.statement_type));
             self.state.last_query_end = Some(chrono::Utc::now());
           }
         },
+        Some(DbTask::Estimate(task, ..)) => {
+          if task.is_finished() {
+            let estimate = task.await?;
+            let threshold = self.config.settings.row_estimate_threshold.unwrap_or_default();
+            if let (Some(DbTask::Estimate(_, original, query_string, statement_type)), Some(pool)) =
+              (self.state.query_task.take(), self.pool.clone())
+            {
+              match estimate.filter(|rows| *rows > threshold) {
+                Some(estimate) => {
+                  self.popup =
+                    Some(Box::new(ConfirmQuery::<DB>::new(original, statement_type).estimated_rows(estimate)));
+                  self.state.focus = Focus::PopUp;
+                },
+                None => self.spawn_query(pool, query_string, statement_type),
+              }
+            }
+          }
+        },
         Some(DbTask::TxStart(task)) => {
           if task.is_finished() {
             let (results, tx) = task.await?;
             match results.results {
-              Ok(_) => {
+              Ok(Rows { rows_affected, .. }) => {
+                self.state.record_write(&results.statement_type, rows_affected, WriteOutcome::Pending);
+                self.notify_query_finished("query finished, waiting to commit".to_owned());
                 self.state.query_task = Some(DbTask::TxPending(tx, results));
-                self.popup = Some(Box::new(ConfirmTx::<DB>::new()));
-                self.state.focus = Focus::PopUp;
+                // an open quit confirmation now offers to commit instead
+                if !self.confirming_quit {
+                  self.popup = Some(Box::new(ConfirmTx::<DB>::new()));
+                  self.state.focus = Focus::PopUp;
+                }
               },
               Err(_) => {
+                self.state.record_write(&results.statement_type, None, WriteOutcome::Failed);
+                self.notify_query_finished("query failed".to_owned());
                 self.state.query_task = None;
                 self.components.data.set_data_state(Some(results.results), Some(results.statement_type));
               },
@@ -217,6 +646,20 @@ where
         Some(DbTask::TxCommit(task)) => {},
         _ => {},
       }
+      if let Some(task) = &mut self.state.schema_task {
+        if task.is_finished() {
+          let results = task.await?;
+          self.state.schema_task = None;
+          match results {
+            Ok(schema) => {
+              self.state.menu_loaded_at = Some(chrono::Utc::now());
+              self.components.menu.set_table_list(Some(Ok(schema.objects().clone())));
+              self.state.schema = Some(schema);
+            },
+            Err(e) => self.components.menu.set_table_list(Some(Err(e))),
+          }
+        }
+      }
       if let Some(e) = tui.next().await {
         let mut event_consumed = false;
         match e {
@@ -225,6 +668,8 @@ where
           tui::Event::Render => action_tx.send(Action::Render)?,
           tui::Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
           tui::Event::Mouse(event) => self.last_frame_mouse_event = Some(event),
+          tui::Event::FocusGained => self.terminal_focused = true,
+          tui::Event::FocusLost => self.terminal_focused = false,
           tui::Event::Key(key) => {
             if let Some(keymap) = self.config.keybindings.get(&self.state.focus) {
               if let Some(action) = keymap.get(&vec![key]) {
@@ -237,6 +682,8 @@ where
                 let payload = popup.handle_key_events(key, &mut self.state).await?;
                 match payload {
                   Some(PopUpPayload::SetDataTable(result, statement)) => {
+                    self.state.last_result_rows =
+                      result.as_ref().and_then(|result| result.as_ref().ok()).map(|rows| rows.rows.len());
                     self.components.data.set_data_state(result, statement);
                     self.popup = None;
                     self.state.focus = Focus::Editor;
@@ -246,19 +693,78 @@ where
                     self.popup = None;
                     self.state.focus = Focus::Editor;
                   },
+                  Some(PopUpPayload::Query(query)) => {
+                    action_tx.send(Action::Query(vec![query], false))?;
+                    self.popup = None;
+                    self.state.focus = Focus::Data;
+                  },
+                  Some(PopUpPayload::InsertRows(table, rows)) => {
+                    self.popup = None;
+                    match self.insert_rows_statement(&table, &rows).await {
+                      Ok(statement) => {
+                        action_tx.send(Action::HistoryToEditor(statement.lines().map(|l| l.to_owned()).collect()))?;
+                        self.state.focus = Focus::Editor;
+                      },
+                      Err(message) => self.popup = Some(Box::new(Message::<DB>::new(message))),
+                    }
+                  },
+                  Some(PopUpPayload::Action(action)) => {
+                    action_tx.send(action)?;
+                    self.popup = None;
+                    self.state.focus = Focus::Editor;
+                    self.last_focused_tab = Focus::Editor;
+                  },
+                  Some(PopUpPayload::SetVisibleColumns(columns)) => {
+                    action_tx.send(Action::SetVisibleColumns(columns))?;
+                    self.popup = None;
+                    self.state.focus = Focus::Data;
+                  },
+                  Some(PopUpPayload::JumpToRow(row)) => {
+                    action_tx.send(Action::JumpToRow(row))?;
+                    self.popup = None;
+                    self.state.focus = Focus::Data;
+                  },
+                  Some(PopUpPayload::CopyData(data)) => {
+                    action_tx.send(Action::CopyData(data))?;
+                    self.popup = None;
+                    self.state.focus = Focus::Data;
+                  },
+                  Some(PopUpPayload::Cancel) => {
+                    self.popup = None;
+                    self.state.focus = self.popup_opener;
+                    // the pending transaction still needs an answer
+                    if std::mem::take(&mut self.confirming_quit)
+                      && matches!(self.state.query_task, Some(DbTask::TxPending(..)))
+                    {
+                      self.popup = Some(Box::new(ConfirmTx::<DB>::new()));
+                      self.state.focus = Focus::PopUp;
+                    }
+                  },
                   None => {},
                 }
                 event_consumed = true;
               } else {
                 // If the key was not handled as a single key action,
                 // then consider it for multi-key combinations.
-                self.last_tick_key_events.push(key);
+                // typed search text goes straight to the menu
+                if self.state.focus == Focus::Menu && self.components.menu.is_typing_search() {
+                  self.last_tick_key_events.clear();
+                } else {
+                  self.last_tick_key_events.push(key);
+                  self.last_key_event_at = Some(std::time::Instant::now());
 
-                // Check for multi-key combinations
-                if let Some(action) = keymap.get(&self.last_tick_key_events) {
-                  log::info!("Got action: {action:?}");
-                  action_tx.send(action.clone())?;
-                  event_consumed = true;
+                  // Check for multi-key combinations. keys that start one wait for
+                  // the rest of it instead of going to the focused component
+                  if let Some(action) = keymap.get(&self.last_tick_key_events) {
+                    log::info!("Got action: {action:?}");
+                    action_tx.send(action.clone())?;
+                    self.last_tick_key_events.clear();
+                    event_consumed = true;
+                  } else if is_pending_sequence(keymap, &self.last_tick_key_events) {
+                    event_consumed = true;
+                  } else {
+                    self.last_tick_key_events.clear();
+                  }
                 }
               }
             }
@@ -293,12 +799,41 @@ where
         if action != Action::Tick && action != Action::Render {
           log::debug!("{action:?}");
         }
-        let action_consumed = false;
+        let mut action_consumed = false;
+        let opened_from = self.popup.is_none().then_some(self.state.focus).filter(|focus| *focus != Focus::PopUp);
         match &action {
           Action::Tick => {
-            self.last_tick_key_events.drain(..);
+            let timeout = self.config.settings.key_sequence_timeout.unwrap_or(1000);
+            if self.last_key_event_at.is_some_and(|at| at.elapsed().as_millis() >= timeout as u128) {
+              self.last_tick_key_events.clear();
+              self.last_key_event_at = None;
+            }
+            let refresh_interval = self.config.settings.menu_refresh_interval.unwrap_or(0);
+            if let (Some(requested_at), None, true) =
+              (self.last_menu_request, &self.state.schema_task, refresh_interval > 0)
+            {
+              if requested_at.elapsed().as_secs() >= refresh_interval {
+                action_tx.send(Action::LoadMenu)?;
+              }
+            }
+          },
+          Action::MenuPreview(..) => {
+            let marked = self.components.data.marked_row_count();
+            if !std::mem::take(&mut self.state.preview_confirmed) && marked > 0 {
+              self.popup = Some(Box::new(ConfirmPreview::<DB>::new(action.clone(), marked)));
+              self.state.focus = Focus::PopUp;
+              action_consumed = true;
+            }
+          },
+          Action::Quit => {
+            if self.state.query_task.is_some() && !self.confirming_quit {
+              self.confirming_quit = true;
+              self.popup = Some(Box::new(ConfirmQuit::<DB>::new()));
+              self.state.focus = Focus::PopUp;
+            } else {
+              self.should_quit = true;
+            }
           },
-          Action::Quit => self.should_quit = true,
           Action::Resize(w, h) => {
             tui.resize(Rect::new(0, 0, *w, *h))?;
             tui.draw(|f| {
@@ -358,75 +893,128 @@ where
           Action::LoadMenu => {
             log::info!("LoadMenu");
             if let Some(pool) = &self.pool {
-              let results = database::query(DB::preview_tables_query(), self.state.dialect.as_ref(), pool).await;
-              self.components.menu.set_table_list(Some(results));
+              if let Some(task) = self.state.schema_task.take() {
+                task.abort();
+              }
+              self.last_menu_request = Some(std::time::Instant::now());
+              let pool = pool.clone();
+              let dialect = self.state.dialect.clone();
+              self.state.schema_task = Some(tokio::spawn(async move {
+                let mut objects = database::query(DB::preview_tables_query(), dialect.as_ref(), &pool).await?;
+                // routines and columns are optional; a failure here shouldn't hide the tables
+                match database::query(DB::preview_functions_query(), dialect.as_ref(), &pool).await {
+                  Ok(routines) => objects.rows.extend(routines.rows),
+                  Err(e) => log::error!("{}", e),
+                }
+                let columns = database::query(DB::schema_columns_query(), dialect.as_ref(), &pool).await;
+                let columns = columns.unwrap_or_else(|e| {
+                  log::error!("{}", e);
+                  Rows { headers: vec![], rows: vec![], rows_affected: None }
+                });
+                Ok(SchemaCache::from_rows(objects, &columns))
+              }));
             }
           },
           Action::Query(query_lines, confirmed) => {
             let query_string = query_lines.clone().join(" \n");
             if !query_string.is_empty() {
               self.add_to_history(query_lines.clone());
+              self.state.auto_limited = None;
+              self.state.query_retried = false;
               let first_query = database::get_first_query(query_string.clone(), self.state.dialect.as_ref());
               let execution_type = first_query.map(|(_, statement_type)| {
-                (database::get_execution_type(statement_type.clone(), *confirmed), statement_type)
+                (
+                  database::get_execution_type_with_policy(
+                    statement_type.clone(),
+                    *confirmed,
+                    self.config.settings.statement_policy.as_ref(),
+                  ),
+                  statement_type,
+                )
               });
               let action_tx = action_tx.clone();
               if let Some(pool) = &self.pool {
                 let pool = pool.clone();
                 let dialect = self.state.dialect.clone();
                 match execution_type {
+                  // only once any confirmation the statement_policy asks for is given
+                  Ok((ExecutionType::Normal, statement_type)) if database::is_copy_from_stdin(&statement_type) => {
+                    self.popup = Some(Box::new(CopyFromFile::<DB>::new(query_string.clone())));
+                    self.state.focus = Focus::PopUp;
+                  },
                   Ok((ExecutionType::Transaction, statement_type)) => {
                     self.components.data.set_loading();
+                    let queued = database::pool_is_saturated(&pool);
+                    let started = std::time::Instant::now();
                     let tx = pool.begin().await?;
+                    self.state.pool_waits.lock().unwrap().record(queued, started.elapsed());
+                    self.state.served_by = self.replica.as_ref().map(|_| self.connection_name.clone());
                     self.state.query_task = Some(DbTask::TxStart(tokio::spawn(async move {
+                      let mut tx = tx;
+                      let affected_preview =
+                        database::preview_affected_rows::<DB>(&mut tx, &statement_type, AFFECTED_PREVIEW_ROWS).await;
                       let (results, tx) =
                         database::query_with_tx::<DB>(tx, dialect.as_ref(), query_string.clone()).await;
-                      match results {
+                      let results = match results {
                         Ok(Either::Left(rows_affected)) => {
                           log::info!("{:?} rows affected", rows_affected);
-                          (
-                            QueryResultsWithMetadata {
-                              results: Ok(Rows { headers: vec![], rows: vec![], rows_affected: Some(rows_affected) }),
-                              statement_type,
-                            },
-                            tx,
-                          )
+                          Ok(Rows { headers: vec![], rows: vec![], rows_affected: Some(rows_affected) })
                         },
                         Ok(Either::Right(rows)) => {
                           log::info!("{:?} rows affected", rows.rows_affected);
-                          (QueryResultsWithMetadata { results: Ok(rows), statement_type }, tx)
+                          Ok(rows)
                         },
                         Err(e) => {
                           log::error!("{e:?}");
-                          (QueryResultsWithMetadata { results: Err(e), statement_type }, tx)
+                          Err(e)
+                        },
+                      };
+                      (
+                        QueryResultsWithMetadata {
+                          results,
+                          statement_type,
+                          affected_preview,
+                          failed_over_to: None,
+                          retried: false,
                         },
-                      }
+                        tx,
+                      )
                     })));
                     self.state.last_query_start = Some(chrono::Utc::now());
                     self.state.last_query_end = None;
                   },
                   Ok((ExecutionType::Confirm, statement_type)) => {
-                    self.popup = Some(Box::new(ConfirmQuery::<DB>::new(query_string.clone(), statement_type)));
+                    let mut popup = ConfirmQuery::<DB>::new(query_string.clone(), statement_type.clone());
+                    if let Some(table) = database::unfiltered_write_target(&statement_type) {
+                      popup = popup.row_count(self.count_rows(&pool, table));
+                    }
+                    self.popup = Some(Box::new(popup));
                     self.state.focus = Focus::PopUp;
                   },
                   Ok((ExecutionType::Normal, statement_type)) => {
-                    self.components.data.set_loading();
-                    let dialect = self.state.dialect.clone();
-                    self.state.query_task = Some(DbTask::Query(tokio::spawn(async move {
-                      let results = database::query(query_string.clone(), dialect.as_ref(), &pool).await;
-                      match &results {
-                        Ok(rows) => {
-                          log::info!("{:?} rows, {:?} affected", rows.rows.len(), rows.rows_affected);
-                        },
-                        Err(e) => {
-                          log::error!("{e:?}");
-                        },
-                      };
-
-                      QueryResultsWithMetadata { results, statement_type }
-                    })));
-                    self.state.last_query_start = Some(chrono::Utc::now());
-                    self.state.last_query_end = None;
+                    let original = query_string.clone();
+                    let limit = self.config.settings.auto_limit.filter(|limit| *limit > 0);
+                    let limited = limit.and_then(|limit| {
+                      database::limit_query(&query_string, &statement_type, self.state.dialect.as_ref(), limit)
+                    });
+                    let query_string = match limited {
+                      Some(limited) => {
+                        self.state.auto_limited = limit;
+                        limited
+                      },
+                      None => query_string,
+                    };
+                    // the estimate is for the query as it will run, but the original is what gets confirmed
+                    let estimate = self.config.settings.row_estimate_threshold.is_some()
+                      && !*confirmed
+                      && matches!(statement_type, Statement::Query(_));
+                    if estimate {
+                      self.components.data.set_loading();
+                      let task = self.estimate_rows(&pool, &query_string);
+                      self.state.query_task = Some(DbTask::Estimate(task, original, query_string, statement_type));
+                    } else {
+                      self.spawn_query(pool, query_string, statement_type);
+                    }
                   },
                   Err(e) => self.components.data.set_data_state(Some(Err(e)), None),
                 }
@@ -436,8 +1024,48 @@ where
               }
             }
           },
+          Action::CopyFromFile(query, path) => {
+            let copy = match (&self.pool, database::get_first_query(query.clone(), self.state.dialect.as_ref())) {
+              (Some(pool), Ok((_, statement_type))) => {
+                // sent without anything that follows it in the editor
+                DB::copy_in(pool, statement_type.to_string(), path.clone()).map(|copy| (copy, statement_type))
+              },
+              _ => None,
+            };
+            match copy {
+              Some((copy, statement_type)) => {
+                self.components.data.set_loading();
+                self.state.served_by = self.replica.as_ref().map(|_| self.connection_name.clone());
+                self.state.query_task = Some(DbTask::Query(tokio::spawn(async move {
+                  let results = copy.await.map(|rows_affected| {
+                    log::info!("{:?} rows copied", rows_affected);
+                    Rows { headers: vec![], rows: vec![], rows_affected: Some(rows_affected) }
+                  });
+                  QueryResultsWithMetadata {
+                    results,
+                    statement_type,
+                    affected_preview: None,
+                    failed_over_to: None,
+                    retried: false,
+                  }
+                })));
+                self.state.last_query_start = Some(chrono::Utc::now());
+                self.state.last_query_end = None;
+              },
+              None => {
+                self.popup =
+                  Some(Box::new(Message::<DB>::new(format!("COPY FROM STDIN isn't supported for {}.", DB::NAME))));
+                self.state.focus = Focus::PopUp;
+              },
+            }
+          },
           Action::AbortQuery => {
             match &self.state.query_task {
+              Some(DbTask::Estimate(task, ..)) => {
+                task.abort();
+                self.state.query_task = None;
+                self.components.data.set_cancelled();
+              },
               Some(DbTask::Query(task)) => {
                 task.abort();
                 self.state.query_task = None;
@@ -456,19 +1084,290 @@ where
           Action::ClearHistory => {
             self.clear_history();
           },
+          Action::TogglePinHistory(i) => {
+            history::toggle_pin(&mut self.state.history, *i);
+          },
+          Action::EditCell(row, column) => {
+            let key_columns = self.primary_key_columns(&row.table).await;
+            self.popup = Some(Box::new(EditCell::<DB>::new(row.clone(), *column, key_columns)));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::DeleteRow(row) => {
+            let key_columns = self.primary_key_columns(&row.table).await;
+            match database::delete_row_statement::<DB>(&row.table, &row.headers, &row.values, &key_columns) {
+              Some(statement) => action_tx.send(Action::Query(vec![statement], false))?,
+              None => {
+                self.popup = Some(Box::new(Message::<DB>::new(format!(
+                  "Can't delete this row: no primary key of {} was found in the results.",
+                  row.table
+                ))));
+                self.state.focus = Focus::PopUp;
+              },
+            }
+          },
+          Action::OpenFinder => {
+            let history = self.state.history.iter().map(|entry| FinderItem::History(entry.query_lines.clone()));
+            let tables =
+              self.components.menu.get_table_list().into_iter().map(|(schema, table)| FinderItem::Table(schema, table));
+            self.popup = Some(Box::new(FuzzyFinder::<DB>::new(history.chain(tables).collect())));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::OpenObjectSearch => {
+            self.popup = Some(Box::new(ObjectSearch::<DB>::new(String::new(), vec![])));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::SearchObjects(search) => {
+            let results = match &self.pool {
+              Some(pool) => {
+                database::query(DB::search_objects_query(search), self.state.dialect.as_ref(), pool)
+                  .await
+                  .map(|results| results.rows)
+                  .map_err(|e| e.to_string())
+              },
+              None => Err("not connected".to_owned()),
+            };
+            self.popup = match results {
+              Ok(rows) => Some(Box::new(ObjectSearch::<DB>::new(search.clone(), rows))),
+              Err(e) => Some(Box::new(Message::<DB>::new(format!("Could not search the database: {}", e)))),
+            };
+            self.state.focus = Focus::PopUp;
+          },
+          Action::OpenRegisters(registers) => {
+            self.popup = match registers.is_empty() {
+              true => {
+                Some(Box::new(Message::<DB>::new(
+                  "No registers have been used yet. Yank into one with \"a through \"z.".to_owned(),
+                )))
+              },
+              false => Some(Box::new(RegisterViewer::<DB>::new(registers.clone()))),
+            };
+            self.state.focus = Focus::PopUp;
+          },
+          Action::ConfirmPaste(text) => {
+            self.popup = Some(Box::new(ConfirmPaste::<DB>::new(text.clone())));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::ViewCell(cell) => {
+            self.popup = Some(Box::new(CellViewer::<DB>::new(cell)));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::ViewJson(cell) => {
+            match serde_json::from_str::<serde_json::Value>(cell) {
+              Ok(value) if value.is_object() || value.is_array() => {
+                self.popup = Some(Box::new(JsonViewer::<DB>::new(value)));
+              },
+              _ => {
+                self.popup = match JsonViewer::<DB>::from_array_literal(cell) {
+                  Some(viewer) => Some(Box::new(viewer)),
+                  None => {
+                    Some(Box::new(Message::<DB>::new(
+                      "This field isn't a JSON object or array, an array, or a record.".to_owned(),
+                    )))
+                  },
+                }
+              },
+            }
+            self.state.focus = Focus::PopUp;
+          },
+          Action::ShowHistogram(column, values) => {
+            self.popup = match Histogram::<DB>::new(column.clone(), values) {
+              Some(histogram) => Some(Box::new(histogram)),
+              None => Some(Box::new(Message::<DB>::new(format!("{} has no numeric values to chart.", column)))),
+            };
+            self.state.focus = Focus::PopUp;
+          },
+          Action::OpenColumnPicker(headers, visible) => {
+            self.popup = Some(Box::new(ColumnPicker::<DB>::new(headers.clone(), visible.clone())));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::RecordSearch(search_box, search) => self.state.record_search(*search_box, search),
+          Action::OpenJumpToRow(row_count) => {
+            self.popup = Some(Box::new(JumpToRow::<DB>::new(*row_count)));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::OpenFilter(table) => {
+            self.popup = match self.table_columns(table).await {
+              Ok(columns) if !columns.is_empty() => Some(Box::new(FilterRows::<DB>::new(table.clone(), columns))),
+              Ok(_) => Some(Box::new(Message::<DB>::new(format!("No columns were found for {}.", table)))),
+              Err(e) => Some(Box::new(Message::<DB>::new(format!("Could not load the columns of {}: {}", table, e)))),
+            };
+            self.state.focus = Focus::PopUp;
+          },
+          Action::InsertFromClipboard => {
+            #[cfg(not(feature = "termux"))]
+            let text = clipboard.as_mut().ok().and_then(|clipboard| clipboard.get_text().ok()).unwrap_or_default();
+            #[cfg(feature = "termux")]
+            let text = String::new();
+            self.popup = if text.trim().is_empty() {
+              Some(Box::new(Message::<DB>::new(
+                "Copy some rows (tab-separated or csv) to the clipboard first.".to_owned(),
+              )))
+            } else {
+              Some(Box::new(InsertRows::<DB>::new(database::parse_tabular_text(&text))))
+            };
+            self.state.focus = Focus::PopUp;
+          },
+          Action::KillSession(session_id, cancel_only) => {
+            if let Some(statement) = DB::kill_session_statement(session_id, *cancel_only) {
+              self.popup = Some(Box::new(ConfirmKill::<DB>::new(session_id.clone(), statement, *cancel_only)));
+              self.state.focus = Focus::PopUp;
+            }
+          },
+          Action::ToggleSplit => self.split = self.split.toggle(),
+          Action::OpenAttachDatabase => {
+            self.popup = match DB::attach_database_statement("", "") {
+              Some(_) => Some(Box::new(AttachDatabase::<DB>::new())),
+              None => {
+                Some(Box::new(Message::<DB>::new("Attaching databases is only supported for SQLite.".to_owned())))
+              },
+            };
+            self.state.focus = Focus::PopUp;
+          },
+          Action::AttachDatabase(path) => {
+            let taken = self.attached.iter().map(|(_, alias)| alias.clone()).collect::<Vec<String>>();
+            self.attached.push((path.clone(), database::attach_alias(path, &taken)));
+            // attached databases only exist on the connection that attached them,
+            // so the pool is replaced with one that attaches them on every connection
+            match self.connect().await {
+              Ok(pool) => {
+                self.pool = Some(pool);
+                action_tx.send(Action::LoadMenu)?;
+                self.state.focus = Focus::Menu;
+              },
+              Err(e) => {
+                self.attached.pop();
+                self.popup = Some(Box::new(Message::<DB>::new(format!("Could not attach {}: {}", path, e))));
+                self.state.focus = Focus::PopUp;
+              },
+            }
+          },
+          Action::DetachDatabase(schema) => {
+            match self.attached.iter().position(|(_, alias)| alias == schema) {
+              Some(i) => {
+                let detached = self.attached.remove(i);
+                match self.connect().await {
+                  Ok(pool) => {
+                    self.pool = Some(pool);
+                    action_tx.send(Action::LoadMenu)?;
+                  },
+                  Err(e) => {
+                    self.attached.insert(i, detached);
+                    self.popup = Some(Box::new(Message::<DB>::new(format!("Could not detach {}: {}", schema, e))));
+                    self.state.focus = Focus::PopUp;
+                  },
+                }
+              },
+              None if DB::attach_database_statement("", "").is_some() => {
+                self.popup = Some(Box::new(Message::<DB>::new("Only attached databases can be detached.".to_owned())));
+                self.state.focus = Focus::PopUp;
+              },
+              None => {},
+            }
+          },
+          Action::OpenReconnect => {
+            self.popup = Some(Box::new(Reconnect::<DB>::new(DB::connection_params(&self.state.connection_opts))));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::Reconnect(params) => {
+            // the running query or open transaction belongs to the old pool
+            let opts = match (&self.state.query_task, DB::with_connection_params(&self.state.connection_opts, params)) {
+              (Some(_), _) => Err("Finish or abort the running query before reconnecting.".to_owned()),
+              (None, opts) => opts,
+            };
+            match opts {
+              Ok(opts) => {
+                let previous = std::mem::replace(&mut self.state.connection_opts, opts);
+                match self.connect().await {
+                  Ok(pool) => {
+                    self.pool = Some(pool);
+                    self.connection_name = DB::describe_connection(&self.state.connection_opts);
+                    self.replica = self.connect_replica().await;
+                    self.refresh_current_schema().await;
+                    action_tx.send(Action::LoadMenu)?;
+                    self.state.focus = Focus::Menu;
+                  },
+                  Err(e) => {
+                    self.state.connection_opts = previous;
+                    let hint = database::classify_connection_error(&e).hint();
+                    self.popup = Some(Box::new(Message::<DB>::new(format!("Could not reconnect: {} ({})", hint, e))));
+                    self.state.focus = Focus::PopUp;
+                  },
+                }
+              },
+              Err(message) => {
+                self.popup = Some(Box::new(Message::<DB>::new(message)));
+                self.state.focus = Focus::PopUp;
+              },
+            }
+          },
+          Action::DryRun(query_lines) => {
+            let text = database::dry_run(
+              query_lines.join(" \n"),
+              self.state.dialect.as_ref(),
+              self.config.settings.statement_policy.as_ref(),
+              self.config.settings.auto_limit.filter(|limit| *limit > 0),
+            )
+            .unwrap_or_else(|e| format!("the query can't be run: {}", e));
+            self.popup = Some(Box::new(Message::<DB>::new(text)));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::ExportSchema(schema) => {
+            let message = self.export_schema(schema).unwrap_or_else(|e| format!("Export failed: {}", e));
+            self.popup = Some(Box::new(Message::<DB>::new(message)));
+            self.state.focus = Focus::PopUp;
+          },
+          Action::ShowDiagnostics => {
+            if let Some(pool) = &self.pool {
+              let text = database::pool_diagnostics(pool, &self.state.pool_waits.lock().unwrap());
+              log::info!("{}", text);
+              self.popup = Some(Box::new(Message::<DB>::new(text)));
+              self.state.focus = Focus::PopUp;
+            }
+          },
+          Action::EditInExternalEditor(lines) => {
+            tui.exit()?;
+            let edited = utils::edit_in_external_editor(&lines.join("\n"));
+            tui.enter()?;
+            tui.clear()?;
+            match edited {
+              Ok(text) => action_tx.send(Action::HistoryToEditor(text.lines().map(|l| l.to_owned()).collect()))?,
+              Err(e) => log::error!("{}", e),
+            }
+          },
+          Action::PipeToPager(text) => {
+            tui.exit()?;
+            let piped = utils::pipe_to_pager(text, self.config.settings.pager.as_deref());
+            tui.enter()?;
+            tui.clear()?;
+            if let Err(e) = piped {
+              log::error!("{}", e);
+              self.popup = Some(Box::new(Message::<DB>::new(format!("Could not open the pager: {}", e))));
+              self.state.focus = Focus::PopUp;
+            }
+          },
           Action::CopyData(data) => {
+            let backend = self.config.settings.clipboard.unwrap_or_default();
             #[cfg(not(feature = "termux"))]
-            {
-              clipboard.as_mut().map_or_else(
+            let copied = backend != ClipboardBackend::Osc52
+              && clipboard.as_mut().map_or_else(
                 |e| {
                   log::error!("{e:?}");
+                  false
                 },
                 |clipboard| {
-                  clipboard.set_text(data).unwrap_or_else(|e| {
-                    log::error!("{e:?}");
-                  })
+                  clipboard.set_text(data).map_or_else(
+                    |e| {
+                      log::error!("{e:?}");
+                      false
+                    },
+                    |_| true,
+                  )
                 },
               );
+            #[cfg(feature = "termux")]
+            let copied = false;
+            if !copied && backend != ClipboardBackend::System {
+              utils::copy_with_osc52(data).unwrap_or_else(|e| log::error!("{e:?}"));
             }
           },
           _ => {},
@@ -487,6 +1386,9 @@ where
             action_tx.send(action)?;
           }
         }
+        if let (Some(focus), Some(_)) = (opened_from, &self.popup) {
+          self.popup_opener = focus;
+        }
       }
       if self.last_frame_mouse_event.is_some() {
         tui.draw(|f| {
@@ -499,6 +1401,9 @@ where
             DbTask::Query(task) => {
               task.abort();
             },
+            DbTask::Estimate(task, ..) => {
+              task.abort();
+            },
             DbTask::TxStart(task) => {
               task.abort();
             },
@@ -517,21 +1422,44 @@ where
   }
 
   fn draw_layout(&mut self, f: &mut Frame) {
+    if f.area().width < MIN_WIDTH || f.area().height < MIN_HEIGHT {
+      let message = format!(
+        "terminal too small ({}x{}), need at least {}x{}",
+        f.area().width,
+        f.area().height,
+        MIN_WIDTH,
+        MIN_HEIGHT
+      );
+      let area =
+        center(f.area(), Constraint::Fill(1), Constraint::Length(message.len() as u16 / f.area().width.max(1) + 1));
+      f.render_widget(Paragraph::new(message).centered().wrap(Wrap { trim: true }).yellow(), area);
+      return;
+    }
     let hints_layout = Layout::default()
       .direction(Direction::Vertical)
       .constraints(match f.area().width {
-        x if x < 135 => [Constraint::Fill(1), Constraint::Length(2)],
-        _ => [Constraint::Fill(1), Constraint::Length(1)],
+        x if x < 135 => [Constraint::Fill(1), Constraint::Length(1), Constraint::Length(2)],
+        _ => [Constraint::Fill(1), Constraint::Length(1), Constraint::Length(1)],
       })
       .split(f.area());
     let root_layout = Layout::default()
       .direction(Direction::Horizontal)
       .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
       .split(hints_layout[0]);
-    let right_layout = Layout::default()
-      .direction(Direction::Vertical)
-      .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
-      .split(root_layout[1]);
+    let right_layout = match self.split {
+      Split::Vertical => {
+        Layout::default()
+          .direction(Direction::Vertical)
+          .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+          .split(root_layout[1])
+      },
+      Split::Horizontal => {
+        Layout::default()
+          .direction(Direction::Horizontal)
+          .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+          .split(root_layout[1])
+      },
+    };
     let tabs_layout = Layout::default()
       .direction(Direction::Vertical)
       .constraints([Constraint::Length(1), Constraint::Fill(1)])
@@ -579,40 +1507,88 @@ where
 
     let tabs = Tabs::new(vec![" 󰤏 query <alt+2>", "   history <alt+4>"])
       .highlight_style(
-        Style::new()
-          .fg(if self.state.focus == Focus::Editor || self.state.focus == Focus::History {
-            Color::Green
-          } else {
-            Color::default()
-          })
-          .reversed(),
+        if self.state.focus == Focus::Editor || self.state.focus == Focus::History {
+          self.config.style(self.state.focus, "focused")
+        } else {
+          Style::default()
+        }
+        .reversed(),
       )
       .select(if self.last_focused_tab == Focus::Editor { 0 } else { 1 })
       .padding(" ", "")
       .divider(" ");
 
     let state = &self.state;
+    // never hand a component more than the frame, however the layout split it
+    let frame_area = f.area();
+    let clamp = |area: Rect| area.intersection(frame_area);
 
     f.render_widget(tabs, tabs_layout[0]);
     f.render_widget(Clear, tabs_layout[1]);
     if self.last_focused_tab == Focus::Editor {
-      self.components.editor.draw(f, tabs_layout[1], state).unwrap();
+      self.components.editor.draw(f, clamp(tabs_layout[1]), state).unwrap();
     } else {
-      self.components.history.draw(f, tabs_layout[1], state).unwrap();
+      self.components.history.draw(f, clamp(tabs_layout[1]), state).unwrap();
     }
-    self.components.menu.draw(f, root_layout[0], state).unwrap();
-    self.components.data.draw(f, right_layout[1], state).unwrap();
-    self.render_hints(f, hints_layout[1]);
+    self.components.menu.draw(f, clamp(root_layout[0]), state).unwrap();
+    self.components.data.draw(f, clamp(right_layout[1]), state).unwrap();
+    self.render_status(f, hints_layout[1]);
+    self.render_hints(f, hints_layout[2]);
 
     if let Some(popup) = &self.popup {
       self.render_popup(f, popup.as_ref());
     }
   }
 
+  fn render_status(&self, frame: &mut Frame, area: Rect) {
+    // the bars aren't panes, so they follow the focused pane's styles
+    let style = |key: &str| self.config.style(self.state.focus, key);
+    let separator = Span::styled(" │ ", Style::default().dim());
+    let mut left = vec![
+      Span::styled(format!(" {}", DB::NAME), style("status")),
+      separator.clone(),
+      Span::raw(self.connection_name.clone()),
+    ];
+    if let Some(schema) = self.state.current_schema.as_ref().filter(|schema| !schema.is_empty()) {
+      left.extend([separator.clone(), Span::raw(format!("schema {}", schema))]);
+    }
+    if !self.last_tick_key_events.is_empty() {
+      let pending =
+        self.last_tick_key_events.iter().map(|key| format!("<{}>", key_event_to_string(key))).collect::<String>();
+      left.extend([separator.clone(), Span::styled(format!("{} …", pending), style("busy"))]);
+    }
+    let elapsed = self.state.last_query_start.map(|start| {
+      let end = self.state.last_query_end.unwrap_or_else(chrono::Utc::now);
+      end.signed_duration_since(start).num_milliseconds() as f64 / 1000_f64
+    });
+    let right = match (&self.state.query_task, elapsed) {
+      (Some(DbTask::Query(_)) | Some(DbTask::TxStart(_)), Some(elapsed)) => {
+        Span::styled(format!("running {:.1}s ", elapsed), style("busy"))
+      },
+      (Some(DbTask::Estimate(..)), _) => Span::styled("estimating rows ", style("busy")),
+      (Some(DbTask::TxPending(..)), _) => Span::styled("awaiting commit ", style("busy")),
+      (None, Some(elapsed)) => {
+        let rows = match self.state.last_result_rows {
+          Some(1) => "1 row in ".to_owned(),
+          Some(n) => format!("{} rows in ", n),
+          None => "".to_owned(),
+        };
+        Span::raw(format!("{}{:.3}s ", rows, elapsed))
+      },
+      _ => Span::raw(""),
+    };
+    let layout = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Fill(1), Constraint::Length((right.width() as u16).min(area.width))])
+      .split(area);
+    frame.render_widget(Paragraph::new(Line::from(left)), layout[0]);
+    frame.render_widget(Paragraph::new(Line::from(right)), layout[1]);
+  }
+
   fn render_hints(&self, frame: &mut Frame, area: Rect) {
-    let block = Block::default().style(Style::default().fg(Color::Blue));
+    let block = Block::default().style(self.config.style(self.state.focus, "hints"));
     let help_text = format!(
-        "{}{}",
+        "{}{}{}",
         match self.state.query_task {
             None => "",
             _ if self.state.focus == Focus::Editor => "[<alt + q>] abort ",
@@ -620,12 +1596,16 @@ where
             _ => ""
         },
         match self.state.focus {
-            Focus::Menu  => "[R] refresh [j|↓] down [k|↑] up [l|<enter>] table list [h|󰁮 ] schema list [/] search [g] top [G] bottom",
-            Focus::Editor if self.state.query_task.is_none() => "[<alt + enter>|<f5>] execute query",
-            Focus::History => "[j|↓] down [k|↑] up [y] copy query [I] edit query [D] clear history",
-            Focus::Data if self.state.query_task.is_none() => "[j|↓] next row [k|↑] prev row [w|e] next col [b] prev col [v] select field [V] select row [y] copy [g] top [G] bottom [0] first col [$] last col",
+            Focus::Menu  => "[R] refresh [j|↓] down [k|↑] up [l|<enter>] table list [h|󰁮 ] schema list [/] search [g] top [G] bottom [t] object type [S] server settings [A] activity [E] export erd",
+            Focus::Editor if self.state.query_task.is_none() => "[<alt + enter>|<f5>] execute query [<alt + e>] open in $EDITOR [<alt + i>] insert from clipboard [<alt + f>] format [<alt + x>] explain",
+            Focus::History => "[j|↓] down [k|↑] up [y] copy query [I] edit query [p] pin query [D] clear history",
+            Focus::Data if self.state.query_task.is_none() => "[j|↓] next row [k|↑] prev row [w|e] next col [b] prev col [v] select field [<enter>] view field [V] select row [y] copy [I] copy as inserts [E] edit field [J] inspect field [H] histogram [S] stats [|] pager [D] delete row [P] pin explain [X|K] cancel|kill session [g] top [G] bottom [0] first col [$] last col",
             Focus::PopUp => "[<esc>] cancel",
             _ => "",
+        },
+        match self.state.focus {
+            Focus::Menu if DB::attach_database_statement("", "").is_some() => " [a] attach db [d] detach db",
+            _ => "",
         }
     );
     let paragraph = Paragraph::new(Line::from(help_text).centered()).block(block).wrap(Wrap { trim: true });
@@ -633,18 +1613,43 @@ where
   }
 
   fn render_popup(&self, frame: &mut Frame, popup: &dyn PopUp<DB>) {
+    if let Some(body) = popup.get_body(&self.state) {
+      let area = center(frame.area(), Constraint::Percentage(80), Constraint::Percentage(80));
+      let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(self.config.style(Focus::PopUp, "accent"))
+        .title(Line::from(body.title).centered())
+        .padding(Padding::horizontal(1));
+      let layout = Layout::default()
+        .constraints([Constraint::Fill(1), Constraint::Length(1)])
+        .direction(Direction::Vertical)
+        .split(block.inner(area));
+      // keep the selected line on screen
+      let scroll = body.selected.saturating_sub(layout[0].height.saturating_sub(1) as usize) as u16;
+      frame.render_widget(Clear, area);
+      frame.render_widget(block, area);
+      frame.render_widget(Paragraph::new(body.lines).scroll((scroll, 0)), layout[0]);
+      frame.render_widget(Paragraph::new(Line::from(popup.get_actions_text(&self.state)).centered()), layout[1]);
+      return;
+    }
     let area = center(frame.area(), Constraint::Percentage(50), Constraint::Percentage(50));
     let block = Block::default()
       .borders(Borders::ALL)
-      .border_style(Style::default().fg(Color::Yellow))
-      .title(Line::from(" Confirm Action ").centered())
+      .border_style(if popup.is_warning() {
+        Style::default().fg(Color::Red)
+      } else {
+        self.config.style(Focus::PopUp, "accent")
+      })
+      .title(Line::from(if popup.is_warning() { " Warning " } else { " Confirm Action " }).centered())
       .padding(Padding::uniform(1));
     let layout = Layout::default()
       .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
       .direction(Direction::Vertical)
       .split(block.inner(area));
 
-    let popup_cta = Paragraph::new(Line::from(popup.get_cta_text(&self.state)).centered()).wrap(Wrap { trim: false });
+    let cta_style = if popup.is_warning() { Style::default().fg(Color::Red).bold() } else { Style::default() };
+    let popup_cta = Paragraph::new(Line::from(popup.get_cta_text(&self.state)).style(cta_style).centered())
+      .wrap(Wrap { trim: false });
     let popup_actions = Paragraph::new(Line::from(popup.get_actions_text(&self.state)).centered());
     frame.render_widget(Clear, area);
     frame.render_widget(block, area);
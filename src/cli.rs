@@ -1,10 +1,10 @@
 use std::{
-  io::{self, Write},
+  io::{self, IsTerminal, Write},
   path::PathBuf,
   str::FromStr,
 };
 
-use clap::Parser;
+use clap::{Args, Parser};
 use color_eyre::eyre::{self, Result};
 
 use crate::utils::version;
@@ -34,9 +34,22 @@ pub struct Cli {
   #[arg(long = "password", value_name = "PASSWORD", help = "Password for database connection")]
   pub password: Option<String>,
 
-  #[arg(long = "host", value_name = "HOST", help = "Host for database connection (ex. localhost)")]
+  #[arg(
+    long = "host",
+    value_name = "HOST",
+    help = "Host for database connection (ex. localhost). Accepts IPv6 literals and comma-separated host lists"
+  )]
   pub host: Option<String>,
 
+  #[arg(
+    long = "socket",
+    value_name = "SOCKET",
+    conflicts_with_all = ["connection_url", "host"],
+    help = "Unix socket for database connection, used instead of host. For postgres, the directory containing the \
+            socket (ex. /var/run/postgresql); for mysql, the socket file (ex. /var/run/mysqld/mysqld.sock)"
+  )]
+  pub socket: Option<String>,
+
   #[arg(long = "port", value_name = "PORT", help = "Port for database connection (ex. 5432)")]
   pub port: Option<u16>,
 
@@ -45,6 +58,55 @@ pub struct Cli {
 
   #[arg(long = "driver", value_name = "DRIVER", help = "Driver for database connection (ex. postgres)")]
   pub driver: Option<Driver>,
+
+  #[arg(
+    short = 'f',
+    long = "file",
+    value_name = "FILE",
+    help = "Start the editor with the query in FILE, or - for stdin. Piped stdin is read without this option"
+  )]
+  pub file: Option<String>,
+
+  #[arg(long = "run", help = "Run the query from --file or stdin once connected")]
+  pub run: bool,
+
+  #[command(flatten)]
+  pub ssl: SslOptions,
+}
+
+/// TLS options for postgres and mysql connections. They are applied on top of
+/// any given in the connection URL.
+#[derive(Args, Debug, Clone, Default)]
+pub struct SslOptions {
+  #[arg(
+    long = "ssl-mode",
+    value_name = "SSL_MODE",
+    help = "SSL mode for the connection (ex. require, verify-ca, verify-full). MySQL's names (ex. verify_identity) \
+            work too"
+  )]
+  pub mode: Option<String>,
+
+  #[arg(long = "ssl-ca", value_name = "SSL_CA", help = "Root certificate file used to verify the server")]
+  pub ca: Option<String>,
+
+  #[arg(long = "ssl-cert", value_name = "SSL_CERT", help = "Client certificate file")]
+  pub cert: Option<String>,
+
+  #[arg(long = "ssl-key", value_name = "SSL_KEY", help = "Client private key file")]
+  pub key: Option<String>,
+}
+
+impl SslOptions {
+  // these options, with the ssl setting filling in the ones that weren't given
+  pub fn or_settings(&self, settings: Option<&crate::config::SslSettings>) -> SslOptions {
+    let settings = settings.cloned().unwrap_or_default();
+    SslOptions {
+      mode: self.mode.clone().or(settings.mode),
+      ca: self.ca.clone().or(settings.ca),
+      cert: self.cert.clone().or(settings.cert),
+      key: self.key.clone().or(settings.key),
+    }
+  }
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -76,6 +138,197 @@ pub fn extract_driver_from_url(url: &str) -> Result<Driver> {
   }
 }
 
+/// A single entry of a host list, e.g. `localhost:5432` or `[::1]:5432`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostSpec {
+  pub host: String,
+  pub port: Option<u16>,
+}
+
+/// Parses a comma-separated list of hosts, each with an optional port.
+/// IPv6 literals may be bracketed (`[::1]:5432`) or bare (`::1`), in which
+/// case no port can be given.
+pub fn parse_host_list(hosts: &str) -> Result<Vec<HostSpec>> {
+  hosts.split(',').map(parse_host).collect()
+}
+
+fn parse_host(entry: &str) -> Result<HostSpec> {
+  let entry = entry.trim();
+  if entry.is_empty() {
+    return Err(eyre::Report::msg("Empty host in host list"));
+  }
+  let (host, port) = if let Some(rest) = entry.strip_prefix('[') {
+    let (host, rest) =
+      rest.split_once(']').ok_or_else(|| eyre::Report::msg(format!("Unclosed bracket in host {}", entry)))?;
+    match rest {
+      "" => (host, None),
+      _ => {
+        match rest.strip_prefix(':') {
+          Some(port) => (host, Some(port)),
+          None => return Err(eyre::Report::msg(format!("Unexpected characters after host {}", entry))),
+        }
+      },
+    }
+  } else if entry.matches(':').count() > 1 {
+    (entry, None)
+  } else {
+    match entry.split_once(':') {
+      Some((host, port)) => (host, Some(port)),
+      None => (entry, None),
+    }
+  };
+  if host.is_empty() {
+    return Err(eyre::Report::msg(format!("Empty host in {}", entry)));
+  }
+  let port = match port {
+    Some(port) => Some(port.parse().map_err(|_| eyre::Report::msg(format!("Invalid port in host {}", entry)))?),
+    None => None,
+  };
+  Ok(HostSpec { host: host.to_owned(), port })
+}
+
+/// Strips the brackets url parsing leaves around IPv6 hosts.
+pub fn unbracket_host(host: &str) -> &str {
+  host.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(host)
+}
+
+/// Expands a multi-host connection URL (`postgres://user@host1:5432,host2:5432/db`)
+/// into one URL per host, in the order they should be tried. URLs with a single
+/// host are returned unchanged.
+pub fn expand_host_list(url: &str) -> Result<Vec<String>> {
+  let url = url.trim();
+  let scheme_end = url.find("://").ok_or_else(|| eyre::Report::msg("Invalid connection URL format"))? + 3;
+  let authority_end = url[scheme_end..].find(['/', '?']).map(|i| scheme_end + i).unwrap_or(url.len());
+  let authority = &url[scheme_end..authority_end];
+  let (userinfo, hosts) = match authority.rfind('@') {
+    Some(i) => (&authority[..=i], &authority[i + 1..]),
+    None => ("", authority),
+  };
+  if !hosts.contains(',') {
+    return Ok(vec![url.to_owned()]);
+  }
+  Ok(
+    parse_host_list(hosts)?
+      .into_iter()
+      .map(|spec| {
+        let host = if spec.host.contains(':') { format!("[{}]", spec.host) } else { spec.host };
+        let port = spec.port.map(|p| format!(":{}", p)).unwrap_or_default();
+        format!("{}{}{}{}{}", &url[..scheme_end], userinfo, host, port, &url[authority_end..])
+      })
+      .collect(),
+  )
+}
+
+/// Returns the value of a query parameter from a connection URL.
+pub fn url_query_param(url: &str, key: &str) -> Option<String> {
+  let (_, query) = url.split_once('?')?;
+  query.split('&').find_map(|pair| {
+    match pair.split_once('=') {
+      Some((k, v)) if k == key => Some(v.to_owned()),
+      _ => None,
+    }
+  })
+}
+
+/// Reads the query to start the editor with, from `--file` or piped stdin.
+/// This has to happen before any prompts, since they read from stdin too.
+pub fn read_initial_query(args: &Cli) -> Result<Option<String>> {
+  let file = args.file.as_deref();
+  let piped = match file {
+    Some("-") => true,
+    Some(_) => false,
+    None => !io::stdin().is_terminal(),
+  };
+  check_piped_stdin(args, piped)?;
+  let query = match file {
+    Some("-") => io::read_to_string(io::stdin())?,
+    Some(path) => {
+      std::fs::read_to_string(path).map_err(|e| eyre::Report::msg(format!("Could not read {}: {}", path, e)))?
+    },
+    None if !io::stdin().is_terminal() => io::read_to_string(io::stdin())?,
+    None => return Ok(None),
+  };
+  Ok(Some(query).filter(|q| !q.trim().is_empty()))
+}
+
+/// A query piped to stdin leaves nothing there to answer prompts with, so the
+/// connection has to be given in full on the command line.
+pub fn check_piped_stdin(args: &Cli, piped: bool) -> Result<()> {
+  if piped && needs_prompt(args) {
+    return Err(eyre::Report::msg(
+      "stdin is used for the query, so it can't answer prompts. Pass --url, or --driver with the rest of the \
+       connection options",
+    ));
+  }
+  Ok(())
+}
+
+// whether connecting would prompt on stdin for something. the password prompt reads
+// from the terminal itself, so it doesn't count
+fn needs_prompt(args: &Cli) -> bool {
+  if args.connection_url.is_some() {
+    return false;
+  }
+  match args.driver {
+    None => true,
+    Some(Driver::Sqlite) => args.database.is_none(),
+    Some(_) => {
+      args.user.is_none()
+        || args.database.is_none()
+        || (args.socket.is_none() && (args.host.is_none() || args.port.is_none()))
+    },
+  }
+}
+
+/// Why rainfrog couldn't start, attached to the error with `wrap_err` so that
+/// the process can exit with a code scripts can branch on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupFailure {
+  /// The server couldn't be reached, or refused the connection for a reason
+  /// other than the credentials.
+  Connection,
+  Authentication,
+  /// Invalid options, connection URL, or config file.
+  Config,
+}
+
+impl StartupFailure {
+  pub fn name(self) -> &'static str {
+    match self {
+      StartupFailure::Connection => "connection",
+      StartupFailure::Authentication => "authentication",
+      StartupFailure::Config => "config",
+    }
+  }
+
+  /// Any other error exits with 1.
+  pub fn exit_code(self) -> i32 {
+    match self {
+      StartupFailure::Connection => 2,
+      StartupFailure::Authentication => 3,
+      StartupFailure::Config => 4,
+    }
+  }
+}
+
+impl std::fmt::Display for StartupFailure {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} error", self.name())
+  }
+}
+
+/// The exit code for a startup failure, and a one-line JSON summary of it for stderr.
+/// None for any other error.
+pub fn error_summary(error: &eyre::Report) -> Option<(i32, String)> {
+  let failure = error.downcast_ref::<StartupFailure>()?;
+  let summary = serde_json::json!({
+    "error": failure.name(),
+    "exit_code": failure.exit_code(),
+    "message": error.root_cause().to_string(),
+  });
+  Some((failure.exit_code(), summary.to_string()))
+}
+
 pub fn prompt_for_driver() -> Result<Driver> {
   let mut driver = String::new();
   print!("Database driver (postgres, mysql, sqlite): ");
@@ -83,3 +336,137 @@ pub fn prompt_for_driver() -> Result<Driver> {
   io::stdin().read_line(&mut driver)?;
   driver.trim().to_lowercase().parse()
 }
+
+// None when left empty, to enter a host and port instead
+pub fn prompt_for_socket() -> Result<Option<String>> {
+  let mut socket = String::new();
+  print!("socket (leave empty to enter a host instead): ");
+  io::stdout().flush()?;
+  io::stdin().read_line(&mut socket)?;
+  Ok(Some(socket.trim().to_owned()).filter(|socket| !socket.is_empty()))
+}
+
+pub fn prompt_for_retry() -> Result<bool> {
+  let mut answer = String::new();
+  print!("Re-enter connection options? [y/N]: ");
+  io::stdout().flush()?;
+  io::stdin().read_line(&mut answer)?;
+  Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_error_summary() {
+    let error = eyre::Report::msg("password authentication failed").wrap_err(StartupFailure::Authentication);
+    assert_eq!(
+      error_summary(&error),
+      Some((3, r#"{"error":"authentication","exit_code":3,"message":"password authentication failed"}"#.to_owned()))
+    );
+    assert_eq!(error_summary(&eyre::Report::msg("panicked")), None);
+  }
+
+  #[test]
+  fn test_ssl_or_settings() {
+    let given = SslOptions { mode: Some("require".to_owned()), ..Default::default() };
+    let settings = crate::config::SslSettings {
+      mode: Some("disable".to_owned()),
+      ca: Some("ca.pem".to_owned()),
+      ..Default::default()
+    };
+    let ssl = given.or_settings(Some(&settings));
+    assert_eq!(ssl.mode.as_deref(), Some("require"));
+    assert_eq!(ssl.ca.as_deref(), Some("ca.pem"));
+    assert_eq!(ssl.cert, None);
+    assert_eq!(given.or_settings(None).mode.as_deref(), Some("require"));
+  }
+
+  #[test]
+  fn test_socket_conflicts() {
+    assert!(Cli::try_parse_from(["rainfrog", "--socket", "/var/run/postgresql"]).is_ok());
+    assert!(Cli::try_parse_from(["rainfrog", "--url", "postgres://localhost", "--socket", "/tmp"]).is_err());
+    assert!(Cli::try_parse_from(["rainfrog", "--host", "localhost", "--socket", "/tmp"]).is_err());
+  }
+
+  #[test]
+  fn test_parse_host_list() {
+    let spec = |host: &str, port: Option<u16>| HostSpec { host: host.to_owned(), port };
+    assert_eq!(parse_host_list("localhost").unwrap(), vec![spec("localhost", None)]);
+    assert_eq!(parse_host_list("localhost:5432").unwrap(), vec![spec("localhost", Some(5432))]);
+    assert_eq!(parse_host_list("::1").unwrap(), vec![spec("::1", None)]);
+    assert_eq!(parse_host_list("[::1]").unwrap(), vec![spec("::1", None)]);
+    assert_eq!(parse_host_list("[fe80::1%eth0]:5433").unwrap(), vec![spec("fe80::1%eth0", Some(5433))]);
+    assert_eq!(parse_host_list("host1:5432, [2001:db8::1]:5433,host3").unwrap(), vec![
+      spec("host1", Some(5432)),
+      spec("2001:db8::1", Some(5433)),
+      spec("host3", None)
+    ]);
+    assert!(parse_host_list("").is_err());
+    assert!(parse_host_list("host1,,host2").is_err());
+    assert!(parse_host_list("[::1").is_err());
+    assert!(parse_host_list("[::1]5432").is_err());
+    assert!(parse_host_list("host:port").is_err());
+    assert!(parse_host_list(":5432").is_err());
+  }
+
+  #[test]
+  fn test_expand_host_list() {
+    assert_eq!(expand_host_list("postgres://user:pw@[::1]:5432/db").unwrap(), vec!["postgres://user:pw@[::1]:5432/db"]);
+    assert_eq!(
+      expand_host_list("postgres://user:p@ss@host1:5432,[::1]:5433,host3/db?target_session_attrs=read-write").unwrap(),
+      vec![
+        "postgres://user:p@ss@host1:5432/db?target_session_attrs=read-write",
+        "postgres://user:p@ss@[::1]:5433/db?target_session_attrs=read-write",
+        "postgres://user:p@ss@host3/db?target_session_attrs=read-write",
+      ]
+    );
+    assert_eq!(expand_host_list("mysql://host1,host2").unwrap(), vec!["mysql://host1", "mysql://host2"]);
+    assert!(expand_host_list("postgres://host1,/db").is_err());
+    assert!(expand_host_list("localhost:5432").is_err());
+  }
+
+  #[test]
+  fn test_unbracket_host() {
+    assert_eq!(unbracket_host("[::1]"), "::1");
+    assert_eq!(unbracket_host("::1"), "::1");
+    assert_eq!(unbracket_host("localhost"), "localhost");
+  }
+
+  #[test]
+  fn test_url_query_param() {
+    let url = "postgres://h1,h2/db?sslmode=require&target_session_attrs=primary";
+    assert_eq!(url_query_param(url, "target_session_attrs"), Some("primary".to_owned()));
+    assert_eq!(url_query_param(url, "application_name"), None);
+    assert_eq!(url_query_param("postgres://h1/db", "sslmode"), None);
+  }
+
+  fn args(options: &[&str]) -> Cli {
+    Cli::try_parse_from(std::iter::once("rainfrog").chain(options.iter().copied())).unwrap()
+  }
+
+  #[test]
+  fn test_read_initial_query() {
+    let path = std::env::temp_dir().join(format!("rainfrog-initial-query-{}.sql", std::process::id()));
+    let file = args(&["--file", path.to_str().unwrap()]);
+    std::fs::write(&path, "select 1;\nselect 2;\n").unwrap();
+    assert_eq!(read_initial_query(&file).unwrap(), Some("select 1;\nselect 2;\n".to_owned()));
+    std::fs::write(&path, "  \n").unwrap();
+    assert_eq!(read_initial_query(&file).unwrap(), None);
+    std::fs::remove_file(&path).unwrap();
+    assert!(read_initial_query(&file).is_err());
+  }
+
+  #[test]
+  fn test_check_piped_stdin() {
+    // piped, but the driver (or the host and port) would still have to be prompted for
+    assert!(check_piped_stdin(&args(&[]), true).is_err());
+    assert!(check_piped_stdin(&args(&["--driver", "postgres", "--username", "me", "--database", "app"]), true).is_err());
+    assert!(check_piped_stdin(&args(&[]), false).is_ok());
+    assert!(check_piped_stdin(&args(&["--url", "postgres://localhost/app"]), true).is_ok());
+    assert!(check_piped_stdin(&args(&["--driver", "sqlite", "--database", "app.db"]), true).is_ok());
+    let socket = ["--driver", "postgres", "--username", "me", "--database", "app", "--socket", "/tmp"];
+    assert!(check_piped_stdin(&args(&socket), true).is_ok());
+  }
+}
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+  collections::{HashMap, HashSet},
+  sync::Arc,
+  time::Duration,
+};
 
 use color_eyre::eyre::Result;
 use crossterm::{
@@ -14,18 +18,23 @@ use tui_textarea::{Input, Key};
 
 use super::{scroll_table::SelectionMode, Frame};
 use crate::{
-  action::Action,
+  action::{Action, SelectedRow},
   app::{App, AppState},
   components::{
     scroll_table::{ScrollDirection, ScrollTable},
     Component,
   },
-  config::{Config, KeyBindings},
-  database::{get_headers, row_to_json, row_to_vec, statement_type_string, DbError, Rows},
+  config::{Config, KeyBindings, PagerFormat},
+  database::{
+    get_headers, get_source_table, in_list, is_numeric_type, is_permission_denied, referenced_rows_query, row_to_json,
+    row_to_vec, rows_to_csv, rows_to_insert_statements, rows_to_text, statement_type_string, DatabaseQueries, DbError,
+    Rows, ValueParser,
+  },
   focus::Focus,
   tui::Event,
 };
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Default)]
 pub enum DataState<'a> {
   #[default]
@@ -40,6 +49,15 @@ pub enum DataState<'a> {
   StatementCompleted(Statement),
 }
 
+// estimated row count above which a sequential scan gets flagged
+const LARGE_SCAN_ROWS: u64 = 10_000;
+
+// characters of a value shown in the table, one less than the column width
+const DEFAULT_MAX_CELL_LENGTH: u16 = 35;
+
+// rows formatted past either edge of the view, so scrolling a little doesn't format any again
+const ROW_BUFFER: usize = 50;
+
 #[derive(Clone, Debug)]
 pub struct ExplainOffsets {
   pub y_offset: u16,
@@ -50,6 +68,7 @@ pub trait SettableDataTable<'a> {
   fn set_data_state(&mut self, data: Option<Result<Rows, DbError>>, statement_type: Option<Statement>);
   fn set_loading(&mut self);
   fn set_cancelled(&mut self);
+  fn marked_row_count(&self) -> usize;
 }
 
 pub trait DataComponent<'a, DB: sqlx::Database>: Component<DB> + SettableDataTable<'a> {}
@@ -63,11 +82,17 @@ pub struct Data<'a> {
   config: Config,
   scrollable: ScrollTable<'a>,
   data_state: DataState<'a>,
+  source_table: Option<String>,
+  explain_source: Vec<String>,
+  pinned_explain: Option<Vec<String>>,
   explain_scroll: Option<ExplainOffsets>,
   explain_width: u16,
   explain_height: u16,
   explain_max_x_offset: u16,
   explain_max_y_offset: u16,
+  stats_footer: bool,
+  // whether the driver shows a column type in the display time zone
+  utc_type: Option<fn(&str) -> bool>,
 }
 
 impl Data<'_> {
@@ -77,12 +102,91 @@ impl Data<'_> {
       config: Config::default(),
       scrollable: ScrollTable::default(),
       data_state: DataState::Blank,
+      source_table: None,
+      explain_source: vec![],
+      pinned_explain: None,
       explain_scroll: None,
       explain_width: 0,
       explain_height: 0,
       explain_max_x_offset: 0,
       explain_max_y_offset: 0,
+      stats_footer: false,
+      utc_type: None,
+    }
+  }
+
+  // lays out the results for the scroll table, showing only its visible columns
+  fn build_table(&mut self) {
+    let DataState::HasResults(rows) = &self.data_state else {
+      return;
+    };
+    let columns = match self.scrollable.get_visible_columns() {
+      [] => (0..rows.headers.len()).collect::<Vec<usize>>(),
+      visible => visible.to_vec(),
+    };
+    let timezone = self.config.display_timezone();
+    // columns that were shown in the display time zone say which one in their header
+    let header = columns
+      .iter()
+      .filter_map(|i| rows.headers.get(*i))
+      .map(|h| {
+        match timezone.as_ref().filter(|_| self.utc_type.is_some_and(|is_utc| is_utc(&h.type_name))) {
+          Some(timezone) => Cell::from(format!("{}\n{} ({})", h.name, h.type_name, timezone.name())),
+          None => Cell::from(format!("{}\n{}", h.name, h.type_name)),
+        }
+      })
+      .collect::<Vec<Cell>>();
+    let separator = self.config.settings.thousands_separator.as_deref().unwrap_or_default();
+    let max_length = self.config.settings.max_cell_length.unwrap_or(DEFAULT_MAX_CELL_LENGTH).max(1);
+    let selected = self.config.style(Focus::Data, "selected");
+    let footer = self.stats_footer.then(|| {
+      columns
+        .iter()
+        .filter_map(|i| {
+          let stats = column_stats(&rows.rows, *i, is_numeric_type(&rows.headers.get(*i)?.type_name), separator);
+          Some(Cell::from(truncate_cell(&stats, max_length)).dim().italic())
+        })
+        .collect::<Vec<Cell>>()
+    });
+    self
+      .scrollable
+      .set_table(header, rows.rows.len(), max_length.saturating_add(1))
+      .row_numbers(self.config.settings.row_numbers.unwrap_or(false))
+      .footer(footer)
+      .styles(
+        selected.reversed().bold(),
+        selected.reversed().bold().italic(),
+        self.config.style(Focus::Data, "accent").bold(),
+      );
+  }
+
+  // formats the rows around the ones in view for the scroll table, unless it has them already
+  fn fill_rows(&mut self, area: Rect) {
+    let DataState::HasResults(rows) = &self.data_state else {
+      return;
+    };
+    let in_view = self.scrollable.rows_in_view(area);
+    if self.scrollable.has_rows(&in_view) {
+      return;
     }
+    let columns = match self.scrollable.get_visible_columns() {
+      [] => (0..rows.headers.len()).collect::<Vec<usize>>(),
+      visible => visible.to_vec(),
+    };
+    let separator = self.config.settings.thousands_separator.as_deref().unwrap_or_default();
+    let max_length = self.config.settings.max_cell_length.unwrap_or(DEFAULT_MAX_CELL_LENGTH).max(1);
+    let start = in_view.start.saturating_sub(ROW_BUFFER);
+    let end = std::cmp::min(in_view.end.saturating_add(ROW_BUFFER), rows.rows.len());
+    let value_rows = rows.rows[start..end]
+      .iter()
+      .map(|r| {
+        columns
+          .iter()
+          .filter_map(|i| Some(format_cell(r.get(*i)?, &rows.headers.get(*i)?.type_name, separator, max_length)))
+          .collect::<Vec<Cell>>()
+      })
+      .collect::<Vec<_>>();
+    self.scrollable.set_rows(start, value_rows);
   }
 
   pub fn scroll(&mut self, direction: ScrollDirection) {
@@ -181,6 +285,7 @@ impl<'a> SettableDataTable<'a> for Data<'a> {
     self.explain_max_y_offset = 0;
     self.explain_scroll = None;
     self.scrollable = ScrollTable::default();
+    self.source_table = statement_type.as_ref().and_then(get_source_table);
     match data {
       Some(Ok(rows)) => {
         if rows.rows.is_empty() && rows.rows_affected.is_some_and(|n| n > 0) {
@@ -193,25 +298,22 @@ impl<'a> SettableDataTable<'a> for Data<'a> {
         } else if rows.rows.is_empty() {
           self.data_state = DataState::NoResults;
         } else if matches!(statement_type, Some(Statement::Explain { .. })) {
-          self.explain_width = rows.rows.iter().fold(0_u16, |acc, r| acc.max(r.join(" ").len() as u16));
-          self.explain_height = rows.rows.len() as u16;
+          self.explain_source = rows.rows.iter().map(|r| r.join(" ")).collect();
+          let lines = match &self.pinned_explain {
+            Some(pinned) if pinned != &self.explain_source => {
+              let mut lines = vec![Line::from("-- compared with the pinned plan ([U] unpin)").dim()];
+              lines.extend(diff_plans(pinned, &self.explain_source).into_iter().map(plan_diff_line));
+              lines
+            },
+            _ => self.explain_source.iter().map(|l| explain_line(l.clone())).collect::<Vec<Line>>(),
+          };
+          self.explain_width = lines.iter().fold(0_u16, |acc, l| acc.max(l.width() as u16));
+          self.explain_height = lines.len() as u16;
           self.explain_scroll = Some(ExplainOffsets { y_offset: 0, x_offset: 0 });
-          self.data_state = DataState::Explain(Text::from_iter(rows.rows.iter().map(|r| r.join(" "))));
+          self.data_state = DataState::Explain(Text::from(lines));
         } else {
-          let header_row = Row::new(
-            rows.headers.iter().map(|h| Cell::from(format!("{}\n{}", h.name, h.type_name))).collect::<Vec<Cell>>(),
-          )
-          .height(2)
-          .bottom_margin(1);
-          let value_rows = rows.rows.iter().map(|r| Row::new(r.clone()).bottom_margin(1));
-          let buf_table = Table::default()
-            .rows(value_rows)
-            .header(header_row)
-            .style(Style::default())
-            .column_spacing(1)
-            .highlight_style(Style::default().fg(Color::LightBlue).reversed().bold());
-          self.scrollable.set_table(buf_table, rows.headers.len(), rows.rows.len(), 36_u16);
           self.data_state = DataState::HasResults(rows);
+          self.build_table();
         }
       },
       Some(Err(e)) => {
@@ -230,9 +332,13 @@ impl<'a> SettableDataTable<'a> for Data<'a> {
   fn set_cancelled(&mut self) {
     self.data_state = DataState::Cancelled;
   }
+
+  fn marked_row_count(&self) -> usize {
+    self.scrollable.get_marked_rows().len()
+  }
 }
 
-impl<DB: Database> Component<DB> for Data<'_> {
+impl<DB: Database + DatabaseQueries + ValueParser> Component<DB> for Data<'_> {
   fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
     self.command_tx = Some(tx);
     Ok(())
@@ -240,6 +346,7 @@ impl<DB: Database> Component<DB> for Data<'_> {
 
   fn register_config_handler(&mut self, config: Config) -> Result<()> {
     self.config = config;
+    self.utc_type = Some(DB::is_utc_type);
     Ok(())
   }
 
@@ -329,7 +436,14 @@ impl<DB: Database> Component<DB> for Data<'_> {
           None | Some(SelectionMode::Copied) => {
             self.scrollable.transition_selection_mode(Some(SelectionMode::Row));
           },
-          _ => {},
+          Some(SelectionMode::Cell) => {
+            if let DataState::HasResults(Rows { rows, .. }) = &self.data_state {
+              let (x, y) = self.scrollable.get_cell_offsets();
+              if let Some(cell) = rows.get(y).and_then(|row| row.get(x as usize)) {
+                self.command_tx.clone().unwrap().send(Action::ViewCell(cell.clone()))?;
+              }
+            }
+          },
         };
       },
       Input { key: Key::Backspace, .. } => {
@@ -360,16 +474,186 @@ impl<DB: Database> Component<DB> for Data<'_> {
             },
             _ => {},
           }
-        } else if let DataState::Explain(text) = &self.data_state {
-          self.command_tx.clone().unwrap().send(Action::CopyData(text.to_string()))?;
+        } else if let DataState::Explain(_) = &self.data_state {
+          self.command_tx.clone().unwrap().send(Action::CopyData(self.explain_source.join("\n")))?;
           self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
         } else if let DataState::Error(err) = &self.data_state {
           self.command_tx.clone().unwrap().send(Action::CopyData(err.to_string()))?;
           self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
         }
       },
+      // alt+C leaves out repeated values, e.g. to grab a list of ids
+      Input { key: Key::Char('C'), alt, .. } => {
+        if let (DataState::HasResults(Rows { rows, .. }), Some(SelectionMode::Cell)) =
+          (&self.data_state, self.scrollable.get_selection_mode())
+        {
+          let (x, _) = self.scrollable.get_cell_offsets();
+          self.command_tx.clone().unwrap().send(Action::CopyData(column_values(rows, x as usize, alt)))?;
+          self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
+        }
+      },
+      Input { key: Key::Char(' '), .. } => {
+        if let DataState::HasResults(_) = &self.data_state {
+          self.scrollable.toggle_marked_row().scroll(ScrollDirection::Down);
+        }
+      },
+      // the marked rows' values from the selected column, or the row under the
+      // cursor's value if none are marked. alt+i inserts it into the editor
+      Input { key: Key::Char('i'), alt, .. } => {
+        if let DataState::HasResults(Rows { rows, headers, .. }) = &self.data_state {
+          let (x, y) = self.scrollable.get_cell_offsets();
+          let marked = self.scrollable.get_marked_rows();
+          let values = rows
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| if marked.is_empty() { *i == y } else { marked.contains(i) })
+            .filter_map(|(_, row)| row.get(x as usize).map(|value| value.as_str()))
+            .collect::<Vec<&str>>();
+          if let Some(header) = headers.get(x as usize) {
+            let list = in_list::<DB>(&header.name, &values, &header.type_name);
+            if alt {
+              self.command_tx.clone().unwrap().send(Action::InsertIntoEditor(list))?;
+              self.command_tx.clone().unwrap().send(Action::FocusEditor)?;
+            } else {
+              self.command_tx.clone().unwrap().send(Action::CopyData(list))?;
+              self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
+            }
+          }
+        }
+      },
+      Input { key: Key::Char('I'), .. } => {
+        if let DataState::HasResults(Rows { rows, headers, .. }) = &self.data_state {
+          let (_, y) = self.scrollable.get_cell_offsets();
+          let rows = match self.scrollable.get_selection_mode() {
+            Some(SelectionMode::Row) => &rows[y..=y],
+            _ => &rows[..],
+          };
+          let table = self.source_table.as_deref().unwrap_or("table_name");
+          let statements = rows_to_insert_statements::<DB>(table, headers, rows);
+          self.command_tx.clone().unwrap().send(Action::CopyData(statements))?;
+          self.scrollable.transition_selection_mode(Some(SelectionMode::Copied));
+        }
+      },
+      Input { key: Key::Char('E'), .. } => {
+        if let (DataState::HasResults(Rows { rows, headers, .. }), Some(table), Some(SelectionMode::Cell)) =
+          (&self.data_state, &self.source_table, self.scrollable.get_selection_mode())
+        {
+          if app_state.query_task.is_none() {
+            let (x, y) = self.scrollable.get_cell_offsets();
+            let row = SelectedRow { table: table.clone(), headers: headers.clone(), values: rows[y].clone() };
+            self.command_tx.clone().unwrap().send(Action::EditCell(row, x as usize))?;
+          }
+        }
+      },
+      // follows a foreign key to the row it points to
+      Input { key: Key::Char('R'), .. } => {
+        if let (
+          DataState::HasResults(Rows { rows, headers, .. }),
+          Some(table),
+          Some(schema),
+          Some(SelectionMode::Cell),
+        ) = (&self.data_state, &self.source_table, &app_state.schema, self.scrollable.get_selection_mode())
+        {
+          let (x, y) = self.scrollable.get_cell_offsets();
+          let query = referenced_rows_query::<DB>(
+            schema,
+            app_state.dialect.as_ref(),
+            table,
+            &headers[x as usize],
+            &rows[y][x as usize],
+          );
+          if let (Some(query), None) = (query, &app_state.query_task) {
+            self.command_tx.clone().unwrap().send(Action::Query(vec![query], false))?;
+          }
+        }
+      },
+      Input { key: Key::Char('D'), .. } => {
+        if let (DataState::HasResults(Rows { rows, headers, .. }), Some(table), Some(SelectionMode::Row)) =
+          (&self.data_state, &self.source_table, self.scrollable.get_selection_mode())
+        {
+          if app_state.query_task.is_none() {
+            let (_, y) = self.scrollable.get_cell_offsets();
+            let row = SelectedRow { table: table.clone(), headers: headers.clone(), values: rows[y].clone() };
+            self.command_tx.clone().unwrap().send(Action::DeleteRow(row))?;
+          }
+        }
+      },
+      // the activity preview names its id column session_id
+      Input { key: Key::Char(c @ ('K' | 'X')), .. } => {
+        if let DataState::HasResults(Rows { rows, headers, .. }) = &self.data_state {
+          if headers.first().is_some_and(|h| h.name == "session_id") && app_state.query_task.is_none() {
+            let (_, y) = self.scrollable.get_cell_offsets();
+            if let Some(session_id) = rows.get(y).and_then(|r| r.first()) {
+              self.command_tx.clone().unwrap().send(Action::KillSession(session_id.clone(), c == 'X'))?;
+            }
+          }
+        }
+      },
+      Input { key: Key::Char('J'), .. } => {
+        if let (DataState::HasResults(Rows { rows, .. }), Some(SelectionMode::Cell)) =
+          (&self.data_state, self.scrollable.get_selection_mode())
+        {
+          let (x, y) = self.scrollable.get_cell_offsets();
+          if let Some(cell) = rows.get(y).and_then(|row| row.get(x as usize)) {
+            self.command_tx.clone().unwrap().send(Action::ViewJson(cell.clone()))?;
+          }
+        }
+      },
+      Input { key: Key::Char('H'), .. } => {
+        if let (DataState::HasResults(Rows { rows, headers, .. }), Some(SelectionMode::Cell)) =
+          (&self.data_state, self.scrollable.get_selection_mode())
+        {
+          let (x, _) = self.scrollable.get_cell_offsets();
+          if let Some(header) = headers.get(x as usize) {
+            let values = rows.iter().filter_map(|row| row.get(x as usize).cloned()).collect();
+            self.command_tx.clone().unwrap().send(Action::ShowHistogram(header.name.clone(), values))?;
+          }
+        }
+      },
+      Input { key: Key::Char('|'), .. } => {
+        if let DataState::HasResults(Rows { rows, headers, .. }) = &self.data_state {
+          let text = match self.config.settings.pager_format.unwrap_or_default() {
+            PagerFormat::Text => rows_to_text(headers, rows),
+            PagerFormat::Csv => rows_to_csv(headers, rows),
+          };
+          self.command_tx.clone().unwrap().send(Action::PipeToPager(text))?;
+        }
+      },
+      Input { key: Key::Char('S'), .. } => {
+        if let DataState::HasResults(_) = &self.data_state {
+          self.stats_footer = !self.stats_footer;
+          self.build_table();
+        }
+      },
+      Input { key: Key::Char('c'), .. } => {
+        if let DataState::HasResults(Rows { headers, .. }) = &self.data_state {
+          let names = headers.iter().map(|h| h.name.clone()).collect();
+          let visible = self.scrollable.get_visible_columns().to_vec();
+          self.command_tx.clone().unwrap().send(Action::OpenColumnPicker(names, visible))?;
+        }
+      },
+      Input { key: Key::Char('F'), .. } => {
+        if let (DataState::HasResults(_), Some(table)) = (&self.data_state, &self.source_table) {
+          if app_state.query_task.is_none() {
+            self.command_tx.clone().unwrap().send(Action::OpenFilter(table.clone()))?;
+          }
+        }
+      },
+      Input { key: Key::Char(':'), .. } => {
+        if let DataState::HasResults(Rows { rows, .. }) = &self.data_state {
+          self.command_tx.clone().unwrap().send(Action::OpenJumpToRow(rows.len()))?;
+        }
+      },
+      Input { key: Key::Char('P'), .. } => {
+        if let DataState::Explain(_) = &self.data_state {
+          self.pinned_explain = Some(self.explain_source.clone());
+        }
+      },
+      Input { key: Key::Char('U'), .. } => {
+        self.pinned_explain = None;
+      },
       Input { key: Key::Esc, .. } => {
-        self.scrollable.transition_selection_mode(None);
+        self.scrollable.transition_selection_mode(None).clear_marked_rows();
       },
       _ => {},
     };
@@ -377,8 +661,18 @@ impl<DB: Database> Component<DB> for Data<'_> {
   }
 
   fn update(&mut self, action: Action, app_state: &AppState<'_, DB>) -> Result<Option<Action>> {
-    if let Action::Query(query, confirmed) = action {
-      self.scrollable.reset_scroll();
+    match action {
+      Action::Query(query, confirmed) => {
+        self.scrollable.reset_scroll();
+      },
+      Action::SetVisibleColumns(columns) => {
+        self.scrollable.set_visible_columns(columns).first_column();
+        self.build_table();
+      },
+      Action::JumpToRow(row) => {
+        self.scrollable.go_to_row(row);
+      },
+      _ => {},
     }
     Ok(None)
   }
@@ -386,11 +680,7 @@ impl<DB: Database> Component<DB> for Data<'_> {
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect, app_state: &AppState<'_, DB>) -> Result<()> {
     let focused = app_state.focus == Focus::Data;
 
-    let mut block = Block::default().borders(Borders::ALL).border_style(if focused {
-      Style::new().green()
-    } else {
-      Style::new().dim()
-    });
+    let mut block = Block::default().borders(Borders::ALL).border_style(self.config.border_style(Focus::Data, focused));
 
     let inner_area = block.inner(area);
 
@@ -403,7 +693,7 @@ impl<DB: Database> Component<DB> for Data<'_> {
       });
     }
 
-    if let DataState::HasResults(Rows { rows, .. }) = &self.data_state {
+    if let DataState::HasResults(Rows { rows, headers, .. }) = &self.data_state {
       let (x, y) = self.scrollable.get_cell_offsets();
       let row = &rows[y];
       let title_string = match self.scrollable.get_selection_mode() {
@@ -411,13 +701,42 @@ impl<DB: Database> Component<DB> for Data<'_> {
           format!(" 󰆼 results <alt+3> (row {} of {})", y.saturating_add(1), rows.len())
         },
         Some(SelectionMode::Cell) => {
-          format!(" 󰆼 results <alt+3> (row {} of {}) - {} ", y.saturating_add(1), rows.len(), row[x as usize].clone())
+          format!(
+            " 󰆼 results <alt+3> (row {} of {}) - {} ",
+            y.saturating_add(1),
+            rows.len(),
+            title_value(&row[x as usize])
+          )
         },
         Some(SelectionMode::Copied) => {
           format!(" 󰆼 results <alt+3> ({} rows) - copied! ", rows.len())
         },
         _ => format!(" 󰆼 results <alt+3> ({} rows)", rows.len()),
       };
+      let title_string = match app_state.auto_limited {
+        Some(limit) if rows.len() as u64 >= limit => {
+          format!("{} - limited to {} rows ", title_string.trim_end(), limit)
+        },
+        _ => title_string,
+      };
+      let hidden = match self.scrollable.get_visible_columns().len() {
+        0 => 0,
+        visible => headers.len().saturating_sub(visible),
+      };
+      let title_string =
+        if hidden > 0 { format!("{} - {} columns hidden ", title_string.trim_end(), hidden) } else { title_string };
+      let marked = self.scrollable.get_marked_rows().len();
+      let title_string =
+        if marked > 0 { format!("{} - {} marked ", title_string.trim_end(), marked) } else { title_string };
+      let title_string = match &app_state.served_by {
+        Some(host) => format!("{} - via {} ", title_string.trim_end(), host),
+        None => title_string,
+      };
+      let title_string = if app_state.query_retried {
+        format!("{} - connection was reset; retried ", title_string.trim_end())
+      } else {
+        title_string
+      };
       block = block.title(title_string);
     } else {
       let title_string = match self.scrollable.get_selection_mode() {
@@ -495,17 +814,37 @@ impl<DB: Database> Component<DB> for Data<'_> {
       },
       DataState::HasResults(_) => {
         self.scrollable.block(block);
+        self.fill_rows(area);
         self.scrollable.draw(f, area, app_state)?;
       },
+      DataState::Error(e) if is_permission_denied(e) => {
+        let text = Text::from(vec![
+          Line::from("permission denied: your user isn't allowed to read this.").fg(Color::Red),
+          Line::from("ask the owner or an admin to grant select on it (and usage on its schema).").fg(Color::Red),
+          Line::from(""),
+          Line::from(e.to_string()).dim(),
+        ]);
+        f.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }).block(block), area);
+      },
       DataState::Error(e) => {
+        let message =
+          if app_state.query_retried { format!("connection was reset; retried: {}", e) } else { e.to_string() };
         f.render_widget(
-          Paragraph::new(e.to_string()).style(Style::default().fg(Color::Red)).wrap(Wrap { trim: true }).block(block),
+          Paragraph::new(message).style(Style::default().fg(Color::Red)).wrap(Wrap { trim: true }).block(block),
           area,
         );
       },
       DataState::Loading => {
+        // redrawn every frame, so the timer ticks while the query runs
+        let text = match app_state.last_query_start {
+          Some(start) => {
+            let elapsed = chrono::Utc::now().signed_duration_since(start).num_milliseconds() as f64 / 1000_f64;
+            format!("running… {:.1}s", elapsed)
+          },
+          None => "loading...".to_owned(),
+        };
         f.render_widget(
-          Paragraph::new(Text::from("loading...").fg(Color::Green)).wrap(Wrap { trim: false }).block(block),
+          Paragraph::new(Text::from(text).fg(Color::Green)).wrap(Wrap { trim: false }).block(block),
           area,
         );
       },
@@ -520,3 +859,358 @@ impl<DB: Database> Component<DB> for Data<'_> {
     Ok(())
   }
 }
+
+// numeric columns are right-aligned with grouped digits and uuids are lowercased;
+// this only affects what's drawn, so copied values stay as the database returned them
+fn format_cell<'a>(value: &str, type_name: &str, thousands_separator: &str, max_length: u16) -> Cell<'a> {
+  if is_numeric_type(type_name) {
+    let value = group_digits(value, thousands_separator).unwrap_or_else(|| value.to_owned());
+    Cell::from(Text::from(truncate_cell(&value, max_length)).alignment(Alignment::Right))
+  } else if type_name.eq_ignore_ascii_case("uuid") {
+    Cell::from(truncate_cell(&value.to_lowercase(), max_length))
+  } else {
+    Cell::from(truncate_cell(value, max_length))
+  }
+}
+
+// values that don't fit on one line of the column end in an ellipsis, instead of being cut off silently
+fn truncate_cell(value: &str, max_length: u16) -> String {
+  let max_length = max_length as usize;
+  let first_line = value.lines().next().unwrap_or_default();
+  if first_line.len() == value.len() && value.chars().count() <= max_length {
+    return value.to_owned();
+  }
+  let mut truncated = first_line.chars().take(max_length.saturating_sub(1)).collect::<String>();
+  truncated.push('…');
+  truncated
+}
+
+// the selected field's value in the title, which has room for only one line
+fn title_value(value: &str) -> String {
+  if !value.contains('\n') {
+    return value.to_owned();
+  }
+  format!("{} … ({} lines, <enter> to view)", value.lines().next().unwrap_or_default(), value.lines().count())
+}
+
+// one value per line, in the order the rows were loaded
+fn column_values(rows: &[Vec<String>], column: usize, distinct: bool) -> String {
+  let mut seen = HashSet::new();
+  rows
+    .iter()
+    .filter_map(|row| row.get(column))
+    .filter(|value| !distinct || seen.insert(value.as_str()))
+    .map(|value| value.as_str())
+    .collect::<Vec<&str>>()
+    .join("\n")
+}
+
+// quick stats over a column's loaded values, for the footer. NULLs are counted on their own
+// and left out of the rest, and numeric columns also get their sum and average
+fn column_stats(rows: &[Vec<String>], column: usize, numeric: bool, separator: &str) -> String {
+  let values = rows.iter().filter_map(|row| row.get(column)).collect::<Vec<&String>>();
+  let nulls = values.iter().filter(|value| **value == "NULL").count();
+  let count = values.len() - nulls;
+  let numbers = values.iter().filter_map(|value| value.parse::<f64>().ok()).collect::<Vec<f64>>();
+  if !numeric || numbers.is_empty() {
+    return format!("{} values, {} null", count, nulls);
+  }
+  let sum = numbers.iter().sum::<f64>();
+  let sum = if sum.fract() == 0.0 && sum.abs() < 1e15 { format!("{}", sum as i64) } else { format!("{:.2}", sum) };
+  let sum = group_digits(&sum, separator).unwrap_or(sum);
+  format!("{} values, {} null, sum {} avg {:.2}", count, nulls, sum, numbers.iter().sum::<f64>() / numbers.len() as f64)
+}
+
+fn group_digits(value: &str, separator: &str) -> Option<String> {
+  let (sign, digits) = match value.strip_prefix('-') {
+    Some(digits) => ("-", digits),
+    None => ("", value),
+  };
+  if separator.is_empty() || digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  let mut grouped = String::from(sign);
+  for (i, c) in digits.chars().enumerate() {
+    if i > 0 && (digits.len() - i) % 3 == 0 {
+      grouped.push_str(separator);
+    }
+    grouped.push(c);
+  }
+  Some(grouped)
+}
+
+// builds a line of the explain output, annotating anything that's likely
+// to keep the planner from using an index
+fn explain_line<'a>(line: String) -> Line<'a> {
+  match explain_line_warning(&line) {
+    Some(warning) => {
+      Line::from(vec![
+        Span::styled(line, Style::default().fg(Color::Yellow)),
+        Span::styled(format!("  <- {}", warning), Style::default().fg(Color::Yellow).italic()),
+      ])
+    },
+    None => Line::from(line),
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum PlanDiff {
+  Same(String),
+  Changed(String, String), // (pinned, new)
+  Removed(String),
+  Added(String),
+}
+
+// plan lines are matched with their numbers blanked out, so a node whose
+// costs or timings moved shows up as changed rather than removed and re-added
+fn diff_plans(pinned: &[String], new: &[String]) -> Vec<PlanDiff> {
+  let normalize =
+    |l: &String| split_numbers(l).into_iter().map(|t| if is_number(t) { "#" } else { t }).collect::<String>();
+  let (old_keys, new_keys): (Vec<String>, Vec<String>) =
+    (pinned.iter().map(normalize).collect(), new.iter().map(normalize).collect());
+  // longest common subsequence table, filled from the end
+  let mut lcs = vec![vec![0_usize; new.len() + 1]; pinned.len() + 1];
+  for i in (0..pinned.len()).rev() {
+    for j in (0..new.len()).rev() {
+      lcs[i][j] = if old_keys[i] == new_keys[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+    }
+  }
+  let (mut i, mut j) = (0, 0);
+  let mut diff = vec![];
+  while i < pinned.len() || j < new.len() {
+    if i < pinned.len() && j < new.len() && old_keys[i] == new_keys[j] {
+      diff.push(if pinned[i] == new[j] {
+        PlanDiff::Same(new[j].clone())
+      } else {
+        PlanDiff::Changed(pinned[i].clone(), new[j].clone())
+      });
+      i += 1;
+      j += 1;
+    } else if j < new.len() && (i == pinned.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+      diff.push(PlanDiff::Added(new[j].clone()));
+      j += 1;
+    } else {
+      diff.push(PlanDiff::Removed(pinned[i].clone()));
+      i += 1;
+    }
+  }
+  diff
+}
+
+fn plan_diff_line<'a>(diff: PlanDiff) -> Line<'a> {
+  match diff {
+    PlanDiff::Same(line) => Line::from(format!("  {}", line)),
+    PlanDiff::Removed(line) => Line::from(format!("- {}", line)).fg(Color::Red),
+    PlanDiff::Added(line) => Line::from(format!("+ {}", line)).fg(Color::Green),
+    PlanDiff::Changed(pinned, new) => {
+      let mut spans = vec![Span::styled("~ ", Style::default().fg(Color::Yellow))];
+      for (old, new) in split_numbers(&pinned).into_iter().zip(split_numbers(&new)) {
+        if old == new {
+          spans.push(Span::raw(new.to_owned()));
+        } else {
+          spans.push(Span::styled(new.to_owned(), Style::default().fg(Color::Yellow).bold()));
+          spans.push(Span::styled(format!(" (was {})", old), Style::default().dim()));
+        }
+      }
+      Line::from(spans)
+    },
+  }
+}
+
+// splits a line into alternating runs of numeric and non-numeric characters
+fn split_numbers(line: &str) -> Vec<&str> {
+  let mut tokens = vec![];
+  let mut start = 0;
+  for (i, c) in line.char_indices().skip(1) {
+    let prev = line[..i].chars().next_back().unwrap_or_default();
+    if is_number_char(prev) != is_number_char(c) {
+      tokens.push(&line[start..i]);
+      start = i;
+    }
+  }
+  if start < line.len() {
+    tokens.push(&line[start..]);
+  }
+  tokens
+}
+
+fn is_number_char(c: char) -> bool {
+  c.is_ascii_digit() || c == '.'
+}
+
+fn is_number(token: &str) -> bool {
+  token.chars().any(|c| c.is_ascii_digit()) && token.chars().all(is_number_char)
+}
+
+fn explain_line_warning(line: &str) -> Option<&'static str> {
+  let trimmed = line.trim_start().trim_start_matches("->").trim_start();
+  if trimmed.starts_with("Seq Scan on ") || trimmed.starts_with("Parallel Seq Scan on ") {
+    return match explain_estimated_rows(trimmed) {
+      Some(rows) if rows >= LARGE_SCAN_ROWS => Some("sequential scan on a large table"),
+      _ => None,
+    };
+  }
+  // sqlite's EXPLAIN QUERY PLAN
+  if trimmed.starts_with("SCAN ") && !trimmed.contains(" USING ") {
+    return Some("full table scan");
+  }
+  let is_predicate = ["Filter:", "Join Filter:", "Index Cond:", "Recheck Cond:"].iter().any(|p| trimmed.starts_with(p));
+  if !is_predicate {
+    return None;
+  }
+  if has_column_cast(trimmed) {
+    Some("cast on a column may prevent index use")
+  } else if has_wrapped_column(trimmed) {
+    Some("function-wrapped column may prevent index use")
+  } else {
+    None
+  }
+}
+
+fn explain_estimated_rows(line: &str) -> Option<u64> {
+  let start = line.find("rows=")? + "rows=".len();
+  line[start..].chars().take_while(|c| c.is_ascii_digit()).collect::<String>().parse().ok()
+}
+
+fn is_identifier_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '"'
+}
+
+// postgres renders casts of columns as `(column)::type`, while literals
+// are rendered as `'value'::type`
+fn has_column_cast(predicate: &str) -> bool {
+  predicate.match_indices(")::").any(|(i, _)| {
+    let before = &predicate[..i];
+    let identifier = before.chars().rev().take_while(|c| is_identifier_char(*c)).collect::<String>();
+    !identifier.is_empty()
+      && !identifier.chars().all(|c| c.is_ascii_digit())
+      && before[..before.len() - identifier.len()].ends_with('(')
+  })
+}
+
+// matches things like `lower(email)` or `date(created_at)`, but not
+// literals passed into functions, like `lower('abc')`
+fn has_wrapped_column(predicate: &str) -> bool {
+  predicate.match_indices('(').any(|(i, _)| {
+    let function = predicate[..i].chars().rev().take_while(|c| c.is_ascii_alphanumeric() || *c == '_').count();
+    let argument = predicate[i + 1..].trim_start_matches('(');
+    function > 0
+      && !matches!(predicate[i - function..i].to_uppercase().as_str(), "ANY" | "ALL" | "ARRAY")
+      && argument.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '"')
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_truncate_cell() {
+    assert_eq!(truncate_cell("short", 8), "short");
+    assert_eq!(truncate_cell("exactly8", 8), "exactly8");
+    assert_eq!(truncate_cell("a longer value", 8), "a longe…");
+    assert_eq!(truncate_cell("first\nsecond", 8), "first…");
+    assert_eq!(title_value("first\nsecond"), "first … (2 lines, <enter> to view)");
+  }
+
+  #[test]
+  fn test_explain_line_warning() {
+    let test_cases = vec![
+      ("Seq Scan on users  (cost=0.00..35.50 rows=2550 width=4)", None),
+      ("Seq Scan on users  (cost=0.00..3500.50 rows=255000 width=4)", Some("sequential scan on a large table")),
+      (
+        "  ->  Parallel Seq Scan on events  (cost=0.00..1.00 rows=10000 width=4)",
+        Some("sequential scan on a large table"),
+      ),
+      ("Index Scan using users_pkey on users  (cost=0.29..8.30 rows=1 width=4)", None),
+      ("  Filter: (id = 1)", None),
+      ("  Filter: ((email)::text = 'a@b.c'::text)", Some("cast on a column may prevent index use")),
+      ("  Filter: (lower(email) = 'a@b.c'::text)", Some("function-wrapped column may prevent index use")),
+      ("  Filter: (name = lower('ABC'::text))", None),
+      ("  Filter: (id = ANY (ids))", None),
+      ("  Index Cond: (id = ANY ('{1,2}'::integer[]))", None),
+      ("SCAN users", Some("full table scan")),
+      ("SEARCH users USING INTEGER PRIMARY KEY (rowid=?)", None),
+    ];
+    for (line, expected) in test_cases {
+      assert_eq!(explain_line_warning(line), expected, "Failed for line: {}", line);
+    }
+  }
+
+  #[test]
+  fn test_column_values() {
+    let rows: Vec<Vec<String>> =
+      [["1", "a"], ["2", "b"], ["1", "c"]].iter().map(|r| r.iter().map(|v| v.to_string()).collect()).collect();
+    assert_eq!(column_values(&rows, 0, false), "1\n2\n1");
+    assert_eq!(column_values(&rows, 0, true), "1\n2");
+    assert_eq!(column_values(&rows, 1, true), "a\nb\nc");
+    assert_eq!(column_values(&rows, 2, false), "");
+  }
+
+  #[test]
+  fn test_column_stats() {
+    let rows: Vec<Vec<String>> = [["1200", "a"], ["NULL", "NULL"], ["34", "b"]]
+      .iter()
+      .map(|r| r.iter().map(|v| v.to_string()).collect())
+      .collect();
+    assert_eq!(column_stats(&rows, 0, true, ","), "2 values, 1 null, sum 1,234 avg 617.00");
+    assert_eq!(column_stats(&rows, 1, false, ","), "2 values, 1 null");
+    assert_eq!(column_stats(&rows, 1, true, ","), "2 values, 1 null");
+    let rows: Vec<Vec<String>> =
+      [["0.5"], ["0.25"]].iter().map(|r| r.iter().map(|v| v.to_string()).collect()).collect();
+    assert_eq!(column_stats(&rows, 0, true, ""), "2 values, 0 null, sum 0.75 avg 0.38");
+  }
+
+  #[test]
+  fn test_group_digits() {
+    assert_eq!(group_digits("1234567", ","), Some("1,234,567".to_owned()));
+    assert_eq!(group_digits("-1234", "."), Some("-1.234".to_owned()));
+    assert_eq!(group_digits("123", ","), Some("123".to_owned()));
+    assert_eq!(group_digits("1000", " "), Some("1 000".to_owned()));
+    assert_eq!(group_digits("1234", ""), None);
+    assert_eq!(group_digits("1234.5", ","), None);
+    assert_eq!(group_digits("NULL", ","), None);
+    assert_eq!(group_digits("-", ","), None);
+  }
+
+  #[test]
+  fn test_diff_plans() {
+    let lines = |l: &[&str]| l.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+    let pinned = lines(&[
+      "Seq Scan on users  (cost=0.00..35.50 rows=10 width=36)",
+      "  Filter: (lower(email) = 'a'::text)",
+      "Planning Time: 0.100 ms",
+    ]);
+    let new = lines(&[
+      "Index Scan using users_email_idx on users  (cost=0.15..8.17 rows=1 width=36)",
+      "  Index Cond: (lower(email) = 'a'::text)",
+      "Planning Time: 0.100 ms",
+    ]);
+    assert_eq!(diff_plans(&pinned, &new), vec![
+      PlanDiff::Added(new[0].clone()),
+      PlanDiff::Added(new[1].clone()),
+      PlanDiff::Removed(pinned[0].clone()),
+      PlanDiff::Removed(pinned[1].clone()),
+      PlanDiff::Same(new[2].clone()),
+    ]);
+    let new = lines(&[
+      "Seq Scan on users  (cost=0.00..12.25 rows=10 width=36)",
+      "  Filter: (lower(email) = 'a'::text)",
+      "Planning Time: 0.100 ms",
+    ]);
+    assert_eq!(diff_plans(&pinned, &new), vec![
+      PlanDiff::Changed(pinned[0].clone(), new[0].clone()),
+      PlanDiff::Same(new[1].clone()),
+      PlanDiff::Same(new[2].clone()),
+    ]);
+  }
+
+  #[test]
+  fn test_split_numbers() {
+    assert_eq!(split_numbers("(cost=0.00..35.50 rows=10)"), vec!["(cost=", "0.00..35.50", " rows=", "10", ")"]);
+    assert_eq!(split_numbers("12 ms"), vec!["12", " ms"]);
+    assert_eq!(split_numbers(""), Vec::<&str>::new());
+    assert!(is_number("0.00..35.50"));
+    assert!(!is_number("."));
+  }
+}
@@ -1,7 +1,7 @@
 use std::{
   collections::HashMap,
   sync::{Arc, Mutex},
-  time::Duration,
+  time::{Duration, Instant},
 };
 
 #[cfg(not(feature = "termux"))]
@@ -10,19 +10,25 @@ use color_eyre::eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
 use ratatui::{prelude::*, widgets::*};
 use serde::{Deserialize, Serialize};
+use sqlparser::{
+  dialect::Dialect,
+  keywords::Keyword,
+  parser::{Parser, ParserError},
+  tokenizer::{Location, Token, Tokenizer, Whitespace},
+};
 use sqlx::{Database, Executor, Pool};
 use tokio::sync::mpsc::UnboundedSender;
-use tui_textarea::{Input, Key, Scrolling, TextArea};
+use tui_textarea::{CursorMove, Input, Key, Scrolling, TextArea};
 
 use super::{Component, Frame};
 use crate::{
   action::{Action, MenuPreview},
   app::{App, AppState, DbTask},
-  config::{Config, KeyBindings},
+  config::{Config, ExplainFlavor, KeyBindings},
   database::{self, get_keywords, DatabaseQueries, HasRowsAffected, ValueParser},
   focus::Focus,
   tui::Event,
-  vim::{Mode, Transition, Vim},
+  vim::{Mode, Registers, Vim},
 };
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +47,362 @@ fn keyword_regex() -> String {
   format!("(?i)(^|[^a-zA-Z0-9\'\"`._]+)({})($|[^a-zA-Z0-9\'\"`._]+)", get_keywords().join("|"))
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SyntaxClass {
+  Keyword,
+  String,
+  Number,
+  Comment,
+}
+
+impl SyntaxClass {
+  fn of(token: &Token) -> Option<Self> {
+    match token {
+      Token::Word(word) if word.keyword != Keyword::NoKeyword && word.quote_style.is_none() => Some(Self::Keyword),
+      Token::Number(..) => Some(Self::Number),
+      Token::Whitespace(Whitespace::SingleLineComment { .. } | Whitespace::MultiLineComment(_)) => Some(Self::Comment),
+      Token::SingleQuotedString(_)
+      | Token::DoubleQuotedString(_)
+      | Token::TripleSingleQuotedString(_)
+      | Token::TripleDoubleQuotedString(_)
+      | Token::DollarQuotedString(_)
+      | Token::SingleQuotedByteStringLiteral(_)
+      | Token::DoubleQuotedByteStringLiteral(_)
+      | Token::TripleSingleQuotedByteStringLiteral(_)
+      | Token::TripleDoubleQuotedByteStringLiteral(_)
+      | Token::SingleQuotedRawStringLiteral(_)
+      | Token::DoubleQuotedRawStringLiteral(_)
+      | Token::TripleSingleQuotedRawStringLiteral(_)
+      | Token::TripleDoubleQuotedRawStringLiteral(_)
+      | Token::NationalStringLiteral(_)
+      | Token::EscapedStringLiteral(_)
+      | Token::UnicodeStringLiteral(_)
+      | Token::HexStringLiteral(_) => Some(Self::String),
+      _ => None,
+    }
+  }
+
+  fn style(self) -> Style {
+    match self {
+      Self::Keyword => Style::default().fg(Color::Magenta).bold(),
+      Self::String => Style::default().fg(Color::Green),
+      Self::Number => Style::default().fg(Color::Cyan),
+      Self::Comment => Style::default().fg(Color::DarkGray).italic(),
+    }
+  }
+}
+
+// how long the text has to sit unchanged before it is parsed for warnings
+const LINT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// where the editor's text first fails to parse, as a 0-based (row, column)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LintError {
+  row: usize,
+  column: usize,
+  message: String,
+}
+
+// parses the whole editor the way a query would be, and reports the first error.
+// errors without a location (ex. an unexpected end of input) point at the end of the text
+fn lint_sql(lines: &[String], dialect: &dyn Dialect) -> Option<LintError> {
+  let message = match Parser::parse_sql(dialect, &lines.join("\n")).err()? {
+    ParserError::ParserError(message) | ParserError::TokenizerError(message) => message,
+    ParserError::RecursionLimitExceeded => return None,
+  };
+  let location = message.rsplit_once(" at Line: ").and_then(|(message, location)| {
+    let (line, column) = location.split_once(", Column: ")?;
+    Some((message.to_owned(), line.parse::<usize>().ok()?, column.parse::<usize>().ok()?))
+  });
+  Some(match location {
+    Some((message, line, column)) => {
+      LintError { row: line.saturating_sub(1), column: column.saturating_sub(1), message }
+    },
+    None => {
+      LintError {
+        row: lines.len().saturating_sub(1),
+        column: lines.last().map_or(0, |line| line.chars().count()),
+        message,
+      }
+    },
+  })
+}
+
+/// (start char, end char, class) ranges for each line of the editor
+type SyntaxRanges = Vec<Vec<(usize, usize, SyntaxClass)>>;
+
+// returns None when the text can't be tokenized (ex. an unterminated string),
+// in which case the editor falls back to only highlighting keywords
+fn syntax_ranges(lines: &[String], dialect: &dyn Dialect) -> Option<SyntaxRanges> {
+  let text = lines.join("\n");
+  let tokens = Tokenizer::new(dialect, &text).tokenize_with_location().ok()?;
+  let mut ranges: SyntaxRanges = vec![vec![]; lines.len()];
+  // sqlparser locations are 1-based and count chars, so a token ends where the next one starts
+  let position = |line: u64, column: u64| (line.saturating_sub(1) as usize, column.saturating_sub(1) as usize);
+  let end_of_text = (lines.len().saturating_sub(1), lines.last().map_or(0, |l| l.chars().count()));
+  for (i, token) in tokens.iter().enumerate() {
+    let Some(class) = SyntaxClass::of(&token.token) else {
+      continue;
+    };
+    let start = position(token.location.line, token.location.column);
+    let end = tokens.get(i + 1).map_or(end_of_text, |next| position(next.location.line, next.location.column));
+    for row in start.0..=end.0.min(lines.len().saturating_sub(1)) {
+      let from = if row == start.0 { start.1 } else { 0 };
+      let to = if row == end.0 { end.1 } else { lines[row].chars().count() };
+      if from < to {
+        ranges[row].push((from, to, class));
+      }
+    }
+  }
+  Some(ranges)
+}
+
+// completes a partially typed keyword (or name from [completions]) as far as every
+// match agrees, keeping the case the user started typing in
+fn keyword_completion(prefix: &str, extra: &[String]) -> Option<String> {
+  if prefix.is_empty() {
+    return None;
+  }
+  let upper = prefix.to_uppercase();
+  let keywords = get_keywords().into_iter().chain(extra.iter().map(|e| e.to_uppercase())).collect::<Vec<String>>();
+  let mut matches = keywords.iter().filter(|k| k.starts_with(&upper));
+  let first = matches.next()?;
+  let common = matches.fold(first.as_str(), |common, keyword| {
+    let len = common.chars().zip(keyword.chars()).take_while(|(a, b)| a == b).count();
+    &common[..len]
+  });
+  if common.len() <= upper.len() {
+    return None;
+  }
+  let completion = &common[upper.len()..];
+  Some(if prefix.chars().any(|c| c.is_ascii_lowercase()) { completion.to_lowercase() } else { completion.to_owned() })
+}
+
+struct Piece {
+  token: Token,
+  text: String,
+  spaced: bool,
+  newline: bool,
+}
+
+#[derive(Default)]
+struct Reflow {
+  formatted: Vec<String>,
+  line: String,
+  line_indent: usize,
+}
+
+impl Reflow {
+  fn push(&mut self, text: &str, spaced: bool) {
+    if spaced && !self.line.trim().is_empty() {
+      self.line.push(' ');
+    }
+    self.line.push_str(text);
+  }
+
+  fn break_line(&mut self, indent: usize) {
+    if !self.line.trim().is_empty() {
+      self.formatted.push(self.line.trim_end().to_owned());
+    }
+    self.line = "  ".repeat(indent);
+    self.line_indent = indent;
+  }
+}
+
+// puts the explain prefix in front of the query, unless it is already explained
+fn explain_query(lines: &[String], prefix: &str) -> Vec<String> {
+  let mut lines = lines.to_vec();
+  if let Some(first) = lines.iter_mut().find(|line| !line.trim().is_empty()) {
+    let first_word = first.split_whitespace().next().unwrap_or_default();
+    if !first_word.eq_ignore_ascii_case("explain") {
+      *first = format!("{} {}", prefix, first.trim_start());
+    }
+  }
+  lines
+}
+
+// reflows a query so that each clause starts on its own line and subqueries are
+// indented. every token keeps its original text, so quoting and comments survive.
+// returns None when the text can't be tokenized
+fn format_sql(lines: &[String], dialect: &dyn Dialect) -> Option<Vec<String>> {
+  let text = lines.join("\n");
+  let chars = text.chars().collect::<Vec<char>>();
+  let tokens = Tokenizer::new(dialect, &text).tokenize_with_location().ok()?;
+  let line_starts = lines
+    .iter()
+    .scan(0, |start, line| {
+      let line_start = *start;
+      *start += line.chars().count() + 1;
+      Some(line_start)
+    })
+    .collect::<Vec<usize>>();
+  let offset = |location: &Location| {
+    line_starts
+      .get(location.line.saturating_sub(1) as usize)
+      .map_or(chars.len(), |start| start + location.column.saturating_sub(1) as usize)
+      .min(chars.len())
+  };
+  let mut pieces = vec![];
+  let (mut spaced, mut newline) = (false, false);
+  for (i, token) in tokens.iter().enumerate() {
+    match &token.token {
+      Token::Whitespace(Whitespace::Newline) => (spaced, newline) = (true, true),
+      Token::Whitespace(Whitespace::Space | Whitespace::Tab) => spaced = true,
+      Token::EOF => {},
+      t => {
+        let start = offset(&token.location);
+        let end = tokens.get(i + 1).map_or(chars.len(), |next| offset(&next.location)).max(start);
+        pieces.push(Piece { token: t.clone(), text: chars[start..end].iter().collect(), spaced, newline });
+        (spaced, newline) = (false, false);
+      },
+    }
+  }
+  let keyword = |i: usize| {
+    match pieces.get(i) {
+      Some(Piece { token: Token::Word(word), .. }) => word.keyword,
+      _ => Keyword::NoKeyword,
+    }
+  };
+
+  let mut out = Reflow::default();
+  let mut indent = 0;
+  let mut clause: Option<Keyword> = None;
+  // one entry per open paren: (wraps a subquery, clause outside of it)
+  let mut parens: Vec<(bool, Option<Keyword>)> = vec![];
+  let mut between = false;
+  let mut statement_ended = false;
+  for (i, piece) in pieces.iter().enumerate() {
+    // clauses inside function calls and column lists stay on one line
+    let breakable = parens.last().map_or(true, |(subquery, _)| *subquery);
+    let previous = i.checked_sub(1).map_or(Keyword::NoKeyword, keyword);
+    let next = keyword(i + 1);
+    if let Token::Whitespace(Whitespace::SingleLineComment { .. }) = piece.token {
+      // a trailing comment stays at the end of the line it was written on
+      if !piece.newline && out.line.trim().is_empty() && !out.formatted.is_empty() {
+        let last = out.formatted.last_mut().unwrap();
+        last.push(' ');
+        last.push_str(piece.text.trim_end());
+      } else {
+        if statement_ended {
+          out.formatted.push(String::new());
+          statement_ended = false;
+        }
+        out.push(piece.text.trim_end(), piece.spaced);
+        out.break_line(out.line_indent);
+      }
+      continue;
+    }
+    if statement_ended {
+      out.formatted.push(String::new());
+      statement_ended = false;
+    }
+    match &piece.token {
+      Token::Word(word) => {
+        let starts_clause = breakable
+          && match word.keyword {
+            Keyword::SELECT
+            | Keyword::WHERE
+            | Keyword::HAVING
+            | Keyword::LIMIT
+            | Keyword::OFFSET
+            | Keyword::UNION
+            | Keyword::EXCEPT
+            | Keyword::INTERSECT
+            | Keyword::RETURNING
+            | Keyword::SET => true,
+            Keyword::FROM => !matches!(previous, Keyword::DELETE | Keyword::DISTINCT),
+            Keyword::VALUES => previous != Keyword::DEFAULT,
+            Keyword::GROUP | Keyword::ORDER => next == Keyword::BY,
+            Keyword::JOIN => {
+              !matches!(
+                previous,
+                Keyword::LEFT
+                  | Keyword::RIGHT
+                  | Keyword::FULL
+                  | Keyword::INNER
+                  | Keyword::CROSS
+                  | Keyword::NATURAL
+                  | Keyword::OUTER
+              )
+            },
+            Keyword::LEFT | Keyword::RIGHT | Keyword::FULL | Keyword::INNER | Keyword::CROSS => {
+              previous != Keyword::NATURAL && matches!(next, Keyword::JOIN | Keyword::OUTER)
+            },
+            Keyword::NATURAL => true,
+            _ => false,
+          };
+        let continues_condition = breakable
+          && matches!(word.keyword, Keyword::AND | Keyword::OR)
+          && matches!(clause, Some(Keyword::WHERE | Keyword::HAVING | Keyword::JOIN));
+        if word.keyword == Keyword::BETWEEN {
+          between = true;
+        }
+        if continues_condition && between {
+          between = false;
+        } else if continues_condition {
+          out.break_line(indent + 1);
+        } else if starts_clause {
+          out.break_line(indent);
+          clause = Some(match word.keyword {
+            Keyword::LEFT | Keyword::RIGHT | Keyword::FULL | Keyword::INNER | Keyword::CROSS | Keyword::NATURAL => {
+              Keyword::JOIN
+            },
+            keyword => keyword,
+          });
+        }
+        out.push(&piece.text, piece.spaced);
+      },
+      Token::Comma => {
+        out.push(&piece.text, false);
+        if breakable && clause == Some(Keyword::SELECT) {
+          out.break_line(indent + 1);
+        }
+      },
+      Token::LParen => {
+        let subquery = matches!(next, Keyword::SELECT | Keyword::WITH);
+        out.push(&piece.text, piece.spaced);
+        parens.push((subquery, clause));
+        if subquery {
+          indent += 1;
+          clause = None;
+          out.break_line(indent);
+        }
+      },
+      Token::RParen => {
+        if let Some((true, outer)) = parens.pop() {
+          indent = indent.saturating_sub(1);
+          clause = outer;
+          out.break_line(indent);
+        }
+        out.push(&piece.text, piece.spaced);
+      },
+      Token::SemiColon => {
+        out.push(&piece.text, false);
+        out.break_line(0);
+        indent = 0;
+        clause = None;
+        parens.clear();
+        between = false;
+        statement_ended = true;
+      },
+      _ => out.push(&piece.text, piece.spaced),
+    }
+  }
+  out.break_line(0);
+  if out.formatted.is_empty() {
+    return None;
+  }
+  // multi-line comments carry their own line breaks
+  Some(out.formatted.join("\n").split('\n').map(|l| l.to_owned()).collect())
+}
+
+// an editor buffer that isn't being shown, with its own undo history and vim state
+struct EditorBuffer<'a> {
+  textarea: TextArea<'a>,
+  vim_state: Vim,
+  cursor_style: Style,
+}
+
 #[derive(Default)]
 pub struct Editor<'a> {
   command_tx: Option<UnboundedSender<Action>>,
@@ -48,8 +410,15 @@ pub struct Editor<'a> {
   selection: Option<Selection>,
   textarea: TextArea<'a>,
   vim_state: Vim,
+  registers: Registers,
   cursor_style: Style,
   last_query_duration: Option<chrono::Duration>,
+  lint: Option<LintError>,
+  linted_lines: Vec<String>,
+  lint_due: Option<Instant>,
+  // every buffer but the shown one, in order; the shown one sits at buffer_index
+  buffers: Vec<EditorBuffer<'a>>,
+  buffer_index: usize,
 }
 
 impl Editor<'_> {
@@ -62,8 +431,14 @@ impl Editor<'_> {
       selection: None,
       textarea,
       vim_state: Vim::new(Mode::Normal),
+      registers: Registers::new(),
       cursor_style: Mode::Normal.cursor_style(),
       last_query_duration: None,
+      lint: None,
+      linted_lines: vec![],
+      lint_due: None,
+      buffers: vec![],
+      buffer_index: 0,
     }
   }
 
@@ -83,6 +458,8 @@ impl Editor<'_> {
           }
         }
       },
+      Input { key: Key::Tab, shift: false, ctrl: false, alt: false }
+        if self.vim_state.mode == Mode::Insert && self.complete_keyword(app_state) => {},
       Input { key: Key::Tab, shift: false, .. } if self.vim_state.mode != Mode::Insert => {
         if let Some(sender) = &self.command_tx {
           sender.send(Action::CycleFocusForwards)?;
@@ -100,19 +477,206 @@ impl Editor<'_> {
       },
       _ => {
         let new_vim_state = self.vim_state.clone();
-        self.vim_state = match new_vim_state.transition(input, &mut self.textarea) {
-          Transition::Mode(mode) if new_vim_state.mode != mode => {
-            self.cursor_style = mode.cursor_style();
-            Vim::new(mode)
-          },
-          Transition::Nop | Transition::Mode(_) => new_vim_state,
-          Transition::Pending(input) => new_vim_state.with_pending(input),
-        };
+        let transition = new_vim_state.transition(input, &mut self.textarea, &mut self.registers);
+        self.vim_state = new_vim_state.after(transition);
+        self.cursor_style = self.vim_state.mode.cursor_style();
         self.vim_state.register_action_handler(self.command_tx.clone())?;
       },
     };
     Ok(())
   }
+
+  fn complete_keyword<DB: Database>(&mut self, app_state: &AppState<'_, DB>) -> bool {
+    let (row, col) = self.textarea.cursor();
+    let Some(line) = self.textarea.lines().get(row) else {
+      return false;
+    };
+    let before_cursor = line.chars().take(col).collect::<Vec<char>>();
+    let prefix = before_cursor
+      .iter()
+      .rev()
+      .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+      .collect::<Vec<&char>>()
+      .into_iter()
+      .rev()
+      .collect::<String>();
+    // a complete [completions] name expands to its text
+    if let Some(text) = self.config.completions.get(&prefix).filter(|text| **text != prefix) {
+      for _ in 0..prefix.chars().count() {
+        self.textarea.delete_char();
+      }
+      self.textarea.insert_str(text);
+      return true;
+    }
+    // table and column names come from the schema loaded along with the menu
+    let mut extra = self.config.completions.keys().cloned().collect::<Vec<String>>();
+    extra.extend(app_state.schema.iter().flat_map(|cache| cache.names()));
+    match keyword_completion(&prefix, &extra) {
+      Some(completion) => {
+        self.textarea.insert_str(completion);
+        true
+      },
+      None => false,
+    }
+  }
+
+  fn highlighting_enabled(&self) -> bool {
+    self.config.settings.syntax_highlighting.unwrap_or(true)
+  }
+
+  fn linting_enabled(&self) -> bool {
+    self.config.settings.lint.unwrap_or(true)
+  }
+
+  // called on every draw: an edit hides the warning, and the text is parsed again
+  // once it has been left alone for the debounce
+  fn refresh_lint(&mut self, dialect: &dyn Dialect) {
+    if !self.linting_enabled() {
+      self.lint = None;
+      return;
+    }
+    let lines = self.textarea.lines();
+    if lines != self.linted_lines.as_slice() {
+      self.linted_lines = lines.to_vec();
+      self.lint_due = Some(Instant::now() + LINT_DEBOUNCE);
+      self.lint = None;
+    } else if self.lint_due.is_some_and(|due| Instant::now() >= due) {
+      self.lint_due = None;
+      self.lint = lint_sql(lines, dialect);
+    }
+  }
+
+  // marks the line number of the line with the error, found the same way as in highlight_syntax
+  fn mark_lint(&self, buf: &mut Buffer, inner: Rect, lint: &LintError) {
+    let gutter = self.textarea.lines().len().to_string().len() as u16 + 2;
+    if inner.width <= gutter {
+      return;
+    }
+    for y in inner.top()..inner.bottom() {
+      let line_number = (inner.x..inner.x + gutter)
+        .filter_map(|x| buf.cell((x, y)).map(|cell| cell.symbol().to_owned()))
+        .collect::<String>();
+      if line_number.trim().parse::<usize>().ok() == Some(lint.row + 1) {
+        for x in inner.x..inner.x + gutter {
+          if let Some(cell) = buf.cell_mut((x, y)) {
+            cell.set_style(Style::default().fg(Color::Black).bg(Color::Red));
+          }
+        }
+      }
+    }
+  }
+
+  fn reset_textarea(&mut self, lines: Vec<String>) {
+    self.textarea = TextArea::from(lines);
+    if self.highlighting_enabled() {
+      self.textarea.set_search_pattern(keyword_regex()).unwrap();
+    }
+  }
+
+  // tui-textarea doesn't expose its viewport, so the rendered rows are matched back to editor lines
+  // using the line number gutter and only restyled when their text lines up with the source line
+  fn highlight_syntax(&self, buf: &mut Buffer, inner: Rect, ranges: &SyntaxRanges) {
+    let lines = self.textarea.lines();
+    let gutter = lines.len().to_string().len() as u16 + 2;
+    if inner.width <= gutter {
+      return;
+    }
+    for y in inner.top()..inner.bottom() {
+      let line_number = (inner.x..inner.x + gutter)
+        .filter_map(|x| buf.cell((x, y)).map(|cell| cell.symbol().to_owned()))
+        .collect::<String>();
+      let Some(row) = line_number.trim().parse::<usize>().ok().and_then(|n| n.checked_sub(1)) else {
+        continue;
+      };
+      let (Some(line), Some(row_ranges)) = (lines.get(row), ranges.get(row)) else {
+        continue;
+      };
+      let mut x = inner.x + gutter;
+      let mut cells = vec![];
+      for (i, c) in line.chars().enumerate() {
+        if x >= inner.right() {
+          break;
+        }
+        match buf.cell((x, y)) {
+          Some(cell) if cell.symbol().starts_with(c) => cells.push((x, i)),
+          _ => {
+            cells.clear();
+            break;
+          },
+        }
+        x += Span::raw(c.to_string()).width().max(1) as u16;
+      }
+      for (x, i) in cells {
+        let class = row_ranges.iter().find(|(from, to, _)| (*from..*to).contains(&i)).map(|r| r.2);
+        if let Some(cell) = buf.cell_mut((x, y)) {
+          // leave the cursor and selection alone
+          if cell.modifier.contains(Modifier::REVERSED) || cell.bg != Color::Reset {
+            continue;
+          }
+          // also clears keyword search matches that the tokenizer knows aren't keywords (ex. quoted identifiers)
+          cell.fg = Color::Reset;
+          cell.modifier = Modifier::empty();
+          if let Some(class) = class {
+            cell.set_style(class.style());
+          }
+        }
+      }
+    }
+  }
+}
+
+impl<'a> Editor<'a> {
+  // swaps the shown buffer for another one, keeping the order of the rest
+  fn replace_buffer(&mut self, buffer: EditorBuffer<'a>) -> EditorBuffer<'a> {
+    self.selection = None;
+    self.lint = None;
+    self.linted_lines = vec![];
+    self.lint_due = None;
+    EditorBuffer {
+      textarea: std::mem::replace(&mut self.textarea, buffer.textarea),
+      vim_state: std::mem::replace(&mut self.vim_state, buffer.vim_state),
+      cursor_style: std::mem::replace(&mut self.cursor_style, buffer.cursor_style),
+    }
+  }
+
+  fn new_buffer(&mut self) {
+    let mut textarea = TextArea::default();
+    if self.highlighting_enabled() {
+      textarea.set_search_pattern(keyword_regex()).unwrap();
+    }
+    let mut vim_state = Vim::new(Mode::Normal);
+    vim_state.register_action_handler(self.command_tx.clone()).ok();
+    let previous = self.replace_buffer(EditorBuffer { textarea, vim_state, cursor_style: Mode::Normal.cursor_style() });
+    self.buffers.insert(self.buffer_index, previous);
+    self.buffer_index = self.buffers.len();
+  }
+
+  // wraps around at either end
+  fn switch_buffer(&mut self, forwards: bool) {
+    let count = self.buffers.len() + 1;
+    if count == 1 {
+      return;
+    }
+    let index = if forwards { (self.buffer_index + 1) % count } else { (self.buffer_index + count - 1) % count };
+    let placeholder =
+      EditorBuffer { textarea: TextArea::default(), vim_state: Vim::new(Mode::Normal), cursor_style: Style::default() };
+    let previous = self.replace_buffer(placeholder);
+    self.buffers.insert(self.buffer_index, previous);
+    let next = self.buffers.remove(index);
+    self.replace_buffer(next);
+    self.buffer_index = index;
+  }
+
+  // the last buffer can't be closed
+  fn close_buffer(&mut self) {
+    if self.buffers.is_empty() {
+      return;
+    }
+    let index = self.buffer_index.min(self.buffers.len() - 1);
+    let next = self.buffers.remove(index);
+    self.replace_buffer(next);
+    self.buffer_index = index;
+  }
 }
 
 impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
@@ -124,6 +688,9 @@ impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
 
   fn register_config_handler(&mut self, config: Config) -> Result<()> {
     self.config = config;
+    if !self.highlighting_enabled() {
+      self.textarea.set_search_pattern("").unwrap();
+    }
     Ok(())
   }
 
@@ -159,7 +726,13 @@ impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
       return Ok(None);
     }
     if let Some(Event::Paste(text)) = event {
-      self.textarea.insert_str(text);
+      // inserted in one edit, so a single undo takes the whole paste back out
+      let warning_lines = self.config.settings.paste_warning_lines.unwrap_or(1000);
+      if warning_lines > 0 && text.lines().count() > warning_lines {
+        self.command_tx.as_ref().unwrap().send(Action::ConfirmPaste(text))?;
+      } else {
+        self.textarea.insert_str(text);
+      }
     } else if let Some(Event::Mouse(event)) = event {
       self.handle_mouse_events(event, app_state).unwrap();
     } else if let Some(Event::Key(key)) = event {
@@ -181,23 +754,82 @@ impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
           MenuPreview::Constraints => DB::preview_constraints_query(&schema, &table),
           MenuPreview::Indexes => DB::preview_indexes_query(&schema, &table),
           MenuPreview::Policies => DB::preview_policies_query(&schema, &table),
+          MenuPreview::Definition => DB::preview_function_definition_query(&schema, &table),
         };
-        self.textarea = TextArea::from(vec![query.clone()]);
-        self.textarea.set_search_pattern(keyword_regex()).unwrap();
+        self.reset_textarea(vec![query.clone()]);
         self.command_tx.as_ref().unwrap().send(Action::Query(vec![query.clone()], false))?;
       },
+      Action::PreviewFiltered(query) => {
+        if app_state.query_task.is_some() {
+          return Ok(None);
+        }
+        self.reset_textarea(vec![query.clone()]);
+        self.command_tx.as_ref().unwrap().send(Action::Query(vec![query], false))?;
+      },
+      Action::PreviewSettings(search) => {
+        if app_state.query_task.is_some() {
+          return Ok(None);
+        }
+        let query = DB::preview_settings_query(search.as_deref());
+        let lines = query.lines().map(|l| l.trim().to_owned()).collect::<Vec<String>>();
+        self.reset_textarea(lines.clone());
+        self.command_tx.as_ref().unwrap().send(Action::Query(lines, false))?;
+      },
+      Action::PreviewActivity => {
+        if app_state.query_task.is_some() {
+          return Ok(None);
+        }
+        let query = DB::preview_activity_query();
+        let lines = query.lines().map(|l| l.trim().to_owned()).collect::<Vec<String>>();
+        self.reset_textarea(lines.clone());
+        self.command_tx.as_ref().unwrap().send(Action::Query(lines, false))?;
+      },
       Action::SubmitEditorQuery => {
         if let Some(sender) = &self.command_tx {
           sender.send(Action::Query(self.textarea.lines().to_vec(), false))?;
         }
       },
+      Action::ExplainEditorQuery => {
+        if let Some(sender) = &self.command_tx {
+          let analyze = self.config.settings.explain.unwrap_or_default() == ExplainFlavor::Analyze;
+          sender.send(Action::Query(explain_query(self.textarea.lines(), &DB::explain_prefix(analyze)), false))?;
+        }
+      },
+      Action::DryRunEditorQuery => {
+        if let Some(sender) = &self.command_tx {
+          sender.send(Action::DryRun(self.textarea.lines().to_vec()))?;
+        }
+      },
+      Action::OpenExternalEditor => {
+        if let Some(sender) = &self.command_tx {
+          sender.send(Action::EditInExternalEditor(self.textarea.lines().to_vec()))?;
+        }
+      },
       Action::HistoryToEditor(lines) => {
-        self.textarea = TextArea::from(lines.clone());
-        self.textarea.set_search_pattern(keyword_regex()).unwrap();
+        self.reset_textarea(lines);
+      },
+      Action::FormatQuery => {
+        if let Some(lines) = format_sql(self.textarea.lines(), app_state.dialect.as_ref()) {
+          // replacing the selection keeps the change undoable
+          self.textarea.select_all();
+          self.textarea.insert_str(lines.join("\n"));
+          self.textarea.move_cursor(CursorMove::Top);
+        }
       },
       Action::CopyData(data) => {
         self.textarea.set_yank_text(data);
       },
+      Action::InsertIntoEditor(text) => {
+        self.textarea.insert_str(text);
+      },
+      Action::NewBuffer => self.new_buffer(),
+      Action::NextBuffer => self.switch_buffer(true),
+      Action::PrevBuffer => self.switch_buffer(false),
+      Action::CloseBuffer => self.close_buffer(),
+      Action::ShowRegisters => {
+        let registers = self.registers.iter().map(|(name, text)| (*name, text.clone())).collect();
+        self.command_tx.as_ref().unwrap().send(Action::OpenRegisters(registers))?;
+      },
       _ => {},
     }
     Ok(None)
@@ -225,21 +857,172 @@ impl<DB: Database + DatabaseQueries> Component<DB> for Editor<'_> {
         seconds
       )
     });
-    let block = self
+    self.refresh_lint(app_state.dialect.as_ref());
+    let mut block = self
       .vim_state
       .mode
       .block()
-      .border_style(if focused { Style::new().green() } else { Style::new().dim() })
+      .border_style(self.config.border_style(Focus::Editor, focused))
       .title(Line::from(duration_string).right_aligned());
+    if !self.buffers.is_empty() {
+      block = block.title(format!(" buffer {}/{} ", self.buffer_index + 1, self.buffers.len() + 1));
+    }
+    if let Some(lint) = &self.lint {
+      block = block.title_bottom(
+        Line::from(format!(" {}:{} {} ", lint.row + 1, lint.column + 1, lint.message)).style(Style::default().red()),
+      );
+    }
 
+    let inner = block.inner(area);
     self.textarea.set_cursor_style(self.cursor_style);
     self.textarea.set_block(block);
-    self.textarea.set_line_number_style(if focused { Style::default().fg(Color::Yellow) } else { Style::new().dim() });
+    self.textarea.set_line_number_style(if focused {
+      self.config.style(Focus::Editor, "accent")
+    } else {
+      self.config.style(Focus::Editor, "unfocused")
+    });
     self.textarea.set_cursor_line_style(Style::default().not_underlined());
     self.textarea.set_hard_tab_indent(false);
     self.textarea.set_tab_length(2);
     self.textarea.set_search_style(Style::default().fg(Color::Magenta).bold());
     f.render_widget(&self.textarea, area);
+    if self.highlighting_enabled() {
+      if let Some(ranges) = syntax_ranges(self.textarea.lines(), app_state.dialect.as_ref()) {
+        self.highlight_syntax(f.buffer_mut(), inner, &ranges);
+      }
+    }
+    if let Some(lint) = &self.lint {
+      self.mark_lint(f.buffer_mut(), inner, lint);
+    }
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use sqlparser::dialect::PostgreSqlDialect;
+
+  use super::*;
+
+  #[test]
+  fn test_buffers() {
+    let mut editor = Editor::default();
+    editor.textarea.insert_str("select 1");
+    editor.new_buffer();
+    editor.textarea.insert_str("select 2");
+    editor.new_buffer();
+    editor.textarea.insert_str("select 3");
+    assert_eq!((editor.buffer_index, editor.buffers.len()), (2, 2));
+    editor.switch_buffer(true);
+    assert_eq!((editor.buffer_index, editor.textarea.lines()), (0, &["select 1".to_owned()][..]));
+    editor.switch_buffer(false);
+    assert_eq!(editor.textarea.lines(), ["select 3"]);
+    editor.switch_buffer(false);
+    editor.close_buffer();
+    assert_eq!((editor.buffer_index, editor.textarea.lines()), (1, &["select 3".to_owned()][..]));
+    editor.close_buffer();
+    editor.close_buffer();
+    assert_eq!(
+      (editor.buffer_index, editor.buffers.len(), editor.textarea.lines()),
+      (0, 0, &["select 1".to_owned()][..])
+    );
+  }
+
+  #[test]
+  fn test_explain_query() {
+    let lines = |query: &str| query.lines().map(|l| l.to_owned()).collect::<Vec<String>>();
+    assert_eq!(
+      explain_query(&lines("\n  select *\nfrom users"), "EXPLAIN ANALYZE"),
+      lines("\nEXPLAIN ANALYZE select *\nfrom users")
+    );
+    assert_eq!(explain_query(&lines("explain select 1"), "EXPLAIN ANALYZE"), lines("explain select 1"));
+    assert_eq!(explain_query(&lines(""), "EXPLAIN"), lines(""));
+  }
+
+  #[test]
+  fn test_format_sql() {
+    let format = |query: &str| {
+      format_sql(&query.lines().map(|l| l.to_owned()).collect::<Vec<String>>(), &PostgreSqlDialect {}).unwrap()
+    };
+    assert_eq!(
+      format("select a, count(*) from t where x = 1 and y between 1 and 2 group by a order by a limit 10"),
+      vec![
+        "select a,",
+        "  count(*)",
+        "from t",
+        "where x = 1",
+        "  and y between 1 and 2",
+        "group by a",
+        "order by a",
+        "limit 10"
+      ]
+    );
+    assert_eq!(
+      format("select * from (select id from t where s = 'it''s') s left join u on u.id = s.id and u.ok"),
+      vec![
+        "select *",
+        "from (",
+        "  select id",
+        "  from t",
+        "  where s = 'it''s'",
+        ") s",
+        "left join u on u.id = s.id",
+        "  and u.ok"
+      ]
+    );
+    assert_eq!(format("-- top\nselect a from t; -- done\nselect 1 union all select 2"), vec![
+      "-- top",
+      "select a",
+      "from t; -- done",
+      "",
+      "select 1",
+      "union all",
+      "select 2"
+    ]);
+    let formatted = format("select a, b from t where x in (1, 2) and y = now()::date");
+    assert_eq!(format(&formatted.join("\n")), formatted);
+    assert!(format_sql(&["select 'unterminated".to_owned()], &PostgreSqlDialect {}).is_none());
+  }
+
+  #[test]
+  fn test_syntax_ranges() {
+    let lines = vec!["select 1, 'a b' -- note".to_owned(), "from t /* x".to_owned(), "y */".to_owned()];
+    let ranges = syntax_ranges(&lines, &PostgreSqlDialect {}).unwrap();
+    assert_eq!(ranges[0], vec![
+      (0, 6, SyntaxClass::Keyword),
+      (7, 8, SyntaxClass::Number),
+      (10, 15, SyntaxClass::String),
+      (16, 23, SyntaxClass::Comment)
+    ]);
+    assert_eq!(ranges[1], vec![(0, 4, SyntaxClass::Keyword), (7, 11, SyntaxClass::Comment)]);
+    assert_eq!(ranges[2], vec![(0, 4, SyntaxClass::Comment)]);
+    assert!(syntax_ranges(&["select 'oops".to_owned()], &PostgreSqlDialect {}).is_none());
+  }
+
+  #[test]
+  fn test_lint_sql() {
+    let lint =
+      |query: &str| lint_sql(&query.lines().map(|l| l.to_owned()).collect::<Vec<String>>(), &PostgreSqlDialect {});
+    assert_eq!(lint("select 1;\nselect * from users"), None);
+    assert_eq!(lint(""), None);
+    let error = lint("select *\nfrom users wher id = 1").unwrap();
+    assert_eq!((error.row, error.column), (1, 16));
+    assert!(!error.message.contains("Line:"));
+    let error = lint("select * from").unwrap();
+    assert_eq!((error.row, error.column), (0, 13));
+  }
+
+  #[test]
+  fn test_keyword_completion() {
+    assert_eq!(keyword_completion("sel", &[]), Some("ect".to_owned()));
+    assert_eq!(keyword_completion("SEL", &[]), Some("ECT".to_owned()));
+    assert_eq!(keyword_completion("distin", &[]), Some("ct".to_owned()));
+    assert_eq!(keyword_completion("s", &[]), None);
+    assert_eq!(keyword_completion("zzz", &[]), None);
+    assert_eq!(keyword_completion("", &[]), None);
+    let extra = vec!["customer_accounts".to_owned(), "customer_orders".to_owned()];
+    assert_eq!(keyword_completion("cust", &extra), Some("omer_".to_owned()));
+    assert_eq!(keyword_completion("customer_a", &extra), Some("ccounts".to_owned()));
+    assert_eq!(keyword_completion("distin", &extra), Some("ct".to_owned()));
+  }
+}
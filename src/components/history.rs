@@ -9,7 +9,7 @@ use tui_textarea::{Input, Key, Scrolling, TextArea};
 use super::{Component, Frame};
 use crate::{
   action::{Action, MenuPreview},
-  app::{App, AppState},
+  app::{App, AppState, HistoryEntry, WriteOutcome, WriteRecord},
   config::{Config, KeyBindings},
   focus::Focus,
   tui::Event,
@@ -22,6 +22,7 @@ pub struct History {
   list_state: ListState,
   copied: bool,
   last_query_duration: Option<chrono::Duration>,
+  window_start: usize,
 }
 
 impl History {
@@ -32,6 +33,7 @@ impl History {
       list_state: ListState::default(),
       copied: false,
       last_query_duration: None,
+      window_start: 0,
     }
   }
 
@@ -107,6 +109,9 @@ impl<DB: sqlx::Database> Component<DB> for History {
         KeyCode::Char('D') => {
           self.command_tx.as_ref().unwrap().send(Action::ClearHistory)?;
         },
+        KeyCode::Char('p') => {
+          self.command_tx.as_ref().unwrap().send(Action::TogglePinHistory(i))?;
+        },
         _ => {},
       };
     }
@@ -140,49 +145,65 @@ impl<DB: sqlx::Database> Component<DB> for History {
     });
     let block = Block::default()
       .borders(Borders::ALL)
-      .border_style(if focused { Style::new().green() } else { Style::new().dim() })
+      .border_style(self.config.border_style(Focus::History, focused))
       .title(Line::from(duration_string).right_aligned());
     let scrollbar_margin = area.inner(Margin { vertical: 1, horizontal: 0 });
 
-    let items = app_state
-      .history
+    match self.list_state.selected() {
+      Some(x) if x > app_state.history.len().saturating_sub(1) => {
+        self.list_state.select(Some(0));
+      },
+      None => {
+        self.list_state.select(Some(0));
+      },
+      _ => {},
+    };
+    let selected = self.list_state.selected().unwrap_or(0);
+
+    // only the entries that fit on screen are turned into list items, so that
+    // rendering cost doesn't grow with the size of the history
+    let max_lines = 1_usize.max(area.height.saturating_sub(6) as usize);
+    let available_height = area.height.saturating_sub(2) as usize;
+    let heights = app_state.history.iter().map(|h| entry_height(&h.query_lines, max_lines)).collect::<Vec<usize>>();
+    let (window_start, window_end) = visible_window(&heights, selected, self.window_start, available_height);
+    self.window_start = window_start;
+
+    let items = app_state.history[window_start..window_end]
       .iter()
       .enumerate()
       .map(|(i, h)| {
-        let selected = self.list_state.selected() == Some(i);
-        let color = if selected && focused { Color::Blue } else { Color::default() };
-        let max_lines = 1_usize.max(area.height.saturating_sub(6) as usize);
+        let selected = selected == window_start + i;
+        let style = if selected && focused { self.config.style(Focus::History, "selected") } else { Style::default() };
         let mut lines = h
           .query_lines[0..max_lines.min(h.query_lines.len())]
           .iter()
-          .map(|s| Line::from(s.clone()).style(Style::default().fg(color)))
+          .map(|s| Line::from(s.clone()).style(style))
           .collect::<Vec<Line>>();
         if h.query_lines.len() > max_lines {
-          lines.push(Line::from(format!("... and {} more lines", h.query_lines.len().saturating_sub(max_lines))).style(Style::default().fg(color)));
+          lines.push(Line::from(format!("... and {} more lines", h.query_lines.len().saturating_sub(max_lines))).style(style));
         }
-        lines.insert(
-          0,
+        let mut header =
           Line::from(format!("{}{}", if self.copied && selected { " copied! - " } else { "" }, h.timestamp))
-            .style(if focused { Color::Yellow } else { Color::default() }),
-        );
+            .style(if focused { self.config.style(Focus::History, "accent") } else { Style::default() });
+        if h.pinned {
+          header.push_span(Span::styled(" [pinned]", Style::default().fg(Color::Cyan)));
+        }
+        if h.runs > 1 {
+          header.push_span(Span::styled(format!(" (ran {} times)", h.runs), Style::default().dim()));
+        }
+        if let Some(write) = &h.write {
+          header.push_span(Span::raw(" "));
+          header.push_span(write_span(write));
+        }
+        lines.insert(0, header);
         lines.push(
           Line::from("----------------------------------------------------------------------------------------------------------------------------------------------------------------")
-            .style(Style::default().fg(color)),
+            .style(style),
         );
         ListItem::new(Text::from_iter(lines))
       })
       .collect::<Vec<ListItem>>();
 
-    match self.list_state.selected() {
-      Some(x) if x > items.len().saturating_sub(1) => {
-        self.list_state.select(Some(0));
-      },
-      None => {
-        self.list_state.select(Some(0));
-      },
-      _ => {},
-    };
-
     let list = List::default()
       .items(items)
       .block(block)
@@ -190,13 +211,158 @@ impl<DB: sqlx::Database> Component<DB> for History {
       .highlight_symbol(if self.copied { "  " } else { " > " })
       .highlight_spacing(HighlightSpacing::Always);
 
-    f.render_stateful_widget(list, area, &mut self.list_state);
+    let mut window_state = ListState::default().with_selected(Some(selected.saturating_sub(window_start)));
+    f.render_stateful_widget(list, area, &mut window_state);
     let vertical_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
       .symbols(scrollbar::VERTICAL)
-      .style(if focused { Style::default().fg(Color::Green) } else { Style::default() });
+      .style(if focused { self.config.style(Focus::History, "focused") } else { Style::default() });
     let mut vertical_scrollbar_state = ScrollbarState::new(app_state.history.len().saturating_sub(1))
       .position(self.list_state.selected().map_or(0, |x| x));
     f.render_stateful_widget(vertical_scrollbar, scrollbar_margin, &mut vertical_scrollbar_state);
     Ok(())
   }
 }
+
+// unpinned entries past this many are dropped, least recently run first,
+// unless the history_max_entries setting says otherwise
+pub const DEFAULT_MAX_HISTORY: usize = 50;
+
+// pinned entries stay at the top, and each group is ordered by most recent run
+fn sort_history(history: &mut [HistoryEntry]) {
+  history.sort_by_key(|entry| (!entry.pinned, std::cmp::Reverse(entry.timestamp)));
+}
+
+// running a query that is already in the history moves it to the top and counts
+// the run, instead of adding a duplicate. a max_entries of None keeps everything
+pub fn record_run(
+  history: &mut Vec<HistoryEntry>,
+  query_lines: Vec<String>,
+  timestamp: chrono::DateTime<chrono::Local>,
+  max_entries: Option<usize>,
+) {
+  match history.iter_mut().find(|entry| entry.query_lines == query_lines) {
+    Some(entry) => {
+      entry.timestamp = timestamp;
+      entry.write = None;
+      entry.runs += 1;
+    },
+    None => history.push(HistoryEntry { query_lines, timestamp, write: None, runs: 1, pinned: false }),
+  }
+  sort_history(history);
+  if let Some(max_entries) = max_entries {
+    let unpinned = history.iter().filter(|entry| !entry.pinned).count();
+    history.truncate(history.len() - unpinned.saturating_sub(max_entries));
+  }
+}
+
+pub fn toggle_pin(history: &mut [HistoryEntry], i: usize) {
+  if let Some(entry) = history.get_mut(i) {
+    entry.pinned = !entry.pinned;
+    sort_history(history);
+  }
+}
+
+fn write_span(write: &WriteRecord) -> Span<'static> {
+  let rows = match write.rows_affected {
+    Some(1) => " 1 row".to_owned(),
+    Some(n) => format!(" {} rows", n),
+    None => "".to_owned(),
+  };
+  let (outcome, color) = match write.outcome {
+    WriteOutcome::Committed => ("committed", Color::Green),
+    WriteOutcome::Pending => ("awaiting commit", Color::Yellow),
+    WriteOutcome::RolledBack => ("rolled back", Color::Magenta),
+    WriteOutcome::Failed => ("failed", Color::Red),
+  };
+  Span::styled(format!("[{}{}, {}]", write.statement_type.to_uppercase(), rows, outcome), Style::default().fg(color))
+}
+
+fn entry_height(query_lines: &[String], max_lines: usize) -> usize {
+  // timestamp + query lines (+ truncation notice) + divider
+  let truncated = usize::from(query_lines.len() > max_lines);
+  2 + query_lines.len().min(max_lines) + truncated
+}
+
+// returns the [start, end) range of entries to render, keeping the selected
+// entry inside the window and moving the window as little as possible.
+fn visible_window(heights: &[usize], selected: usize, start: usize, available_height: usize) -> (usize, usize) {
+  if heights.is_empty() {
+    return (0, 0);
+  }
+  let selected = selected.min(heights.len() - 1);
+  let mut start = start.min(selected);
+  let mut used = heights[start..=selected].iter().sum::<usize>();
+  while start < selected && used > available_height {
+    used -= heights[start];
+    start += 1;
+  }
+  let mut end = selected + 1;
+  while end < heights.len() && used + heights[end] <= available_height {
+    used += heights[end];
+    end += 1;
+  }
+  (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_visible_window() {
+    let heights = vec![3; 1000];
+    assert_eq!(visible_window(&heights, 0, 0, 10), (0, 3));
+    assert_eq!(visible_window(&heights, 2, 0, 10), (0, 3));
+    assert_eq!(visible_window(&heights, 3, 0, 10), (1, 4));
+    assert_eq!(visible_window(&heights, 500, 1, 10), (498, 501));
+    assert_eq!(visible_window(&heights, 497, 498, 10), (497, 500));
+    assert_eq!(visible_window(&heights, 999, 0, 10), (997, 1000));
+    assert_eq!(visible_window(&[], 0, 0, 10), (0, 0));
+  }
+
+  #[test]
+  fn test_visible_window_tall_entry() {
+    // the selected entry is always rendered, even if it doesn't fit
+    assert_eq!(visible_window(&[3, 20, 3], 1, 0, 10), (1, 2));
+  }
+
+  #[test]
+  fn test_record_run() {
+    let start = chrono::Local::now();
+    let at = |seconds: i64| start + chrono::Duration::seconds(seconds);
+    let queries = |history: &[HistoryEntry]| history.iter().map(|h| h.query_lines[0].clone()).collect::<Vec<_>>();
+    let mut history = vec![];
+    record_run(&mut history, vec!["select 1".to_owned()], at(0), Some(DEFAULT_MAX_HISTORY));
+    record_run(&mut history, vec!["select 2".to_owned()], at(1), Some(DEFAULT_MAX_HISTORY));
+    record_run(&mut history, vec!["select 1".to_owned()], at(2), Some(DEFAULT_MAX_HISTORY));
+    assert_eq!(queries(&history), vec!["select 1", "select 2"]);
+    assert_eq!(history[0].runs, 2);
+
+    toggle_pin(&mut history, 1);
+    assert_eq!(queries(&history), vec!["select 2", "select 1"]);
+    record_run(&mut history, vec!["select 3".to_owned()], at(3), Some(DEFAULT_MAX_HISTORY));
+    assert_eq!(queries(&history), vec!["select 2", "select 3", "select 1"]);
+
+    for i in 0..DEFAULT_MAX_HISTORY {
+      record_run(&mut history, vec![format!("select {}", i + 10)], at(i as i64 + 4), Some(DEFAULT_MAX_HISTORY));
+    }
+    assert_eq!(history.len(), DEFAULT_MAX_HISTORY + 1);
+    assert!(history[0].pinned);
+    assert!(!queries(&history).contains(&"select 1".to_owned()));
+
+    toggle_pin(&mut history, 0);
+    assert_eq!(history.last().map(|h| h.query_lines[0].as_str()), Some("select 2"));
+
+    record_run(&mut history, vec!["select 100".to_owned()], at(100), None);
+    assert_eq!(history.len(), DEFAULT_MAX_HISTORY + 2);
+    record_run(&mut history, vec!["select 10".to_owned()], at(101), Some(2));
+    assert_eq!(queries(&history), vec!["select 10", "select 100"]);
+  }
+
+  #[test]
+  fn test_entry_height() {
+    let lines = vec!["select 1".to_string(); 5];
+    assert_eq!(entry_height(&lines, 10), 7);
+    assert_eq!(entry_height(&lines, 3), 6);
+  }
+}
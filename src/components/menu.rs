@@ -1,6 +1,6 @@
 use std::{
   borrow::BorrowMut,
-  collections::HashMap,
+  collections::{HashMap, HashSet},
   sync::{Arc, Mutex},
   time::Duration,
 };
@@ -22,8 +22,74 @@ use crate::{
   database::{get_headers, row_to_json, row_to_vec, DbError, Rows},
   focus::Focus,
   tui::Event,
+  utils::{fuzzy_match, fuzzy_score},
 };
 
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ObjectType {
+  #[default]
+  Table,
+  View,
+  MaterializedView,
+  ForeignTable,
+  Function,
+  Procedure,
+}
+
+impl ObjectType {
+  fn from_menu_value(value: &str) -> Self {
+    match value {
+      "view" => ObjectType::View,
+      "materialized view" => ObjectType::MaterializedView,
+      "foreign table" => ObjectType::ForeignTable,
+      "function" => ObjectType::Function,
+      "procedure" => ObjectType::Procedure,
+      _ => ObjectType::Table,
+    }
+  }
+
+  fn label(&self) -> &'static str {
+    match self {
+      ObjectType::Table => "tables",
+      ObjectType::View => "views",
+      ObjectType::MaterializedView => "materialized views",
+      ObjectType::ForeignTable => "foreign tables",
+      ObjectType::Function => "functions",
+      ObjectType::Procedure => "procedures",
+    }
+  }
+
+  fn marker(&self) -> &'static str {
+    match self {
+      ObjectType::Table => "",
+      ObjectType::View => " (view)",
+      ObjectType::MaterializedView => " (mat. view)",
+      ObjectType::ForeignTable => " (foreign)",
+      ObjectType::Function => " (function)",
+      ObjectType::Procedure => " (procedure)",
+    }
+  }
+
+  fn is_routine(&self) -> bool {
+    matches!(self, ObjectType::Function | ObjectType::Procedure)
+  }
+
+  // all -> tables -> views -> materialized views -> foreign tables -> functions -> procedures -> all
+  fn next_filter(filter: Option<ObjectType>) -> Option<ObjectType> {
+    match filter {
+      None => Some(ObjectType::Table),
+      Some(ObjectType::Table) => Some(ObjectType::View),
+      Some(ObjectType::View) => Some(ObjectType::MaterializedView),
+      Some(ObjectType::MaterializedView) => Some(ObjectType::ForeignTable),
+      Some(ObjectType::ForeignTable) => Some(ObjectType::Function),
+      Some(ObjectType::Function) => Some(ObjectType::Procedure),
+      Some(ObjectType::Procedure) => None,
+    }
+  }
+}
+
 #[derive(Debug, Clone, Default, Eq, PartialEq)]
 pub enum MenuFocus {
   #[default]
@@ -33,6 +99,10 @@ pub enum MenuFocus {
 
 pub trait SettableTableList<'a> {
   fn set_table_list(&mut self, data: Option<Result<Rows, DbError>>);
+  // (schema, table) for every loaded relation that can be previewed
+  fn get_table_list(&self) -> Vec<(String, String)>;
+  // whether keys are going into the search box
+  fn is_typing_search(&self) -> bool;
 }
 
 pub trait MenuComponent<'a, DB: Database>: Component<DB> + SettableTableList<'a> {}
@@ -45,12 +115,23 @@ impl<'a, T, DB: Database> MenuComponent<'a, DB> for T where T: Component<DB> + S
 pub struct Menu {
   command_tx: Option<UnboundedSender<Action>>,
   config: Config,
-  table_map: IndexMap<String, Vec<String>>,
+  table_map: IndexMap<String, Vec<(String, ObjectType)>>,
+  object_filter: Option<ObjectType>,
   schema_index: usize,
   list_state: ListState,
   menu_focus: MenuFocus,
   search: Option<String>,
   search_focused: bool,
+  // which earlier search is shown while stepping through them with up/down
+  search_recall: Option<usize>,
+  // (schema, table) pairs the current user can't select from
+  restricted: HashSet<(String, String)>,
+  // (schema, partition) -> the partitioned table it belongs to
+  partition_of: HashMap<(String, String), String>,
+  // (schema, table) -> how many partitions it has
+  partition_counts: HashMap<(String, String), usize>,
+  // (schema, table) partitioned tables whose partitions are listed
+  expanded: HashSet<(String, String)>,
 }
 
 impl Menu {
@@ -59,11 +140,17 @@ impl Menu {
       command_tx: None,
       config: Config::default(),
       table_map: IndexMap::new(),
+      object_filter: None,
       schema_index: 0,
       list_state: ListState::default(),
       menu_focus: MenuFocus::default(),
       search: None,
       search_focused: false,
+      search_recall: None,
+      restricted: HashSet::new(),
+      partition_of: HashMap::new(),
+      partition_counts: HashMap::new(),
+      expanded: HashSet::new(),
     }
   }
 
@@ -85,17 +172,7 @@ impl Menu {
     match self.menu_focus {
       MenuFocus::Tables => {
         if let Some(i) = self.list_state.selected() {
-          let tables = self.table_map.get_index(self.schema_index).unwrap().1.to_owned();
-          let filtered_tables: Vec<String> = tables
-            .into_iter()
-            .filter(|t| {
-              if let Some(search) = self.search.as_ref() {
-                t.to_lowercase().contains(search.to_lowercase().trim())
-              } else {
-                true
-              }
-            })
-            .collect();
+          let filtered_tables = self.filtered_tables(self.schema_index);
           self.list_state = ListState::default()
             .with_selected(Some(i.saturating_add(1).clamp(0, filtered_tables.len().saturating_sub(1))));
         }
@@ -121,17 +198,7 @@ impl Menu {
     match self.menu_focus {
       MenuFocus::Tables => {
         if let Some(i) = self.list_state.selected() {
-          let tables = self.table_map.get_index(self.schema_index).unwrap().1.to_owned();
-          let filtered_tables: Vec<String> = tables
-            .into_iter()
-            .filter(|t| {
-              if let Some(search) = self.search.as_ref() {
-                t.to_lowercase().contains(search.to_lowercase().trim())
-              } else {
-                true
-              }
-            })
-            .collect();
+          let filtered_tables = self.filtered_tables(self.schema_index);
           self.list_state = ListState::default().with_selected(Some(filtered_tables.len().saturating_sub(1)));
         }
       },
@@ -152,27 +219,195 @@ impl Menu {
     }
   }
 
+  // tables in the schema at the given index that match the search and object type filter.
+  // partitions are listed under their table once it is expanded, or on their own while searching
+  fn filtered_tables(&self, schema_index: usize) -> Vec<(String, ObjectType)> {
+    let Some((schema, tables)) = self.table_map.get_index(schema_index) else {
+      return vec![];
+    };
+    let searching = self.search.as_ref().is_some_and(|search| !search.trim().is_empty());
+    let mut listed = vec![];
+    for (table, object_type) in tables {
+      if searching {
+        listed.push((table.clone(), *object_type));
+      } else if !self.partition_of.contains_key(&(schema.clone(), table.clone())) {
+        listed.push((table.clone(), *object_type));
+        self.push_partitions(schema, tables, table, &mut listed);
+      }
+    }
+    let listed =
+      listed.into_iter().filter(|(_, object_type)| self.object_filter.map_or(true, |filter| filter == *object_type));
+    match self.search.as_ref().filter(|_| searching) {
+      Some(search) => {
+        let mut scored =
+          listed.filter_map(|(t, object_type)| Some((fuzzy_score(search, &t)?, t, object_type))).collect::<Vec<_>>();
+        // best matches first; sort_by_key is stable, so ties keep their listed order
+        scored.sort_by_key(|(score, ..)| -score);
+        scored.into_iter().map(|(_, t, object_type)| (t, object_type)).collect()
+      },
+      None => listed.collect(),
+    }
+  }
+
+  // lists the partitions of an expanded table, and theirs in turn when they are partitioned too
+  fn push_partitions(
+    &self,
+    schema: &str,
+    tables: &[(String, ObjectType)],
+    parent: &str,
+    listed: &mut Vec<(String, ObjectType)>,
+  ) {
+    if !self.expanded.contains(&(schema.to_owned(), parent.to_owned())) {
+      return;
+    }
+    for (table, object_type) in tables {
+      if self.partition_of.get(&(schema.to_owned(), table.clone())).is_some_and(|p| p == parent) {
+        listed.push((table.clone(), *object_type));
+        self.push_partitions(schema, tables, table, listed);
+      }
+    }
+  }
+
+  // how many partitioned tables a table is nested under
+  fn partition_depth(&self, schema: &str, table: &str) -> usize {
+    let mut depth = 0;
+    let mut table = table.to_owned();
+    while let Some(parent) = self.partition_of.get(&(schema.to_owned(), table)) {
+      depth += 1;
+      table = parent.clone();
+    }
+    depth
+  }
+
+  // shows or hides the selected table's partitions. on a partition, its table is collapsed and selected
+  pub fn toggle_partitions(&mut self) {
+    let Some(schema) = self.table_map.get_index(self.schema_index).map(|(schema, _)| schema.clone()) else {
+      return;
+    };
+    let Some((table, _)) =
+      self.list_state.selected().and_then(|i| self.filtered_tables(self.schema_index).get(i).cloned())
+    else {
+      return;
+    };
+    let key = (schema.clone(), table);
+    if self.partition_counts.contains_key(&key) {
+      if !self.expanded.remove(&key) {
+        self.expanded.insert(key);
+      }
+    } else if let Some(parent) = self.partition_of.get(&key).cloned() {
+      self.expanded.remove(&(schema, parent.clone()));
+      let selected = self.filtered_tables(self.schema_index).iter().position(|(table, _)| *table == parent);
+      self.list_state = ListState::default().with_selected(selected.or(Some(0)));
+    }
+  }
+
+  pub fn cycle_object_filter(&mut self) {
+    self.object_filter = ObjectType::next_filter(self.object_filter);
+    if self.menu_focus == MenuFocus::Tables {
+      self.list_state = ListState::default().with_selected(Some(0));
+    }
+  }
+
+  // steps through earlier searches (oldest first), newest first like a shell's history.
+  // stepping past the newest clears the search
+  fn recall_search(&mut self, searches: &[String], older: bool) {
+    let index = match (self.search_recall, older) {
+      (None, false) => return,
+      (None, true) => searches.len().checked_sub(1),
+      (Some(i), true) => Some(i.saturating_sub(1)),
+      (Some(i), false) => Some(i + 1).filter(|i| *i < searches.len()),
+    };
+    if index.is_none() && older {
+      return;
+    }
+    self.search_recall = index;
+    self.search = Some(index.and_then(|i| searches.get(i).cloned()).unwrap_or_default());
+    self.list_state = ListState::default().with_selected(Some(0));
+  }
+
   pub fn reset_search(&mut self) {
     self.search = None;
     self.search_focused = false;
+    self.search_recall = None;
     self.list_state = ListState::default().with_selected(Some(0));
   }
 }
 
 impl SettableTableList<'_> for Menu {
+  fn get_table_list(&self) -> Vec<(String, String)> {
+    self
+      .table_map
+      .iter()
+      .flat_map(|(schema, tables)| {
+        tables
+          .iter()
+          .filter(|(_, object_type)| !object_type.is_routine())
+          .map(move |(table, _)| (schema.clone(), table.clone()))
+      })
+      .collect()
+  }
+
+  fn is_typing_search(&self) -> bool {
+    self.search.is_some() && self.search_focused
+  }
+
   fn set_table_list(&mut self, data: Option<Result<Rows, DbError>>) {
     log::info!("setting menu table list");
-    self.table_map = IndexMap::new();
     match data {
       Some(Ok(rows)) => {
-        rows.rows.iter().for_each(|row| {
+        // a refresh keeps the selected schema and table when they still exist
+        let previous_schema = self.table_map.get_index(self.schema_index).map(|(schema, _)| schema.clone());
+        let previous_table = self
+          .list_state
+          .selected()
+          .and_then(|i| self.filtered_tables(self.schema_index).get(i).map(|(table, _)| table.clone()));
+        let previous_focus = self.menu_focus.clone();
+        self.table_map = IndexMap::new();
+        self.restricted = HashSet::new();
+        self.partition_of = HashMap::new();
+        self.partition_counts = HashMap::new();
+        let schema_filter = self.config.settings.schema_filter.as_deref().filter(|f| !f.trim().is_empty());
+        rows.rows.iter().filter(|row| schema_filter.map_or(true, |f| schema_matches(f, &row[0]))).for_each(|row| {
           let schema = row[0].clone();
           let table = row[1].clone();
           if !self.table_map.contains_key(&schema) {
             self.table_map.insert(schema.clone(), vec![]);
           }
-          self.table_map.get_mut(&schema).unwrap().push(table.clone());
+          let object_type = ObjectType::from_menu_value(row.get(2).map_or("table", |t| t.as_str()));
+          // drivers that can't tell leave the column out, and everything is assumed readable
+          if row.get(3).is_some_and(|can_select| can_select == "false") {
+            self.restricted.insert((schema.clone(), table.clone()));
+          }
+          // only postgres reports partitions, which are grouped under their table
+          if let Some(parent) = row.get(4).filter(|parent| !parent.is_empty()) {
+            self.partition_of.insert((schema.clone(), table.clone()), parent.clone());
+            *self.partition_counts.entry((schema.clone(), parent.clone())).or_default() += 1;
+          }
+          self.table_map.get_mut(&schema).unwrap().push((table.clone(), object_type));
         });
+        self.table_map.sort_keys();
+        if let Some(schema_index) = previous_schema.and_then(|schema| self.table_map.get_index_of(&schema)) {
+          self.schema_index = schema_index;
+          self.menu_focus = if self.table_map.keys().len() == 1 { MenuFocus::Tables } else { previous_focus };
+          self.list_state = match self.menu_focus {
+            MenuFocus::Tables => {
+              let filtered_tables = self.filtered_tables(self.schema_index);
+              let selected = previous_table
+                .and_then(|previous| filtered_tables.iter().position(|(table, _)| *table == previous))
+                .unwrap_or(0);
+              ListState::default().with_selected(Some(selected))
+            },
+            MenuFocus::Schema => ListState::default(),
+          };
+          return;
+        }
+        self.schema_index = self
+          .config
+          .settings
+          .default_schema
+          .as_ref()
+          .and_then(|default_schema| self.table_map.get_index_of(default_schema))
+          .unwrap_or(0);
         if self.table_map.keys().len() == 1 {
           self.menu_focus = MenuFocus::Tables;
           self.list_state = ListState::default().with_selected(Some(0));
@@ -189,6 +424,61 @@ impl SettableTableList<'_> for Menu {
   }
 }
 
+fn format_age(age: chrono::Duration) -> String {
+  match age.num_seconds() {
+    s if s < 10 => "just now".to_owned(),
+    s if s < 60 => format!("{}s ago", s),
+    s if s < 60 * 60 => format!("{}m ago", s / 60),
+    s => format!("{}h ago", s / (60 * 60)),
+  }
+}
+
+// the filter is a comma-separated list of schema names, where `*` matches any
+// run of characters (ex. "public,app_*")
+fn schema_matches(filter: &str, schema: &str) -> bool {
+  filter.split(',').map(str::trim).filter(|p| !p.is_empty()).any(|pattern| wildcard_match(pattern, schema))
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+  let pattern = pattern.to_lowercase();
+  let name = name.to_lowercase();
+  let mut parts = pattern.split('*');
+  let first = parts.next().unwrap_or_default();
+  let Some(mut rest) = name.strip_prefix(first) else {
+    return false;
+  };
+  let mut parts = parts.collect::<Vec<_>>();
+  let Some(last) = parts.pop() else {
+    return rest.is_empty();
+  };
+  for part in parts {
+    match rest.find(part) {
+      Some(i) => rest = &rest[i + part.len()..],
+      None => return false,
+    }
+  }
+  rest.len() >= last.len() && rest.ends_with(last)
+}
+
+// splits a table name into spans, with the characters the search matched highlighted
+fn highlight_matches(name: &str, search: Option<&str>) -> Vec<Span<'static>> {
+  let matched = search.and_then(|search| fuzzy_match(search, name)).map(|(_, matched)| matched).unwrap_or_default();
+  if matched.is_empty() {
+    return vec![Span::raw(name.to_owned())];
+  }
+  name
+    .chars()
+    .enumerate()
+    .map(|(i, c)| {
+      if matched.contains(&i) {
+        Span::styled(c.to_string(), Style::new().yellow().bold())
+      } else {
+        Span::raw(c.to_string())
+      }
+    })
+    .collect()
+}
+
 impl<DB: Database> Component<DB> for Menu {
   fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
     self.command_tx = Some(tx);
@@ -223,12 +513,17 @@ impl<DB: Database> Component<DB> for Menu {
     match key.code {
       KeyCode::Right => self.change_focus(MenuFocus::Tables),
       KeyCode::Left => self.change_focus(MenuFocus::Schema),
+      KeyCode::Down | KeyCode::Up if self.search.is_some() && self.search_focused => {
+        let searches = app_state.search_history.get(&Focus::Menu).map_or(&[][..], |searches| searches.as_slice());
+        self.recall_search(searches, key.code == KeyCode::Up);
+      },
       KeyCode::Down => self.scroll_down(),
       KeyCode::Up => self.scroll_up(),
       KeyCode::Char(c) => {
         if self.search.is_some() && self.search_focused {
           if let Some(search) = self.search.as_mut() {
             search.push(c);
+            self.search_recall = None;
             self.list_state = ListState::default().with_selected(Some(0));
           }
         } else {
@@ -246,20 +541,30 @@ impl<DB: Database> Component<DB> for Menu {
             KeyCode::Char('g') => self.scroll_top(),
             KeyCode::Char('G') => self.scroll_bottom(),
             KeyCode::Char('R') => self.command_tx.as_ref().unwrap().send(Action::LoadMenu)?,
+            KeyCode::Char('t') => self.cycle_object_filter(),
+            KeyCode::Char('p') => self.toggle_partitions(),
+            KeyCode::Char('S') => {
+              self.command_tx.as_ref().unwrap().send(Action::PreviewSettings(self.search.clone()))?;
+            },
+            KeyCode::Char('A') => self.command_tx.as_ref().unwrap().send(Action::PreviewActivity)?,
+            KeyCode::Char('a') => self.command_tx.as_ref().unwrap().send(Action::OpenAttachDatabase)?,
+            KeyCode::Char('E') => {
+              if let Some((schema, _)) = self.table_map.get_index(self.schema_index) {
+                self.command_tx.as_ref().unwrap().send(Action::ExportSchema(schema.clone()))?;
+              }
+            },
+            KeyCode::Char('d') => {
+              if let Some((schema, _)) = self.table_map.get_index(self.schema_index) {
+                self.command_tx.as_ref().unwrap().send(Action::DetachDatabase(schema.clone()))?;
+              }
+            },
             KeyCode::Char('1') | KeyCode::Char('2') | KeyCode::Char('3') | KeyCode::Char('4') => {
-              if let Some(selected) = self.list_state.selected() {
-                let (schema, tables) = self.table_map.get_index(self.schema_index).unwrap();
-                let filtered_tables: Vec<String> = tables
-                  .iter()
-                  .filter(|t| {
-                    if let Some(search) = self.search.as_ref() {
-                      t.to_lowercase().contains(search.to_lowercase().trim())
-                    } else {
-                      true
-                    }
-                  })
-                  .cloned()
-                  .collect();
+              let filtered_tables = self.filtered_tables(self.schema_index);
+              if let Some((table, object_type)) = self.list_state.selected().and_then(|i| filtered_tables.get(i)) {
+                if object_type.is_routine() {
+                  return Ok(None);
+                }
+                let schema = self.table_map.get_index(self.schema_index).unwrap().0;
                 self.command_tx.as_ref().unwrap().send(Action::MenuPreview(
                   match key.code {
                     KeyCode::Char('1') => MenuPreview::Columns,
@@ -269,7 +574,7 @@ impl<DB: Database> Component<DB> for Menu {
                     _ => MenuPreview::Rows,
                   },
                   schema.clone(),
-                  filtered_tables[selected].clone(),
+                  table.clone(),
                 ))?;
               }
             },
@@ -280,26 +585,18 @@ impl<DB: Database> Component<DB> for Menu {
       KeyCode::Enter => {
         if self.search.is_some() && self.search_focused {
           self.search_focused = false;
+          self.search_recall = None;
+          if let Some(search) = &self.search {
+            self.command_tx.as_ref().unwrap().send(Action::RecordSearch(Focus::Menu, search.clone()))?;
+          }
         } else if self.menu_focus == MenuFocus::Schema {
           self.change_focus(MenuFocus::Tables);
-        } else if let Some(selected) = self.list_state.selected() {
-          let (schema, tables) = self.table_map.get_index(self.schema_index).unwrap();
-          let filtered_tables: Vec<String> = tables
-            .iter()
-            .filter(|t| {
-              if let Some(search) = self.search.as_ref() {
-                t.to_lowercase().contains(search.to_lowercase().trim())
-              } else {
-                true
-              }
-            })
-            .cloned()
-            .collect();
-          self.command_tx.as_ref().unwrap().send(Action::MenuPreview(
-            MenuPreview::Rows,
-            schema.clone(),
-            filtered_tables[selected].clone(),
-          ))?;
+        } else if let Some((table, object_type)) =
+          self.list_state.selected().and_then(|i| self.filtered_tables(self.schema_index).get(i).cloned())
+        {
+          let schema = self.table_map.get_index(self.schema_index).unwrap().0;
+          let preview = if object_type.is_routine() { MenuPreview::Definition } else { MenuPreview::Rows };
+          self.command_tx.as_ref().unwrap().send(Action::MenuPreview(preview, schema.clone(), table))?;
         }
       },
       KeyCode::Esc => self.reset_search(),
@@ -308,6 +605,7 @@ impl<DB: Database> Component<DB> for Menu {
           if let Some(search) = self.search.as_mut() {
             if !search.is_empty() {
               search.pop();
+              self.search_recall = None;
               self.list_state = ListState::default().with_selected(Some(0));
             } else {
               self.reset_search();
@@ -324,7 +622,44 @@ impl<DB: Database> Component<DB> for Menu {
 
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect, app_state: &AppState<'_, DB>) -> Result<()> {
     let focused = app_state.focus == Focus::Menu;
+    // once loaded, the cached tree stays up while it refreshes in the background
+    let spinner_frame = chrono::Utc::now().timestamp_subsec_millis() as usize / 100 % SPINNER_FRAMES.len();
+    if app_state.schema_task.is_some() && self.table_map.is_empty() {
+      f.render_widget(
+        Text::styled(
+          format!("{} loading tables...", SPINNER_FRAMES[spinner_frame]),
+          self.config.border_style(Focus::Menu, focused),
+        ),
+        area,
+      );
+      return Ok(());
+    }
+    let loaded = match (&app_state.schema_task, app_state.menu_loaded_at) {
+      (Some(_), _) => format!("{} refreshing", SPINNER_FRAMES[spinner_frame]),
+      (None, Some(loaded_at)) => format!("loaded {}", format_age(chrono::Utc::now().signed_duration_since(loaded_at))),
+      (None, None) => "".to_owned(),
+    };
     let parent_block = Block::default();
+    let selected_schema_tables = self.filtered_tables(self.schema_index);
+    // (indent, partition count) for each listed table
+    let partition_labels = self.table_map.get_index(self.schema_index).map_or(vec![], |(schema, _)| {
+      selected_schema_tables
+        .iter()
+        .map(|(table, _)| {
+          let key = (schema.clone(), table.clone());
+          let indent = match self.partition_depth(schema, table) {
+            0 => "".to_owned(),
+            depth => format!("{}↳ ", "  ".repeat(depth - 1)),
+          };
+          let partitions = match self.partition_counts.get(&key) {
+            Some(count) if self.expanded.contains(&key) => format!(" ({} partitions) ▾", count),
+            Some(count) => format!(" ({} partitions) ▸", count),
+            None => "".to_owned(),
+          };
+          (indent, partitions)
+        })
+        .collect::<Vec<(String, String)>>()
+    });
     let stable_keys = self.table_map.keys().enumerate();
     let mut constraints: Vec<Constraint> = stable_keys
       .clone()
@@ -347,7 +682,7 @@ impl<DB: Database> Component<DB> for Menu {
           if !focused {
             Style::new().dim()
           } else if self.search_focused {
-            Style::default().fg(Color::Yellow)
+            self.config.style(Focus::Menu, "accent")
           } else {
             Style::default()
           },
@@ -363,36 +698,48 @@ impl<DB: Database> Component<DB> for Menu {
             .title(format!(" 󰦄  {} <alt+1> (schema) ", k))
             .borders(Borders::ALL)
             .border_style(if focused && self.menu_focus == MenuFocus::Schema {
-              Style::default().fg(Color::Green)
+              self.config.style(Focus::Menu, "focused")
             } else if focused {
               Style::default()
             } else {
-              Style::new().dim()
+              self.config.style(Focus::Menu, "unfocused")
             })
+            .title_bottom(format!(" [t] {} ", self.object_filter.map_or("all objects", |f| f.label())))
+            .title_bottom(Line::from(format!(" {} ", loaded)).right_aligned())
             .padding(Padding { left: 0, right: 1, top: 0, bottom: 0 });
           let block_margin = layout[layout_index].inner(Margin { vertical: 1, horizontal: 0 });
-          let tables = self.table_map.get_key_value(k).unwrap().1.clone();
-          let filtered_tables: Vec<String> = tables
-            .into_iter()
-            .filter(|t| {
-              if let Some(search) = self.search.as_ref() {
-                t.to_lowercase().contains(search.to_lowercase().trim())
-              } else {
-                true
-              }
-            })
-            .collect();
+          let filtered_tables = selected_schema_tables.clone();
           let table_length = filtered_tables.len();
           let available_height = block.inner(parent_block.inner(area)).height as usize;
           let selected_table_index = self.list_state.selected();
           let filtered_tables_items: Vec<ListItem> = filtered_tables
             .into_iter()
             .enumerate()
-            .map(|(i, t)| {
+            .map(|(i, (t, object_type))| {
               let is_selected = selected_table_index == Some(i);
-              if is_selected && focused && !self.search_focused {
+              let restricted = self.restricted.contains(&(k.to_owned(), t.clone()));
+              let (indent, partitions) = partition_labels.get(i).cloned().unwrap_or_default();
+              let mut spans = vec![Span::raw(indent)];
+              spans.extend(highlight_matches(&t, self.search.as_deref()));
+              spans.extend([
+                Span::styled(object_type.marker(), Style::new().dim()),
+                Span::styled(partitions, Style::new().dim()),
+                Span::styled(if restricted { " (no access)" } else { "" }, Style::new().dim()),
+              ]);
+              let name = Line::from(spans);
+              let name = if restricted { name.dim() } else { name };
+              if is_selected && focused && !self.search_focused && object_type.is_routine() {
                 ListItem::new(Text::from(vec![
-                  Line::from(t),
+                  name,
+                  Line::from(if app_state.query_task.is_some() {
+                    "└[...] definition"
+                  } else {
+                    "└[<enter>] definition"
+                  }),
+                ]))
+              } else if is_selected && focused && !self.search_focused {
+                ListItem::new(Text::from(vec![
+                  name,
                   Line::from(if app_state.query_task.is_some() { "├[...] rows" } else { "├[<enter>] rows" }),
                   Line::from(if app_state.query_task.is_some() { "├[...] columns" } else { "├[1] columns" }),
                   Line::from(if app_state.query_task.is_some() {
@@ -408,24 +755,23 @@ impl<DB: Database> Component<DB> for Menu {
                   }),
                 ]))
               } else {
-                ListItem::new(t)
+                ListItem::new(name)
               }
             })
             .collect();
           let list = List::default().items(filtered_tables_items).block(block).highlight_style(
-            Style::default()
-              .fg(if focused && !self.search_focused && self.menu_focus == MenuFocus::Tables {
-                Color::Green
-              } else {
-                Color::Gray
-              })
-              .add_modifier(if focused { Modifier::BOLD } else { Modifier::REVERSED }),
+            if focused && !self.search_focused && self.menu_focus == MenuFocus::Tables {
+              self.config.style(Focus::Menu, "focused")
+            } else {
+              Style::default().fg(Color::Gray)
+            }
+            .add_modifier(if focused { Modifier::BOLD } else { Modifier::REVERSED }),
           );
           f.render_stateful_widget(list, layout[layout_index], &mut self.list_state);
           let vertical_scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
             .symbols(scrollbar::VERTICAL)
             .style(if focused && !self.search_focused && self.menu_focus == MenuFocus::Tables {
-              Style::default().fg(Color::Green)
+              self.config.style(Focus::Menu, "focused")
             } else {
               Style::default()
             });
@@ -467,3 +813,166 @@ impl<DB: Database> Component<DB> for Menu {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_filtered_tables() {
+    let mut menu = Menu::new();
+    menu.table_map.insert("public".to_owned(), vec![
+      ("users".to_owned(), ObjectType::Table),
+      ("active_users".to_owned(), ObjectType::View),
+      ("user_stats".to_owned(), ObjectType::MaterializedView),
+    ]);
+    let names = |menu: &Menu| menu.filtered_tables(0).into_iter().map(|(t, _)| t).collect::<Vec<String>>();
+    assert_eq!(names(&menu), vec!["users", "active_users", "user_stats"]);
+    menu.cycle_object_filter();
+    assert_eq!(names(&menu), vec!["users"]);
+    menu.cycle_object_filter();
+    assert_eq!(names(&menu), vec!["active_users"]);
+    menu.cycle_object_filter();
+    menu.search = Some("stats".to_owned());
+    assert_eq!(names(&menu), vec!["user_stats"]);
+    menu.cycle_object_filter();
+    assert!(names(&menu).is_empty());
+    while menu.object_filter.is_some() {
+      menu.cycle_object_filter();
+    }
+    assert_eq!(names(&menu), vec!["user_stats"]);
+    assert!(menu.filtered_tables(1).is_empty());
+    menu.search = Some("usr".to_owned());
+    assert_eq!(names(&menu), vec!["users", "user_stats", "active_users"]);
+  }
+
+  #[test]
+  fn test_highlight_matches() {
+    let highlighted = |search| {
+      highlight_matches("user_profiles", search)
+        .into_iter()
+        .filter(|span| span.style != Style::new())
+        .map(|span| span.content.into_owned())
+        .collect::<String>()
+    };
+    assert_eq!(highlighted(Some("usrprof")), "usrprof");
+    assert_eq!(highlighted(None), "");
+    assert_eq!(highlight_matches("users", Some("xyz")), vec![Span::raw("users")]);
+  }
+
+  #[test]
+  fn test_refresh_keeps_selection() {
+    let rows = |tables: &[&str]| {
+      Rows {
+        headers: vec![],
+        rows: tables.iter().map(|t| vec!["public".to_owned(), t.to_string(), "table".to_owned()]).collect(),
+        rows_affected: None,
+      }
+    };
+    let mut menu = Menu::new();
+    menu.set_table_list(Some(Ok(rows(&["a", "b", "c"]))));
+    menu.scroll_down();
+    menu.scroll_down();
+    assert_eq!(menu.list_state.selected(), Some(2));
+    menu.set_table_list(Some(Ok(rows(&["0", "a", "b", "c"]))));
+    assert_eq!(menu.list_state.selected(), Some(3));
+    menu.set_table_list(Some(Err(DbError::Left(sqlx::Error::PoolTimedOut))));
+    assert_eq!(menu.filtered_tables(0).len(), 4);
+  }
+
+  #[test]
+  fn test_restricted_tables() {
+    let row = |table: &str, can_select: Option<&str>| {
+      let mut row = vec!["public".to_owned(), table.to_owned(), "table".to_owned()];
+      row.extend(can_select.map(|c| c.to_owned()));
+      row
+    };
+    let mut menu = Menu::new();
+    menu.set_table_list(Some(Ok(Rows {
+      headers: vec![],
+      rows: vec![row("open", Some("true")), row("secret", Some("false")), row("unknown", None)],
+      rows_affected: None,
+    })));
+    assert_eq!(menu.restricted, HashSet::from([("public".to_owned(), "secret".to_owned())]));
+  }
+
+  #[test]
+  fn test_partitions() {
+    let row = |table: &str, partition_of: &str| {
+      ["public", table, "table", "true", partition_of].iter().map(|v| v.to_string()).collect::<Vec<String>>()
+    };
+    let mut menu = Menu::new();
+    menu.set_table_list(Some(Ok(Rows {
+      headers: vec![],
+      rows: vec![
+        row("orders", ""),
+        row("orders_2023", "orders"),
+        row("orders_2024", "orders"),
+        row("orders_2024_q1", "orders_2024"),
+        row("users", ""),
+      ],
+      rows_affected: None,
+    })));
+    let names = |menu: &Menu| menu.filtered_tables(0).into_iter().map(|(t, _)| t).collect::<Vec<String>>();
+    assert_eq!(names(&menu), vec!["orders", "users"]);
+    assert_eq!(menu.partition_counts.get(&("public".to_owned(), "orders".to_owned())), Some(&2));
+    menu.toggle_partitions();
+    assert_eq!(names(&menu), vec!["orders", "orders_2023", "orders_2024", "users"]);
+    menu.scroll_down();
+    menu.scroll_down();
+    menu.toggle_partitions();
+    assert_eq!(names(&menu), vec!["orders", "orders_2023", "orders_2024", "orders_2024_q1", "users"]);
+    assert_eq!(menu.partition_depth("public", "orders_2024_q1"), 2);
+    menu.scroll_down();
+    menu.toggle_partitions();
+    assert_eq!(names(&menu), vec!["orders", "orders_2023", "orders_2024", "users"]);
+    assert_eq!(menu.list_state.selected(), Some(2));
+    menu.search = Some("q1".to_owned());
+    assert_eq!(names(&menu), vec!["orders_2024_q1"]);
+  }
+
+  #[test]
+  fn test_format_age() {
+    assert_eq!(format_age(chrono::Duration::seconds(3)), "just now");
+    assert_eq!(format_age(chrono::Duration::seconds(42)), "42s ago");
+    assert_eq!(format_age(chrono::Duration::seconds(150)), "2m ago");
+    assert_eq!(format_age(chrono::Duration::seconds(7200)), "2h ago");
+  }
+
+  #[test]
+  fn test_schema_matches() {
+    assert!(schema_matches("public", "public"));
+    assert!(schema_matches("public", "PUBLIC"));
+    assert!(!schema_matches("public", "public2"));
+    assert!(schema_matches("app_*", "app_billing"));
+    assert!(schema_matches("app_*", "app_"));
+    assert!(!schema_matches("app_*", "myapp_billing"));
+    assert!(schema_matches("*_audit", "billing_audit"));
+    assert!(schema_matches("a*b*c", "aXbYc"));
+    assert!(!schema_matches("a*b*c", "aXcYb"));
+    assert!(!schema_matches("ab*ba", "aba"));
+    assert!(schema_matches("public, app_*", "app_users"));
+    assert!(!schema_matches("public, app_*", "pg_temp"));
+    assert!(!schema_matches("", "public"));
+  }
+
+  #[test]
+  fn test_recall_search() {
+    let searches = vec!["users".to_owned(), "orders".to_owned()];
+    let mut menu = Menu::new();
+    menu.search = Some("draft".to_owned());
+    menu.recall_search(&searches, false);
+    assert_eq!(menu.search.as_deref(), Some("draft"));
+    menu.recall_search(&searches, true);
+    assert_eq!(menu.search.as_deref(), Some("orders"));
+    menu.recall_search(&searches, true);
+    menu.recall_search(&searches, true);
+    assert_eq!(menu.search.as_deref(), Some("users"));
+    menu.recall_search(&searches, false);
+    assert_eq!(menu.search.as_deref(), Some("orders"));
+    menu.recall_search(&searches, false);
+    assert_eq!((menu.search.as_deref(), menu.search_recall), (Some(""), None));
+    menu.recall_search(&[], true);
+    assert_eq!(menu.search.as_deref(), Some(""));
+  }
+}
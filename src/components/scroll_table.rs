@@ -1,12 +1,11 @@
-use std::{borrow::BorrowMut, cell::RefCell};
+use std::{borrow::BorrowMut, cell::RefCell, collections::BTreeSet, ops::Range};
 
 use color_eyre::eyre::Result;
 use ratatui::{
-  buffer::Cell,
   prelude::*,
   widgets::{
-    Block, ScrollDirection as RatatuiScrollDir, Scrollbar, ScrollbarOrientation, ScrollbarState, StatefulWidgetRef,
-    Table, TableState, WidgetRef,
+    Block, Cell, Row, ScrollDirection as RatatuiScrollDir, Scrollbar, ScrollbarOrientation, ScrollbarState,
+    StatefulWidgetRef, Table, TableState, WidgetRef,
   },
 };
 use sqlx::{Database, Executor, Pool};
@@ -31,54 +30,121 @@ pub enum SelectionMode {
 
 #[derive(Debug, Clone, Default)]
 pub struct ScrollTable<'a> {
-  table: Table<'a>,
+  // only the cells in view get laid out when drawing, so wide or long results stay cheap
+  header: Vec<Cell<'a>>,
+  // a window of the rows, starting at rows_start, which the owner keeps around the ones in view
+  rows: Vec<Vec<Cell<'a>>>,
+  rows_start: usize,
+  row_count: usize,
   parent_area: Rect,
   block: Option<Block<'a>>,
   pg_height: u16,
-  requested_width: u16,
+  requested_width: usize,
   column_width: u16,
-  max_height: u16,
-  x_offset: u16,
+  x_offset: usize,
   y_offset: usize,
-  max_x_offset: u16,
+  max_x_offset: usize,
   max_y_offset: usize,
   selection_mode: Option<SelectionMode>,
+  marked_rows: BTreeSet<usize>,
+  row_style: Style,
+  cell_style: Style,
+  marked_style: Style,
+  // the underlying columns shown, in display order. empty shows every column as is
+  visible_columns: Vec<usize>,
+  row_numbers: bool,
+  // a row pinned under the others, which scrolls sideways with them
+  footer: Option<Vec<Cell<'a>>>,
 }
 
 impl<'a> ScrollTable<'a> {
   pub fn new() -> Self {
     Self {
-      table: Table::default(),
+      header: vec![],
+      rows: vec![],
+      rows_start: 0,
+      row_count: 0,
       parent_area: Rect::new(0, 0, 0, 0),
       block: None,
       pg_height: 0,
       requested_width: 0,
       column_width: 0,
-      max_height: 0,
       x_offset: 0,
       y_offset: 0,
       max_x_offset: 0,
       max_y_offset: 0,
       selection_mode: None,
+      marked_rows: BTreeSet::new(),
+      row_style: Style::default(),
+      cell_style: Style::default(),
+      marked_style: Style::default(),
+      visible_columns: vec![],
+      row_numbers: false,
+      footer: None,
     }
   }
 
-  pub fn set_table(&mut self, table: Table<'a>, column_count: usize, row_count: usize, column_width: u16) -> &mut Self {
-    let requested_width = column_width.saturating_mul(column_count as u16);
-    let max_height = u16::MAX.saturating_div(std::cmp::max(1, requested_width));
-    self.table = table;
+  // the rows themselves are handed over a window at a time with set_rows
+  pub fn set_table(&mut self, header: Vec<Cell<'a>>, row_count: usize, column_width: u16) -> &mut Self {
+    self.requested_width = (column_width as usize).saturating_mul(header.len());
     self.column_width = column_width;
-    self.requested_width = requested_width;
-    self.max_height = max_height;
     self.max_y_offset = row_count.saturating_sub(1);
+    self.row_count = row_count;
+    self.header = header;
+    self.rows = vec![];
+    self.rows_start = 0;
     self
   }
 
+  pub fn set_rows(&mut self, start: usize, rows: Vec<Vec<Cell<'a>>>) -> &mut Self {
+    self.rows_start = start;
+    self.rows = rows;
+    self
+  }
+
+  // whether the window from set_rows covers all of the given rows
+  pub fn has_rows(&self, rows: &Range<usize>) -> bool {
+    rows.is_empty() || (rows.start >= self.rows_start && rows.end <= self.rows_start + self.rows.len())
+  }
+
+  // the rows drawn in the given area, which start below the 2-line header and its margin,
+  // and each take a line plus a margin
+  pub fn rows_in_view(&self, area: Rect) -> Range<usize> {
+    let height = self.block.inner_if_some(area).height.saturating_sub(u16::from(self.footer.is_some()));
+    let count = (height.saturating_sub(3) as usize).div_ceil(2);
+    self.y_offset..std::cmp::min(self.y_offset.saturating_add(count), self.row_count).max(self.y_offset)
+  }
+
   pub fn block(&mut self, block: Block<'a>) -> &mut Self {
     self.block = Some(block);
     self
   }
 
+  // how the selected row, the selected cell and the marked rows stand out
+  pub fn styles(&mut self, row_style: Style, cell_style: Style, marked_style: Style) -> &mut Self {
+    self.row_style = row_style;
+    self.cell_style = cell_style;
+    self.marked_style = marked_style;
+    self
+  }
+
+  // numbers each row in a gutter that stays put when scrolling sideways
+  pub fn row_numbers(&mut self, row_numbers: bool) -> &mut Self {
+    self.row_numbers = row_numbers;
+    self
+  }
+
+  pub fn footer(&mut self, footer: Option<Vec<Cell<'a>>>) -> &mut Self {
+    self.footer = footer;
+    self
+  }
+
+  // the row is 0-based, and past the end goes to the last row
+  pub fn go_to_row(&mut self, row: usize) -> &mut Self {
+    self.y_offset = std::cmp::min(row, self.max_y_offset);
+    self
+  }
+
   pub fn scroll(&mut self, direction: ScrollDirection) -> &mut Self {
     match direction {
       ScrollDirection::Left => self.x_offset = self.x_offset.saturating_sub(2),
@@ -93,9 +159,9 @@ impl<'a> ScrollTable<'a> {
     if self.column_width == 0 {
       return self;
     }
-    let x_over = self.x_offset % self.column_width;
-    self.x_offset =
-      std::cmp::min(self.x_offset.saturating_add(self.column_width).saturating_sub(x_over), self.max_x_offset);
+    let column_width = self.column_width as usize;
+    let x_over = self.x_offset % column_width;
+    self.x_offset = std::cmp::min(self.x_offset.saturating_add(column_width).saturating_sub(x_over), self.max_x_offset);
     self
   }
 
@@ -103,10 +169,10 @@ impl<'a> ScrollTable<'a> {
     if self.column_width == 0 {
       return self;
     }
-    let x_over = self.x_offset % self.column_width;
+    let x_over = self.x_offset % self.column_width as usize;
     match x_over {
       0 => {
-        self.x_offset = self.x_offset.saturating_sub(self.column_width);
+        self.x_offset = self.x_offset.saturating_sub(self.column_width as usize);
       },
       x => {
         self.x_offset = self.x_offset.saturating_sub(x);
@@ -162,10 +228,20 @@ impl<'a> ScrollTable<'a> {
     self
   }
 
+  // the column is the index into the underlying row, even when columns are hidden or moved
   pub fn get_cell_offsets(&self) -> (u16, usize) {
-    let column_count = self.requested_width.saturating_div(self.column_width);
-    let col_index = (self.x_offset.saturating_sub(self.x_offset % self.column_width)).saturating_div(self.column_width);
-    (col_index, self.y_offset)
+    let col_index = self.x_offset.checked_div(self.column_width as usize).unwrap_or_default();
+    let col_index = self.visible_columns.get(col_index).copied().unwrap_or(col_index);
+    (col_index as u16, self.y_offset)
+  }
+
+  pub fn set_visible_columns(&mut self, columns: Vec<usize>) -> &mut Self {
+    self.visible_columns = columns;
+    self
+  }
+
+  pub fn get_visible_columns(&self) -> &[usize] {
+    &self.visible_columns
   }
 
   pub fn get_selection_mode(&self) -> Option<SelectionMode> {
@@ -177,17 +253,49 @@ impl<'a> ScrollTable<'a> {
     self
   }
 
-  fn get_max_x_offset(&self, parent_area: &Rect, parent_block: &Option<Block>) -> u16 {
+  // marks or unmarks the row under the cursor, independently of the selection mode
+  pub fn toggle_marked_row(&mut self) -> &mut Self {
+    if !self.marked_rows.remove(&self.y_offset) {
+      self.marked_rows.insert(self.y_offset);
+    }
+    self
+  }
+
+  pub fn get_marked_rows(&self) -> &BTreeSet<usize> {
+    &self.marked_rows
+  }
+
+  pub fn clear_marked_rows(&mut self) -> &mut Self {
+    self.marked_rows.clear();
+    self
+  }
+
+  fn get_max_x_offset(&self, parent_area: &Rect, parent_block: &Option<Block>) -> usize {
     let render_area = parent_block.inner_if_some(*parent_area);
     if render_area.is_empty() {
-      return 0_u16;
+      return 0;
+    }
+    self.requested_width.saturating_sub(self.column_width as usize)
+  }
+
+  // the columns that show up in a viewport of the given width, and how far into the first one it starts
+  fn columns_in_view(&self, width: u16) -> (std::ops::Range<usize>, u16) {
+    let column_width = self.column_width as usize;
+    if column_width == 0 {
+      return (0..0, 0);
     }
-    let parent_width = render_area.width;
-    self.requested_width.saturating_sub(self.column_width)
+    let first = self.x_offset / column_width;
+    let skipped = (self.x_offset % column_width) as u16;
+    let count = (width as usize + skipped as usize).div_ceil(column_width);
+    (first..std::cmp::min(first.saturating_add(count), self.header.len()).max(first), skipped)
+  }
+
+  fn row(&self, row: usize) -> &[Cell<'a>] {
+    row.checked_sub(self.rows_start).and_then(|row| self.rows.get(row)).map_or(&[], |cells| cells.as_slice())
   }
 
   fn widget(&'a self) -> Renderer<'a> {
-    Renderer::new(self, self.y_offset)
+    Renderer(self)
   }
 }
 
@@ -195,7 +303,7 @@ impl<DB: Database> Component<DB> for ScrollTable<'_> {
   fn draw(&mut self, f: &mut Frame<'_>, area: Rect, app_state: &AppState<'_, DB>) -> Result<()> {
     self.parent_area = area;
     let render_area = self.block.inner_if_some(area);
-    self.pg_height = std::cmp::min(self.max_height, render_area.height).saturating_sub(3);
+    self.pg_height = render_area.height.saturating_sub(3);
     self.max_x_offset = self.get_max_x_offset(&self.parent_area, &self.block);
     let max_x_offset = self.max_x_offset;
     let x_offset = self.x_offset;
@@ -204,7 +312,7 @@ impl<DB: Database> Component<DB> for ScrollTable<'_> {
     let mut vertical_scrollbar_state = ScrollbarState::new(self.max_y_offset).position(self.y_offset);
     let horizontal_scrollbar =
       Scrollbar::new(ScrollbarOrientation::HorizontalBottom).symbols(scrollbar::HORIZONTAL).thumb_symbol("▀");
-    let mut horizontal_scrollbar_state = ScrollbarState::new(max_x_offset as usize).position(x_offset as usize);
+    let mut horizontal_scrollbar_state = ScrollbarState::new(max_x_offset).position(x_offset);
     match (self.max_x_offset, self.max_y_offset) {
       (0, 0) => {},
       (0, y) => {
@@ -240,62 +348,80 @@ impl<DB: Database> Component<DB> for ScrollTable<'_> {
 
 // based on scrolling approach from tui-textarea:
 // https://github.com/rhysd/tui-textarea/blob/main/src/widget.rs
-pub struct Renderer<'a>(&'a ScrollTable<'a>, TableState);
+pub struct Renderer<'a>(&'a ScrollTable<'a>);
 
-impl<'a> Renderer<'a> {
-  pub fn new(scrollable: &'a ScrollTable<'a>, y_offset: usize) -> Self {
-    Self(scrollable, TableState::default().with_offset(y_offset))
-  }
-
-  pub fn offset(&self) -> usize {
-    self.1.offset()
-  }
-}
-
-impl Widget for Renderer<'_> {
+impl<'a> Widget for Renderer<'a> {
   fn render(self, area: Rect, buf: &mut Buffer) {
     let scrollable = self.0;
-    let table = &scrollable.table;
-    let mut table_state = self.1;
-    let current_offset = table_state.offset();
-    if let Some(SelectionMode::Row) = scrollable.selection_mode {
-      table_state = table_state.with_selected(current_offset);
-    }
+    let current_offset = scrollable.y_offset;
+    let rows = scrollable.rows_in_view(area);
     scrollable.block.render_ref(area, buf);
     let render_area = scrollable.block.inner_if_some(area);
     if render_area.is_empty() {
       return;
     }
     let area = render_area.intersection(buf.area);
-    let mut content_buf = Buffer::empty(Rect::new(
-      0,
-      0,
-      scrollable.requested_width,
-      std::cmp::min(scrollable.max_height, render_area.height),
-    ));
-    ratatui::widgets::StatefulWidgetRef::render_ref(table, content_buf.area, &mut content_buf, &mut table_state);
-    let content_width = content_buf.area.width;
-    let content_height = content_buf.area.height;
-    let max_x = std::cmp::min(area.x.saturating_add(area.width), area.x.saturating_add(content_width));
-    let max_y = std::cmp::min(area.y.saturating_add(area.height), area.y.saturating_add(content_height));
+    let gutter_width = match scrollable.row_numbers {
+      true => std::cmp::min(scrollable.row_count.to_string().len() as u16 + 1, area.width),
+      false => 0,
+    };
+    let gutter = Rect { width: gutter_width, ..area };
+    let area = Rect { x: area.x.saturating_add(gutter_width), width: area.width.saturating_sub(gutter_width), ..area };
+    let (area, footer_y) = match &scrollable.footer {
+      Some(_) if area.height > 1 => {
+        (
+          Rect { height: area.height.saturating_sub(1), ..area },
+          Some(area.y.saturating_add(area.height.saturating_sub(1))),
+        )
+      },
+      _ => (area, None),
+    };
+    let (columns, skipped) = scrollable.columns_in_view(area.width);
+    let in_view =
+      |cells: &[Cell<'a>]| -> Vec<Cell<'a>> { cells.iter().skip(columns.start).take(columns.len()).cloned().collect() };
+    let widths = vec![Constraint::Length(scrollable.column_width.saturating_sub(1)); columns.len()];
+    let table = Table::new(rows.clone().map(|i| Row::new(in_view(scrollable.row(i))).bottom_margin(1)), &widths)
+      .header(Row::new(in_view(&scrollable.header)).height(2).bottom_margin(1))
+      .column_spacing(1)
+      .highlight_style(scrollable.row_style);
+    let mut table_state = TableState::default();
+    if let (Some(SelectionMode::Row), false) = (&scrollable.selection_mode, rows.is_empty()) {
+      table_state = table_state.with_selected(0);
+    }
+    let content_width = (columns.len() as u16).saturating_mul(scrollable.column_width);
+    let mut content_buf = Buffer::empty(Rect::new(0, 0, content_width, area.height));
+    StatefulWidget::render(table, content_buf.area, &mut content_buf, &mut table_state);
+    let max_x = std::cmp::min(
+      area.x.saturating_add(area.width),
+      area.x.saturating_add(content_width - skipped.min(content_width)),
+    );
+    let max_y = area.y.saturating_add(area.height);
+    // numbers line up with the rows, which start below the 2-line header and its margin
+    for y in (gutter.y..max_y).filter(|y| gutter.width > 1 && y - gutter.y >= 3 && (y - gutter.y - 3) % 2 == 0) {
+      let row = current_offset + (y - gutter.y - 3) as usize / 2;
+      if row < scrollable.row_count {
+        let style =
+          if scrollable.marked_rows.contains(&row) { scrollable.marked_style } else { Style::default().dim() };
+        let number = format!("{:>width$}", row + 1, width = gutter.width.saturating_sub(1) as usize);
+        buf.set_string(gutter.x, y, number, style);
+      }
+    }
     for y in area.y..max_y {
       let content_y = y - area.y;
-      let row = get_row(&content_buf.content, content_y, content_width);
       for x in area.x..max_x {
-        let content_x = x + scrollable.x_offset - area.x;
-        let default_cell = Cell::default();
-        let cell = match &row.len().saturating_sub(1).saturating_sub(content_x as usize) {
-          0 => &default_cell,
-          _ => &row[content_x as usize],
+        let content_x = x - area.x + skipped;
+        let Some(cell) = content_buf.cell(Position::from((content_x, content_y))) else {
+          continue;
         };
-        let right_edge = scrollable
-          .column_width
-          .saturating_sub(1) // account for column spacing
-          .saturating_add(scrollable.x_offset)
-          .saturating_sub(scrollable.x_offset % scrollable.column_width);
         let style = match (scrollable.selection_mode.as_ref(), content_x, content_y) {
-          (Some(SelectionMode::Cell), x, y) if y == 3 && x < right_edge => {
-            Style::default().fg(Color::LightBlue).reversed().bold().italic()
+          // the selected cell is the first column in view, short of its spacing
+          (Some(SelectionMode::Cell), x, 3) if x < scrollable.column_width.saturating_sub(1) => scrollable.cell_style,
+          (_, _, y)
+            if y >= 3
+              && (y - 3) % 2 == 0
+              && scrollable.marked_rows.contains(&(current_offset + (y as usize - 3) / 2)) =>
+          {
+            cell.style().patch(scrollable.marked_style)
           },
           _ => cell.style(),
         };
@@ -309,9 +435,85 @@ impl Widget for Renderer<'_> {
           .set_style(style);
       }
     }
+    if let (Some(footer), Some(y)) = (&scrollable.footer, footer_y) {
+      let mut footer_buf = Buffer::empty(Rect::new(0, 0, content_width, 1));
+      Widget::render(
+        Table::new([Row::new(in_view(footer))], &widths).column_spacing(1),
+        footer_buf.area,
+        &mut footer_buf,
+      );
+      for x in area.x..max_x {
+        let content_x = x - area.x + skipped;
+        if let Some(cell) = footer_buf.cell(Position::from((content_x, 0))) {
+          buf.cell_mut(Position::from((x, y))).unwrap().set_symbol(cell.symbol()).set_style(cell.style());
+        }
+      }
+    }
   }
 }
 
-fn get_row(content: &[Cell], row: u16, width: u16) -> Vec<Cell> {
-  content[((row * width) as usize)..(((row + 1) * width) as usize)].to_vec()
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_render_wide_table() {
+    // wider than a buffer can be, which only works since just the columns in view are laid out
+    let columns = 3000;
+    let header = (0..columns).map(|i| Cell::from(format!("c{i}\ntext"))).collect::<Vec<_>>();
+    let rows = (0..50).map(|r| (0..columns).map(|i| Cell::from(format!("{r}-{i}"))).collect()).collect();
+    let mut scrollable = ScrollTable::new();
+    scrollable
+      .set_table(header, 50, 30)
+      .set_rows(0, rows)
+      .styles(Style::default(), Style::default().bold(), Style::default())
+      .transition_selection_mode(Some(SelectionMode::Cell));
+    scrollable.max_x_offset = scrollable.requested_width - 30;
+    scrollable.last_column().prev_column().scroll(ScrollDirection::Down);
+    assert_eq!(scrollable.get_cell_offsets(), (2998, 1));
+    scrollable.scroll(ScrollDirection::Right);
+    let area = Rect::new(0, 0, 40, 7);
+    let mut buf = Buffer::empty(area);
+    scrollable.widget().render(area, &mut buf);
+    let line = |y: u16| (0..area.width).map(|x| buf[(x, y)].symbol()).collect::<String>();
+    assert_eq!(line(0), format!("{:<28}{:<12}", "998", "c2999"));
+    assert_eq!(line(3), format!("{:<28}{:<12}", "2998", "1-2999"));
+    // the selected cell is the one scrolled into, even partway
+    assert_eq!(buf[(0, 3)].modifier, Modifier::BOLD);
+    assert_eq!(buf[(28, 3)].modifier, Modifier::empty());
+  }
+
+  #[test]
+  fn test_rows_in_view() {
+    let mut scrollable = ScrollTable::new();
+    scrollable.set_table(vec![Cell::from("a")], 100_000, 10);
+    // 2 header lines and a margin, then 3 rows with a margin under each
+    let area = Rect::new(0, 0, 20, 8);
+    assert_eq!(scrollable.rows_in_view(area), 0..3);
+    assert!(!scrollable.has_rows(&scrollable.rows_in_view(area)));
+    scrollable.go_to_row(99_998);
+    assert_eq!(scrollable.rows_in_view(area), 99_998..100_000);
+    scrollable.set_rows(99_990, vec![vec![Cell::from("x")]; 10]);
+    assert!(scrollable.has_rows(&scrollable.rows_in_view(area)));
+    scrollable.footer(Some(vec![])).block(Block::bordered());
+    assert_eq!(scrollable.rows_in_view(area), 99_998..99_999);
+    scrollable.top_row();
+    assert!(!scrollable.has_rows(&scrollable.rows_in_view(area)));
+  }
+
+  #[test]
+  fn test_render_clamped_to_buffer() {
+    let mut scrollable = ScrollTable::new();
+    scrollable
+      .set_table(vec![Cell::from("a"), Cell::from("b")], 20, 10)
+      .set_rows(0, vec![vec![Cell::from("x"), Cell::from("y")]; 20])
+      .row_numbers(true)
+      .footer(Some(vec![Cell::from("f")]))
+      .block(Block::bordered());
+    // areas past the buffer, or too small for the border, gutter and footer, draw what fits
+    for (width, height) in [(1, 1), (2, 2), (3, 3), (200, 200)] {
+      let mut buf = Buffer::empty(Rect::new(0, 0, 4, 4));
+      scrollable.widget().render(Rect::new(0, 0, width, height), &mut buf);
+    }
+  }
 }
@@ -1,7 +1,7 @@
 use std::{collections::HashMap, fmt, path::PathBuf};
 
 use color_eyre::eyre::Result;
-use config::Value;
+use config::{Source, Value, ValueKind};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use derive_deref::{Deref, DerefMut};
 use ratatui::style::{Color, Modifier, Style};
@@ -11,7 +11,11 @@ use serde::{
 };
 use serde_json::Value as JsonValue;
 
-use crate::{action::Action, focus::Focus};
+use crate::{
+  action::Action,
+  database::{ExecutionType, StatementPolicy},
+  focus::Focus,
+};
 
 const CONFIG: &str = include_str!("../.config/rainfrog_config.toml");
 
@@ -33,11 +37,25 @@ pub struct Config {
   pub styles: Styles,
   #[serde(default)]
   pub settings: Settings,
+  // name -> text, offered by tab completion in the editor alongside sql keywords
+  #[serde(default)]
+  pub completions: HashMap<String, String>,
+  // the config files as read, so the connection overrides can be applied without reading them again
+  #[serde(skip)]
+  source: config::Map<String, Value>,
 }
 
 impl Config {
   pub fn new() -> Result<Self, config::ConfigError> {
-    let default_config: Config = toml::from_str(CONFIG).unwrap();
+    Self::resolve(Self::read()?, None)
+  }
+
+  // with the overrides in [connections."<name>"] merged over the rest of the config
+  pub fn for_connection(&self, connection: &str) -> Result<Self, config::ConfigError> {
+    Self::resolve(self.source.clone(), Some(connection))
+  }
+
+  fn read() -> Result<config::Map<String, Value>, config::ConfigError> {
     let data_dir = crate::utils::get_data_dir();
     let config_dir = crate::utils::get_config_dir();
     let mut builder = config::Config::builder()
@@ -62,7 +80,17 @@ impl Config {
       log::error!("No configuration file found. Application may not behave as expected");
     }
 
-    let mut cfg: Self = builder.build()?.try_deserialize()?;
+    builder.build()?.collect()
+  }
+
+  fn resolve(source: config::Map<String, Value>, connection: Option<&str>) -> Result<Self, config::ConfigError> {
+    let default_config: Config = toml::from_str(CONFIG).unwrap();
+    let mut root = source.clone();
+    if let Some(connection) = connection {
+      apply_connection_overrides(&mut root, connection);
+    }
+    let mut cfg: Self = Value::new(None, ValueKind::Table(root)).try_deserialize()?;
+    cfg.source = source;
 
     for (focus, default_bindings) in default_config.keybindings.iter() {
       let user_bindings = cfg.keybindings.entry(*focus).or_default();
@@ -82,9 +110,75 @@ impl Config {
         cfg.settings.mouse_mode = default_config.settings.mouse_mode;
       },
     };
+    if cfg.settings.thousands_separator.is_none() {
+      cfg.settings.thousands_separator = default_config.settings.thousands_separator;
+    }
+    if cfg.settings.split.is_none() {
+      cfg.settings.split = default_config.settings.split;
+    }
+    if cfg.settings.max_connections.is_none() {
+      cfg.settings.max_connections = default_config.settings.max_connections;
+    }
+    if cfg.settings.syntax_highlighting.is_none() {
+      cfg.settings.syntax_highlighting = default_config.settings.syntax_highlighting;
+    }
+    if let Some(policy) = &cfg.settings.statement_policy {
+      validate_statement_policy(policy).map_err(config::ConfigError::Message)?;
+    }
+    if let Some(max_connections) = cfg.settings.max_connections {
+      validate_max_connections(max_connections).map_err(config::ConfigError::Message)?;
+    }
+    if let Some(timezone) = &cfg.settings.display_timezone {
+      DisplayTimezone::parse(timezone).map_err(config::ConfigError::Message)?;
+    }
 
     Ok(cfg)
   }
+
+  // None when the setting is missing or invalid, which leaves timestamps in utc
+  pub fn display_timezone(&self) -> Option<DisplayTimezone> {
+    DisplayTimezone::parse(self.settings.display_timezone.as_deref()?).ok()
+  }
+
+  // a style from [styles.<focus>], falling back to the theme's
+  pub fn style(&self, focus: Focus, key: &str) -> Style {
+    self
+      .styles
+      .get(&focus)
+      .and_then(|styles| styles.get(key))
+      .copied()
+      .unwrap_or_else(|| self.settings.theme.unwrap_or_default().style(key))
+  }
+
+  pub fn border_style(&self, focus: Focus, focused: bool) -> Style {
+    self.style(focus, if focused { "focused" } else { "unfocused" })
+  }
+}
+
+// a connection's overrides are merged table by table, so a single keybinding or setting can be changed
+fn apply_connection_overrides(root: &mut config::Map<String, Value>, connection: &str) {
+  let overrides = root
+    .get("connections")
+    .and_then(|connections| connections.clone().into_table().ok())
+    .and_then(|mut connections| connections.remove(connection))
+    .and_then(|overrides| overrides.into_table().ok());
+  if let Some(overrides) = overrides {
+    merge_tables(root, overrides);
+  }
+}
+
+fn merge_tables(base: &mut config::Map<String, Value>, overrides: config::Map<String, Value>) {
+  for (key, value) in overrides {
+    if let ValueKind::Table(table) = value.kind {
+      if let Some(Value { kind: ValueKind::Table(existing), .. }) = base.get_mut(&key) {
+        merge_tables(existing, table);
+        continue;
+      }
+      base.insert(key, Value::new(None, ValueKind::Table(table)));
+    } else {
+      base.insert(key, value);
+    }
+  }
 }
 
 #[derive(Clone, Debug, Default, Deref, DerefMut)]
@@ -251,36 +345,280 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
   key
 }
 
+// each key is either a bracketed name like <ctrl-c> or a single character, so
+// leader-style sequences like "<space>e" can mix the two. a bare name like
+// "esc" without brackets is still read as one key
 pub fn parse_key_sequence(raw: &str) -> Result<Vec<KeyEvent>, String> {
   if raw.chars().filter(|c| *c == '>').count() != raw.chars().filter(|c| *c == '<').count() {
     return Err(format!("Unable to parse `{}`", raw));
   }
-  let raw = if !raw.contains("><") {
-    let raw = raw.strip_prefix('<').unwrap_or(raw);
-    let raw = raw.strip_prefix('>').unwrap_or(raw);
-    raw
-  } else {
-    raw
-  };
-  let sequences = raw
-    .split("><")
-    .map(|seq| {
-      if let Some(s) = seq.strip_prefix('<') {
-        s
-      } else if let Some(s) = seq.strip_suffix('>') {
-        s
-      } else {
-        seq
-      }
-    })
-    .collect::<Vec<_>>();
+  if !raw.contains('<') {
+    if let Ok(key) = parse_key_event(raw) {
+      return Ok(vec![key]);
+    }
+  }
+  let mut keys = vec![];
+  let mut rest = raw;
+  while let Some(c) = rest.chars().next() {
+    let (key, remaining) = match (c, rest.find('>')) {
+      ('<', Some(end)) => (&rest[1..end], &rest[end + 1..]),
+      _ => rest.split_at(c.len_utf8()),
+    };
+    keys.push(parse_key_event(key)?);
+    rest = remaining;
+  }
+  Ok(keys)
+}
 
-  sequences.into_iter().map(parse_key_event).collect()
+// whether the keys typed so far are the start of a longer binding
+pub fn is_pending_sequence(keymap: &HashMap<Vec<KeyEvent>, Action>, keys: &[KeyEvent]) -> bool {
+  !keys.is_empty() && keymap.keys().any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
 }
 
-#[derive(Clone, Debug, Default, Deref, DerefMut, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize)]
 pub struct Settings {
   pub mouse_mode: Option<bool>,
+  pub thousands_separator: Option<String>,
+  pub default_schema: Option<String>,
+  pub schema_filter: Option<String>,
+  pub syntax_highlighting: Option<bool>,
+  pub lint: Option<bool>,
+  pub max_connections: Option<u32>,
+  pub terminal_title: Option<bool>,
+  pub menu_refresh_interval: Option<u64>,
+  pub split: Option<Split>,
+  pub statement_timeout: Option<u64>,
+  pub application_name: Option<String>,
+  pub statement_policy: Option<StatementPolicy>,
+  pub explain: Option<ExplainFlavor>,
+  pub auto_limit: Option<u64>,
+  pub theme: Option<Theme>,
+  pub key_sequence_timeout: Option<u64>,
+  pub clipboard: Option<ClipboardBackend>,
+  pub display_timezone: Option<String>,
+  pub paste_warning_lines: Option<usize>,
+  pub row_estimate_threshold: Option<u64>,
+  pub row_numbers: Option<bool>,
+  pub max_cell_length: Option<u16>,
+  pub history_max_entries: Option<usize>,
+  pub erd_format: Option<ErdFormat>,
+  pub erd_directory: Option<String>,
+  pub pager: Option<String>,
+  pub pager_format: Option<PagerFormat>,
+  pub replicas: Option<String>,
+  pub ssl: Option<SslSettings>,
+  pub query_notification: Option<QueryNotification>,
+}
+
+// the same as the --ssl-* options, which take precedence over these
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SslSettings {
+  pub mode: Option<String>,
+  pub ca: Option<String>,
+  pub cert: Option<String>,
+  pub key: Option<String>,
+}
+
+fn validate_statement_policy(policy: &StatementPolicy) -> Result<(), String> {
+  match policy
+    .iter()
+    .find(|(kind, execution_type)| **execution_type == ExecutionType::Transaction && !kind.allows_transaction())
+  {
+    Some((kind, _)) => {
+      Err(format!(
+        "statement_policy: {} statements can't be run in a transaction",
+        format!("{:?}", kind).to_lowercase()
+      ))
+    },
+    None => Ok(()),
+  }
+}
+
+// a pool without connections can't be created, so 0 is refused up front
+fn validate_max_connections(max_connections: u32) -> Result<(), String> {
+  match max_connections {
+    0 => Err("max_connections must be at least 1".to_owned()),
+    _ => Ok(()),
+  }
+}
+
+// what the editor's explain shortcut asks the database for
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExplainFlavor {
+  // only the planned query
+  #[default]
+  Plan,
+  // the plan with actual timings, which runs the query
+  Analyze,
+}
+
+// the built-in color schemes. each one covers the same style keys, which
+// [styles.<focus>] can override one at a time
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+  #[default]
+  Dark,
+  Light,
+  Solarized,
+}
+
+impl Theme {
+  pub fn style(self, key: &str) -> Style {
+    let (focused, unfocused, selected, accent, hints) = match self {
+      Theme::Dark => {
+        (
+          Style::new().fg(Color::Green),
+          Style::new().add_modifier(Modifier::DIM),
+          Style::new().fg(Color::LightBlue),
+          Style::new().fg(Color::Yellow),
+          Style::new().fg(Color::Blue),
+        )
+      },
+      Theme::Light => {
+        (
+          Style::new().fg(Color::Blue),
+          Style::new().fg(Color::Gray),
+          Style::new().fg(Color::Magenta),
+          Style::new().fg(Color::Red),
+          Style::new().fg(Color::DarkGray),
+        )
+      },
+      Theme::Solarized => {
+        (
+          Style::new().fg(Color::Rgb(0x85, 0x99, 0x00)),
+          Style::new().fg(Color::Rgb(0x58, 0x6e, 0x75)),
+          Style::new().fg(Color::Rgb(0x26, 0x8b, 0xd2)),
+          Style::new().fg(Color::Rgb(0xb5, 0x89, 0x00)),
+          Style::new().fg(Color::Rgb(0x26, 0x8b, 0xd2)),
+        )
+      },
+    };
+    match key {
+      // borders and markers of the focused pane
+      "focused" => focused,
+      "unfocused" => unfocused,
+      // the selected row, cell, or history entry
+      "selected" => selected,
+      // labels that should stand out, like line numbers and timestamps
+      "accent" => accent,
+      // the driver's name in the status bar, and what's in progress (a query, pending keys)
+      "status" => focused,
+      "busy" => accent,
+      // the keybinding hints under the status bar
+      "hints" => hints,
+      _ => Style::default(),
+    }
+  }
+}
+
+// the zone that timestamps with a time zone are shown in, instead of utc
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DisplayTimezone {
+  Local,
+  Named(chrono_tz::Tz),
+}
+
+impl DisplayTimezone {
+  pub fn parse(name: &str) -> Result<Self, String> {
+    match name {
+      "local" => Ok(Self::Local),
+      name => {
+        name.parse::<chrono_tz::Tz>().map(Self::Named).map_err(|_| {
+          format!("display_timezone: {} is not \"local\" or an IANA time zone name (ex. \"Europe/Berlin\")", name)
+        })
+      },
+    }
+  }
+
+  pub fn name(&self) -> String {
+    match self {
+      Self::Local => "local".to_owned(),
+      Self::Named(tz) => tz.name().to_owned(),
+    }
+  }
+
+  // keeps the precision of the original, and replaces "UTC" with the offset
+  pub fn format(&self, time: chrono::DateTime<chrono::Utc>) -> String {
+    let format = "%Y-%m-%d %H:%M:%S%.f %:z";
+    match self {
+      Self::Local => time.with_timezone(&chrono::Local).format(format).to_string(),
+      Self::Named(tz) => time.with_timezone(tz).format(format).to_string(),
+    }
+  }
+}
+
+// where copied text goes
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardBackend {
+  // the system clipboard, or osc 52 when it can't be reached (ex. over ssh)
+  #[default]
+  Auto,
+  System,
+  Osc52,
+}
+
+// how to say that a query finished while the terminal wasn't focused
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryNotification {
+  #[default]
+  Off,
+  Bell,
+  // an osc 9 desktop notification
+  Desktop,
+  Both,
+}
+
+// the file that exporting a schema's er diagram writes
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ErdFormat {
+  #[default]
+  Dbml,
+  // graphviz
+  Dot,
+}
+
+impl ErdFormat {
+  pub fn extension(self) -> &'static str {
+    match self {
+      ErdFormat::Dbml => "dbml",
+      ErdFormat::Dot => "dot",
+    }
+  }
+}
+
+// how the results are written to the pager
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PagerFormat {
+  // aligned columns, like psql
+  #[default]
+  Text,
+  Csv,
+}
+
+// how the editor and the results share the right-hand side of the screen
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Split {
+  // editor above the results
+  #[default]
+  Vertical,
+  // editor beside the results
+  Horizontal,
+}
+
+impl Split {
+  pub fn toggle(self) -> Self {
+    match self {
+      Split::Vertical => Split::Horizontal,
+      Split::Horizontal => Split::Vertical,
+    }
+  }
 }
 
 #[derive(Clone, Debug, Default, Deref, DerefMut)]
@@ -404,6 +742,7 @@ mod tests {
   use pretty_assertions::assert_eq;
 
   use super::*;
+  use crate::database::StatementKind;
 
   #[test]
   fn test_parse_style_default() {
@@ -452,6 +791,52 @@ mod tests {
     assert_eq!(color, None);
   }
 
+  #[test]
+  fn test_display_timezone() {
+    let time = chrono::DateTime::parse_from_rfc3339("2024-07-01T12:30:00.25Z").unwrap().to_utc();
+    let berlin = DisplayTimezone::parse("Europe/Berlin").unwrap();
+    assert_eq!(berlin.format(time), "2024-07-01 14:30:00.250 +02:00");
+    assert_eq!(berlin.name(), "Europe/Berlin");
+    assert_eq!(DisplayTimezone::parse("local"), Ok(DisplayTimezone::Local));
+    assert!(DisplayTimezone::parse("Mars/Olympus_Mons").is_err());
+  }
+
+  #[test]
+  fn test_validate_statement_policy() {
+    let policy: StatementPolicy =
+      toml::from_str("truncate = \"confirm\"\ndelete_without_where = \"confirm\"\nalter = \"normal\"").unwrap();
+    assert_eq!(policy.get(&StatementKind::DeleteWithoutWhere), Some(&ExecutionType::Confirm));
+    assert!(validate_statement_policy(&policy).is_ok());
+    let policy: StatementPolicy = toml::from_str("drop = \"transaction\"").unwrap();
+    assert!(validate_statement_policy(&policy).is_err());
+    let policy: StatementPolicy = toml::from_str("copy = \"transaction\"").unwrap();
+    assert!(validate_statement_policy(&policy).is_err());
+    assert!(toml::from_str::<StatementPolicy>("select = \"confirm\"").is_err());
+    assert!(toml::from_str::<StatementPolicy>("delete = \"sometimes\"").is_err());
+  }
+
+  #[test]
+  fn test_validate_max_connections() {
+    assert!(validate_max_connections(0).is_err());
+    assert!(validate_max_connections(1).is_ok());
+    assert!(validate_max_connections(10).is_ok());
+  }
+
+  #[test]
+  fn test_config_style() {
+    let mut config = Config::default();
+    assert_eq!(config.style(Focus::Data, "focused"), Style::new().fg(Color::Green));
+    config.settings.theme = Some(Theme::Light);
+    assert_eq!(config.style(Focus::Data, "focused"), Style::new().fg(Color::Blue));
+    config.styles.entry(Focus::Data).or_default().insert("focused".to_owned(), parse_style("bold red"));
+    assert_eq!(config.style(Focus::Data, "focused"), Style::new().fg(Color::Indexed(1)).add_modifier(Modifier::BOLD));
+    assert_eq!(config.style(Focus::Menu, "focused"), Style::new().fg(Color::Blue));
+    assert_eq!(config.style(Focus::Menu, "unknown"), Style::default());
+    assert_eq!(config.style(Focus::Menu, "hints"), Style::new().fg(Color::DarkGray));
+    config.settings.theme = None;
+    assert_eq!(config.style(Focus::Editor, "busy"), Style::new().fg(Color::Yellow));
+  }
+
   #[test]
   fn test_config() -> Result<()> {
     let c = Config::new()?;
@@ -460,6 +845,41 @@ mod tests {
       &Action::AbortQuery
     );
     assert_eq!(c.settings.mouse_mode, Some(true));
+    assert_eq!(c.settings.thousands_separator.as_deref(), Some(","));
+    assert_eq!(c.settings.syntax_highlighting, Some(true));
+    assert_eq!(c.settings.max_connections, Some(3));
+    assert_eq!(c.settings.split, Some(Split::Vertical));
+    Ok(())
+  }
+
+  #[test]
+  fn test_connection_overrides() -> Result<()> {
+    let source = r#"
+      [settings]
+      theme = "dark"
+      row_numbers = true
+
+      [keybindings.Editor]
+      "<Ctrl-c>" = "Quit"
+
+      [connections."db.example.com/app".settings]
+      theme = "solarized"
+
+      [connections."db.example.com/app".keybindings.Editor]
+      "<Alt-enter>" = "AbortQuery"
+    "#;
+    let root = config::Config::builder()
+      .add_source(config::File::from_str(source, config::FileFormat::Toml))
+      .build()?
+      .collect()?;
+    let base = Config::resolve(root, None)?;
+    assert_eq!(base.settings.theme, Some(Theme::Dark));
+    let c = base.for_connection("db.example.com/app")?;
+    assert_eq!(c.settings.theme, Some(Theme::Solarized));
+    assert_eq!(c.settings.row_numbers, Some(true));
+    let editor = c.keybindings.get(&Focus::Editor).unwrap();
+    assert_eq!(editor.get(&parse_key_sequence("<Ctrl-c>").unwrap()), Some(&Action::Quit));
+    assert_eq!(editor.get(&parse_key_sequence("<Alt-enter>").unwrap()), Some(&Action::AbortQuery));
     Ok(())
   }
 
@@ -508,6 +928,24 @@ mod tests {
     assert!(parse_key_event("ctrl-invalid-key").is_err());
   }
 
+  #[test]
+  fn test_key_sequences() {
+    let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty());
+    assert_eq!(parse_key_sequence("<space>e").unwrap(), vec![key(' '), key('e')]);
+    assert_eq!(parse_key_sequence("<space><h>").unwrap(), vec![key(' '), key('h')]);
+    assert_eq!(parse_key_sequence("gg").unwrap(), vec![key('g'), key('g')]);
+    assert_eq!(parse_key_sequence("<q>").unwrap(), vec![key('q')]);
+    assert_eq!(parse_key_sequence("esc").unwrap(), vec![KeyEvent::new(KeyCode::Esc, KeyModifiers::empty())]);
+    assert_eq!(parse_key_sequence("<Ctrl-c>").unwrap(), vec![KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)]);
+    assert!(parse_key_sequence("<space").is_err());
+
+    let keymap = HashMap::from([(vec![key(' '), key('e')], Action::FocusEditor), (vec![key('q')], Action::AbortQuery)]);
+    assert!(is_pending_sequence(&keymap, &[key(' ')]));
+    assert!(!is_pending_sequence(&keymap, &[key(' '), key('e')]));
+    assert!(!is_pending_sequence(&keymap, &[key('q')]));
+    assert!(!is_pending_sequence(&keymap, &[]));
+  }
+
   #[test]
   fn test_case_insensitivity() {
     assert_eq!(parse_key_event("CTRL-a").unwrap(), KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL));
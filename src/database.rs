@@ -1,11 +1,20 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+  sync::{Arc, Mutex, OnceLock},
+  time::{Duration, Instant},
+};
 
-use futures::stream::{BoxStream, StreamExt};
+use futures::{
+  future::BoxFuture,
+  stream::{BoxStream, StreamExt},
+};
+use serde::{Deserialize, Serialize};
 use sqlparser::{
-  ast::Statement,
+  ast::{CopyTarget, Expr, FromTable, Query, SetExpr, Statement, TableFactor, TableWithJoins},
   dialect::{Dialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect},
   keywords,
   parser::{Parser, ParserError},
+  tokenizer::{Token, Tokenizer},
 };
 use sqlx::{
   mysql::{MySql, MySqlColumn, MySqlQueryResult, MySqlRow},
@@ -15,13 +24,16 @@ use sqlx::{
   Column, Connection, Database, Either, Error, Executor, Pool, Row, Transaction,
 };
 
-use crate::cli::Cli;
+use crate::{
+  cli::{Cli, SslOptions},
+  config::DisplayTimezone,
+};
 
 mod mysql;
 mod postgresql;
 mod sqlite;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Header {
   pub name: String,
   pub type_name: String,
@@ -40,16 +52,175 @@ pub struct Rows {
   pub rows_affected: Option<u64>,
 }
 pub type Headers = Vec<Header>;
+
+// a column in another table that a foreign key points to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnReference {
+  pub schema: String,
+  pub table: String,
+  // empty when the key refers to the other table's primary key without naming it (sqlite)
+  pub column: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaColumn {
+  pub name: String,
+  pub type_name: String,
+  pub primary_key: bool,
+  pub references: Vec<ColumnReference>,
+}
+
+// the database's objects and every table's columns, loaded in the background so that
+// the menu, completion, and the results can all read them instead of each querying the catalog
+#[derive(Debug, Clone)]
+pub struct SchemaCache {
+  objects: Rows, // the rows of preview_tables_query, followed by preview_functions_query's
+  tables: BTreeMap<(String, String), Vec<SchemaColumn>>, // (schema, table) -> columns
+}
+
+impl SchemaCache {
+  // reads the (schema, table, column, type, key, foreign schema, foreign table, foreign column)
+  // rows of a driver's schema_columns_query. a column in several foreign keys has a row for each
+  pub fn from_rows(objects: Rows, columns: &Rows) -> Self {
+    let mut tables: BTreeMap<(String, String), Vec<SchemaColumn>> = BTreeMap::new();
+    for row in columns.rows.iter().filter(|row| row.len() >= 8) {
+      let columns = tables.entry((row[0].clone(), row[1].clone())).or_default();
+      if columns.last().map_or(true, |column| column.name != row[2]) {
+        columns.push(SchemaColumn {
+          name: row[2].clone(),
+          type_name: row[3].clone(),
+          primary_key: row[4] == "PRI",
+          references: vec![],
+        });
+      }
+      let column = columns.last_mut().unwrap();
+      column.primary_key |= row[4] == "PRI";
+      if !row[6].is_empty() {
+        let reference = ColumnReference { schema: row[5].clone(), table: row[6].clone(), column: row[7].clone() };
+        if !column.references.contains(&reference) {
+          column.references.push(reference);
+        }
+      }
+    }
+    Self { objects, tables }
+  }
+
+  pub fn objects(&self) -> &Rows {
+    &self.objects
+  }
+
+  // without a schema, the table has to be the only one with that name
+  pub fn columns(&self, schema: Option<&str>, table: &str) -> Option<&[SchemaColumn]> {
+    match schema {
+      Some(schema) => self.tables.get(&(schema.to_owned(), table.to_owned())).map(|columns| columns.as_slice()),
+      None => {
+        let mut matches = self.tables.iter().filter(|((_, name), _)| name == table);
+        match (matches.next(), matches.next()) {
+          (Some((_, columns)), None) => Some(columns.as_slice()),
+          _ => None,
+        }
+      },
+    }
+  }
+
+  // one schema's tables and their columns, in name order
+  pub fn schema_tables(&self, schema: &str) -> Vec<(&str, &[SchemaColumn])> {
+    self
+      .tables
+      .iter()
+      .filter(|((table_schema, _), _)| table_schema == schema)
+      .map(|((_, table), columns)| (table.as_str(), columns.as_slice()))
+      .collect()
+  }
+
+  // distinct schema, table, and column names, for completion
+  pub fn names(&self) -> Vec<String> {
+    self
+      .tables
+      .iter()
+      .flat_map(|((schema, table), columns)| {
+        [schema.clone(), table.clone()].into_iter().chain(columns.iter().map(|column| column.name.clone()))
+      })
+      .filter(|name| !name.is_empty())
+      .collect::<BTreeSet<String>>()
+      .into_iter()
+      .collect()
+  }
+}
 pub type DbPool<DB> = Pool<DB>;
 pub type DbError = Either<Error, ParserError>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ExecutionType {
   Confirm,
   Transaction,
   Normal,
 }
 
+// the kinds of statements whose execution type can be changed with the statement_policy setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementKind {
+  Alter,
+  Drop,
+  Truncate,
+  Insert,
+  Update,
+  Delete,
+  UpdateWithoutWhere,
+  DeleteWithoutWhere,
+  Copy,
+}
+
+impl StatementKind {
+  // statements without a WHERE clause are only covered by the *_without_where kinds,
+  // so that a policy for `update` or `delete` doesn't skip their warning
+  fn of(statement: &Statement) -> Option<StatementKind> {
+    match statement {
+      Statement::AlterIndex { .. }
+      | Statement::AlterView { .. }
+      | Statement::AlterRole { .. }
+      | Statement::AlterTable { .. } => Some(StatementKind::Alter),
+      Statement::Drop { .. } => Some(StatementKind::Drop),
+      Statement::Truncate { .. } => Some(StatementKind::Truncate),
+      Statement::Insert(_) => Some(StatementKind::Insert),
+      Statement::Update { .. } if unfiltered_write_target(statement).is_some() => {
+        Some(StatementKind::UpdateWithoutWhere)
+      },
+      Statement::Update { .. } => Some(StatementKind::Update),
+      Statement::Delete(_) if unfiltered_write_target(statement).is_some() => Some(StatementKind::DeleteWithoutWhere),
+      Statement::Delete(_) => Some(StatementKind::Delete),
+      Statement::Copy { to: false, .. } => Some(StatementKind::Copy),
+      Statement::Explain { statement, analyze: true, .. } => Self::of(statement),
+      _ => None,
+    }
+  }
+
+  // the name it goes by in the statement_policy setting
+  pub fn name(self) -> &'static str {
+    match self {
+      StatementKind::Alter => "alter",
+      StatementKind::Drop => "drop",
+      StatementKind::Truncate => "truncate",
+      StatementKind::Insert => "insert",
+      StatementKind::Update => "update",
+      StatementKind::Delete => "delete",
+      StatementKind::UpdateWithoutWhere => "update_without_where",
+      StatementKind::DeleteWithoutWhere => "delete_without_where",
+      StatementKind::Copy => "copy",
+    }
+  }
+
+  // ddl isn't transactional everywhere (mysql commits implicitly), so only dml can be
+  // run in a transaction that waits for a commit. a copy streams its file on its own connection
+  pub fn allows_transaction(self) -> bool {
+    !matches!(self, StatementKind::Alter | StatementKind::Drop | StatementKind::Truncate | StatementKind::Copy)
+  }
+}
+
+pub type StatementPolicy = HashMap<StatementKind, ExecutionType>;
+
 pub trait HasRowsAffected {
   fn rows_affected(&self) -> u64;
 }
@@ -61,18 +232,279 @@ pub trait DatabaseQueries {
   fn preview_constraints_query(schema: &str, table: &str) -> String;
   fn preview_indexes_query(schema: &str, table: &str) -> String;
   fn preview_policies_query(schema: &str, table: &str) -> String;
+  fn preview_functions_query() -> String;
+  fn preview_function_definition_query(schema: &str, name: &str) -> String;
+  fn preview_settings_query(search: Option<&str>) -> String;
+  // tables, views, columns, and functions whose names match the search, as
+  // (schema, table or function, object_type, column) rows
+  fn search_objects_query(search: &str) -> String;
+  fn primary_key_query(schema: Option<&str>, table: &str) -> String;
+  // every listed table's columns, as (schema, table, column, type, key, foreign schema,
+  // foreign table, foreign column) rows where key is 'PRI' for primary key columns, and
+  // the foreign ones are empty unless the column is part of a foreign key
+  fn schema_columns_query() -> String;
+  fn quote_identifier(identifier: &str) -> String;
+  // a string literal, for values and for names compared against the catalog
+  fn quote_literal(value: &str) -> String;
+  fn preview_activity_query() -> String;
+  fn current_schema_query() -> String;
+  // what the editor's explain shortcut puts in front of the query
+  fn explain_prefix(analyze: bool) -> String;
+  // run on every new connection so the server enforces the timeout, not just rainfrog.
+  // None when the driver has no server-side timeout
+  fn statement_timeout_statement(timeout_ms: u64) -> Option<String>;
+  // None when the driver has no sessions that can be cancelled or killed
+  fn kill_session_statement(session_id: &str, cancel_only: bool) -> Option<String>;
+  // None when the driver can't attach other database files to a connection
+  fn attach_database_statement(path: &str, alias: &str) -> Option<String>;
+  // streams the file into a `COPY ... FROM STDIN` statement and resolves to the rows copied.
+  // None when the driver has no way to send a statement its data
+  fn copy_in(pool: &Pool<Self>, statement: String, path: String) -> Option<BoxFuture<'static, Result<u64, DbError>>>
+  where
+    Self: Database;
 }
 
 pub trait ValueParser: Database {
   fn parse_value(row: &Self::Row, col: &Self::Column) -> Option<Value>;
+  // whether values of the type are read as utc, so parse_value shows them in the display time zone
+  fn is_utc_type(type_name: &str) -> bool;
+}
+
+// the zone from `display_timezone`, set once when the app starts
+static DISPLAY_TIMEZONE: OnceLock<DisplayTimezone> = OnceLock::new();
+
+pub fn set_display_timezone(timezone: Option<DisplayTimezone>) {
+  if let Some(timezone) = timezone {
+    let _ = DISPLAY_TIMEZONE.set(timezone);
+  }
+}
+
+// a timestamp that has a time zone, in the display time zone if one is set
+pub fn format_utc(time: chrono::DateTime<chrono::Utc>) -> String {
+  match DISPLAY_TIMEZONE.get() {
+    Some(timezone) => timezone.format(time),
+    None => time.to_string(),
+  }
 }
 
 pub trait BuildConnectionOptions: Database {
   fn build_connection_opts(args: Cli) -> color_eyre::eyre::Result<<Self::Connection as Connection>::Options>;
+  // a short "host/database" label for the connection, used in the terminal title
+  fn describe_connection(opts: &<Self::Connection as Connection>::Options) -> String;
+  // (name, value) pairs for the parts of the connection that can be changed
+  // before reconnecting. the password is never included
+  fn connection_params(opts: &<Self::Connection as Connection>::Options) -> Vec<(String, String)>;
+  fn with_connection_params(
+    opts: &<Self::Connection as Connection>::Options,
+    params: &[(String, String)],
+  ) -> Result<<Self::Connection as Connection>::Options, String>;
+  // the connection's ssl and application_name settings, which can only be resolved once
+  // it's known which connection this is
+  fn with_settings(
+    opts: <Self::Connection as Connection>::Options,
+    ssl: &SslOptions,
+    application_name: Option<&str>,
+  ) -> color_eyre::eyre::Result<<Self::Connection as Connection>::Options>;
+}
+
+// the port field of a connection being edited
+pub fn parse_port(value: &str) -> Result<u16, String> {
+  value.trim().parse::<u16>().map_err(|_| format!("{} is not a valid port.", value.trim()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionFailure {
+  Authentication,
+  UnknownDatabase,
+  Unreachable,
+  Other,
+}
+
+impl ConnectionFailure {
+  pub fn hint(&self) -> &'static str {
+    match self {
+      ConnectionFailure::Authentication => "the username or password was rejected",
+      ConnectionFailure::UnknownDatabase => "the database does not exist",
+      ConnectionFailure::Unreachable => {
+        "the host could not be reached; check the host, port, and that the server is running"
+      },
+      ConnectionFailure::Other => "the connection could not be established",
+    }
+  }
+}
+
+// sorts a connection error into something the user can act on. sqlstates and
+// mysql error codes are checked so the message doesn't depend on the server's language
+pub fn classify_connection_error(error: &Error) -> ConnectionFailure {
+  match error {
+    Error::Database(e) => {
+      match e.code().as_deref() {
+        // postgres invalid_password / invalid_authorization_specification, mysql ER_ACCESS_DENIED_ERROR
+        Some("28P01") | Some("28000") | Some("1045") => ConnectionFailure::Authentication,
+        // postgres invalid_catalog_name, mysql ER_BAD_DB_ERROR
+        Some("3D000") | Some("1049") => ConnectionFailure::UnknownDatabase,
+        _ => ConnectionFailure::Other,
+      }
+    },
+    Error::Io(e) => {
+      match e.kind() {
+        std::io::ErrorKind::ConnectionRefused
+        | std::io::ErrorKind::TimedOut
+        | std::io::ErrorKind::AddrNotAvailable
+        | std::io::ErrorKind::HostUnreachable
+        | std::io::ErrorKind::NetworkUnreachable => ConnectionFailure::Unreachable,
+        // unresolvable hostnames surface as uncategorized io errors
+        _ if e.to_string().contains("failed to lookup address") => ConnectionFailure::Unreachable,
+        _ => ConnectionFailure::Other,
+      }
+    },
+    Error::PoolTimedOut => ConnectionFailure::Unreachable,
+    _ => ConnectionFailure::Other,
+  }
+}
+
+// postgres insufficient_privilege, mysql ER_DBACCESS_DENIED_ERROR, ER_TABLEACCESS_DENIED_ERROR,
+// and ER_COLUMNACCESS_DENIED_ERROR
+pub fn is_permission_denied(error: &DbError) -> bool {
+  match error {
+    Either::Left(Error::Database(e)) => matches!(e.code().as_deref(), Some("42501" | "1044" | "1142" | "1143")),
+    _ => false,
+  }
+}
+
+// the server couldn't be reached or went away, as opposed to the query itself failing.
+// postgres connection_exception (class 08), admin_shutdown, and cannot_connect_now, and
+// mysql CR_SERVER_GONE_ERROR and CR_SERVER_LOST
+pub fn is_connection_error(error: &DbError) -> bool {
+  match error {
+    Either::Left(Error::Io(_) | Error::Tls(_) | Error::PoolTimedOut | Error::PoolClosed) => true,
+    Either::Left(Error::Database(e)) => {
+      e.code()
+        .is_some_and(|code| code.starts_with("08") || matches!(code.as_ref(), "57P01" | "57P03" | "2006" | "2013"))
+    },
+    _ => false,
+  }
+}
+
+// the server or something in between dropped the connection, e.g. a flaky vpn
+pub fn is_connection_reset(error: &DbError) -> bool {
+  match error {
+    Either::Left(Error::Io(e)) => {
+      matches!(
+        e.kind(),
+        std::io::ErrorKind::ConnectionReset
+          | std::io::ErrorKind::ConnectionAborted
+          | std::io::ErrorKind::BrokenPipe
+          | std::io::ErrorKind::UnexpectedEof
+      )
+    },
+    _ => false,
+  }
+}
+
+// the connection's options pointed at each of the replicas in a host list, in order
+pub fn replica_opts<DB: BuildConnectionOptions>(
+  opts: &<DB::Connection as Connection>::Options,
+  replicas: &str,
+) -> Result<Vec<<DB::Connection as Connection>::Options>, String> {
+  crate::cli::parse_host_list(replicas)
+    .map_err(|e| e.to_string())?
+    .into_iter()
+    .map(|spec| {
+      let mut params = vec![("host".to_owned(), spec.host)];
+      params.extend(spec.port.map(|port| ("port".to_owned(), port.to_string())));
+      DB::with_connection_params(opts, &params)
+    })
+    .collect()
+}
+
+// session_setup runs on every new connection, for settings that only last as long as the connection
+pub async fn init_pool<DB>(
+  opts: <DB::Connection as Connection>::Options,
+  max_connections: u32,
+  session_setup: Vec<String>,
+) -> Result<Pool<DB>, Error>
+where
+  DB: Database + DatabaseQueries,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  PoolOptions::<DB>::new()
+    .max_connections(max_connections)
+    .after_connect(move |conn: &mut DB::Connection, _| {
+      let session_setup = session_setup.clone();
+      Box::pin(async move {
+        for statement in session_setup {
+          conn.execute(sqlx::raw_sql(&statement)).await?;
+        }
+        Ok(())
+      })
+    })
+    .connect_with(opts)
+    .await
+}
+
+// how long queries have spent waiting for a connection from the pool
+#[derive(Debug, Default, Clone)]
+pub struct PoolWaits {
+  pub queries: u64,
+  pub queued: u64,
+  pub total_wait: Duration,
+  pub longest_wait: Duration,
+}
+
+impl PoolWaits {
+  pub fn record(&mut self, queued: bool, wait: Duration) {
+    self.queries += 1;
+    if queued {
+      self.queued += 1;
+    }
+    self.total_wait += wait;
+    self.longest_wait = self.longest_wait.max(wait);
+  }
+
+  pub fn average_wait(&self) -> Duration {
+    if self.queries == 0 {
+      return Duration::ZERO;
+    }
+    self.total_wait / self.queries as u32
+  }
 }
 
-pub async fn init_pool<DB: Database>(opts: <DB::Connection as Connection>::Options) -> Result<Pool<DB>, Error> {
-  PoolOptions::new().max_connections(3).connect_with(opts).await
+// the schema name an attached database file shows up as: its file name without
+// the extension, made unique among the databases that are already attached
+pub fn attach_alias(path: &str, taken: &[String]) -> String {
+  let stem = std::path::Path::new(path).file_stem().map_or("".into(), |stem| stem.to_string_lossy());
+  let base = stem.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect::<String>();
+  let base = if base.is_empty() || matches!(base.as_str(), "main" | "temp") { format!("{}_db", base) } else { base };
+  let mut alias = base.clone();
+  let mut n = 2;
+  while taken.contains(&alias) {
+    alias = format!("{}_{}", base, n);
+    n += 1;
+  }
+  alias
+}
+
+// every connection is open and in use, so the next query has to wait for one
+pub fn pool_is_saturated<DB: Database>(pool: &Pool<DB>) -> bool {
+  pool.num_idle() == 0 && pool.size() >= pool.options().get_max_connections()
+}
+
+pub fn pool_diagnostics<DB: Database>(pool: &Pool<DB>, waits: &PoolWaits) -> String {
+  let open = pool.size();
+  let idle = pool.num_idle() as u32;
+  format!(
+    "Connection pool: {} max, {} open ({} idle, {} in use). {} queries, {} queued for a connection (longest wait \
+     {:.3}s, average {:.3}s).",
+    pool.options().get_max_connections(),
+    open,
+    idle,
+    open.saturating_sub(idle),
+    waits.queries,
+    waits.queued,
+    waits.longest_wait.as_secs_f64(),
+    waits.average_wait().as_secs_f64(),
+  )
 }
 
 // since it's possible for raw_sql to execute multiple queries in a single string,
@@ -93,6 +525,47 @@ where
   }
 }
 
+// like `query`, but acquires the connection up front to record how long it waited for one.
+// also says whether the connection was reset and the query was retried on a fresh one
+pub async fn query_timed<DB>(
+  query: String,
+  dialect: &(dyn Dialect + Sync),
+  pool: &Pool<DB>,
+  waits: &Mutex<PoolWaits>,
+) -> (Result<Rows, DbError>, bool)
+where
+  DB: Database + ValueParser,
+  DB::QueryResult: HasRowsAffected,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  let (first_query, statement) = match get_first_query(query, dialect) {
+    Ok(first_query) => first_query,
+    Err(e) => return (Err(e), false),
+  };
+  let queued = pool_is_saturated(pool);
+  let started = Instant::now();
+  let mut conn = match pool.acquire().await {
+    Ok(conn) => conn,
+    Err(e) => return (Err(DbError::Left(e)), false),
+  };
+  waits.lock().unwrap().record(queued, started.elapsed());
+  let stream = sqlx::raw_sql(&first_query).fetch_many(&mut *conn);
+  match query_stream::<DB>(stream).await {
+    // retried once on a fresh connection. only plain reads are, since anything else may have
+    // gone through before the drop
+    Err(e) if is_connection_reset(&e) && reads_only(&statement) => {
+      log::warn!("connection was reset, retrying: {}", e);
+      conn.close().await.ok();
+      let results = match pool.acquire().await {
+        Ok(mut conn) => query_stream::<DB>(sqlx::raw_sql(&first_query).fetch_many(&mut *conn)).await,
+        Err(e) => Err(DbError::Left(e)),
+      };
+      (results, true)
+    },
+    results => (results, false),
+  }
+}
+
 #[allow(clippy::type_complexity)]
 pub async fn query_stream<DB>(
   mut stream: BoxStream<'_, Result<Either<DB::QueryResult, DB::Row>, Error>>,
@@ -183,11 +656,13 @@ pub fn statement_type_string(statement: &Statement) -> String {
     .to_string()
 }
 
+// a confirmed UPDATE or DELETE still runs in a transaction, so it can be rolled back
 pub fn get_execution_type(statement: Statement, confirmed: bool) -> ExecutionType {
-  if confirmed {
-    return ExecutionType::Normal;
+  // writing to every row of a table is rarely intended, so it always asks first
+  if !confirmed && unfiltered_write_target(&statement).is_some() {
+    return ExecutionType::Confirm;
   }
-  match statement {
+  let execution_type = match statement {
     Statement::AlterIndex { .. }
     | Statement::AlterView { .. }
     | Statement::AlterRole { .. }
@@ -210,15 +685,239 @@ pub fn get_execution_type(statement: Statement, confirmed: bool) -> ExecutionTyp
       ExecutionType::Confirm
     },
     Statement::Explain { statement, analyze, .. }
-      if analyze && matches!(statement.as_ref(), Statement::Delete(_) | Statement::Update { .. }) =>
+      if analyze
+        && matches!(statement.as_ref(), Statement::Delete(_) | Statement::Update { .. } | Statement::Insert(_)) =>
     {
       ExecutionType::Transaction
     },
+    // analyzing runs the statement, so any other write asks first
+    Statement::Explain { statement, analyze, .. } if analyze && is_write_statement(&statement) => {
+      ExecutionType::Confirm
+    },
     Statement::Explain { .. } => ExecutionType::Normal,
     _ => ExecutionType::Normal,
+  };
+  match execution_type {
+    ExecutionType::Confirm if confirmed => ExecutionType::Normal,
+    execution_type => execution_type,
+  }
+}
+
+// the table that an UPDATE or DELETE without a WHERE clause (or anything else
+// narrowing down its rows, like joins or a LIMIT) would write every row of
+pub fn unfiltered_write_target(statement: &Statement) -> Option<String> {
+  let target: &TableWithJoins = match statement {
+    Statement::Update { table, selection: None, .. } => table,
+    Statement::Delete(delete) if delete.selection.is_none() && delete.limit.is_none() && delete.using.is_none() => {
+      match &delete.from {
+        FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from) => {
+          match from.as_slice() {
+            [from] => from,
+            _ => return None,
+          }
+        },
+      }
+    },
+    Statement::Explain { statement, analyze: true, .. } => return unfiltered_write_target(statement),
+    _ => return None,
+  };
+  match &target.relation {
+    TableFactor::Table { name, .. } if target.joins.is_empty() => Some(name.to_string()),
+    _ => None,
   }
 }
 
+// a SELECT of the rows an UPDATE or DELETE is about to change. None for other statements, and
+// for ones whose rows can't be selected the same way (joins, USING, FROM, or their own LIMIT)
+pub fn affected_rows_query(statement: &Statement, limit: u64) -> Option<String> {
+  let (target, selection) = match statement {
+    Statement::Update { table, from: None, selection, .. } => (table, selection),
+    Statement::Delete(delete) if delete.using.is_none() && delete.limit.is_none() && delete.order_by.is_empty() => {
+      match &delete.from {
+        FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from) => {
+          match from.as_slice() {
+            [from] => (from, &delete.selection),
+            _ => return None,
+          }
+        },
+      }
+    },
+    _ => return None,
+  };
+  if !target.joins.is_empty() {
+    return None;
+  }
+  let filter = selection.as_ref().map(|selection| format!(" WHERE {}", selection)).unwrap_or_default();
+  Some(format!("SELECT * FROM {}{} LIMIT {}", target.relation, filter, limit))
+}
+
+// reads the rows a write will change inside its transaction, just before it runs. the read
+// gets a savepoint of its own, since a failed statement would otherwise abort a postgres transaction
+pub async fn preview_affected_rows<DB>(
+  tx: &mut Transaction<'static, DB>,
+  statement: &Statement,
+  limit: u64,
+) -> Option<Rows>
+where
+  DB: Database + ValueParser,
+  DB::QueryResult: HasRowsAffected,
+  for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+{
+  let query = affected_rows_query(statement, limit)?;
+  let mut savepoint = Connection::begin(&mut **tx).await.map_err(|e| log::error!("{e:?}")).ok()?;
+  let rows = query_stream::<DB>(sqlx::raw_sql(&query).fetch_many(&mut *savepoint)).await;
+  if let Err(e) = savepoint.rollback().await {
+    log::error!("{e:?}");
+  }
+  rows.map_err(|e| log::error!("{e:?}")).ok()
+}
+
+// a SELECT with a LIMIT added, if it doesn't already limit its rows. SELECT INTO
+// is left alone, since limiting it would change what it writes, and so are locking
+// reads, since mysql wants the LIMIT before the FOR UPDATE
+pub fn limit_statement(statement: &Statement, limit: u64) -> Option<Statement> {
+  let Statement::Query(query) = statement else {
+    return None;
+  };
+  if query.limit.is_some() || query.fetch.is_some() || !query.locks.is_empty() {
+    return None;
+  }
+  match query.body.as_ref() {
+    SetExpr::Select(select) if select.into.is_none() => {},
+    SetExpr::SetOperation { .. } | SetExpr::Query(_) => {},
+    _ => return None,
+  }
+  let mut query = query.clone();
+  query.limit = Some(Expr::Value(sqlparser::ast::Value::Number(limit.to_string(), false)));
+  Some(Statement::Query(query))
+}
+
+// the query as written, with limit_statement's LIMIT added after its last token, so its
+// comments and formatting are kept. None when limit_statement leaves it alone
+pub fn limit_query(query: &str, statement: &Statement, dialect: &dyn Dialect, limit: u64) -> Option<String> {
+  limit_statement(statement, limit)?;
+  let tokens = Tokenizer::new(dialect, query).tokenize_with_location().ok()?;
+  let last = tokens.iter().rposition(|token| !matches!(token.token, Token::Whitespace(_) | Token::SemiColon))?;
+  // sqlparser locations are 1-based and count chars
+  let offset = match tokens.get(last + 1) {
+    Some(next) => {
+      let line_start = query.split('\n').take(next.location.line.saturating_sub(1) as usize).map(|l| l.len() + 1).sum();
+      query[line_start..]
+        .char_indices()
+        .nth(next.location.column.saturating_sub(1) as usize)
+        .map_or(query.len(), |(i, _)| line_start + i)
+    },
+    None => query.len(),
+  };
+  Some(format!("{} limit {}{}", &query[..offset], limit, &query[offset..]))
+}
+
+pub fn count_rows_query(table: &str) -> String {
+  format!("select count(*) from {}", table)
+}
+
+// statements whose outcome is worth keeping in the history's audit trail
+pub fn is_write_statement(statement: &Statement) -> bool {
+  matches!(
+    statement,
+    Statement::Insert(_)
+      | Statement::Update { .. }
+      | Statement::Delete(_)
+      | Statement::Merge { .. }
+      | Statement::Truncate { .. }
+      | Statement::Drop { .. }
+      | Statement::CreateTable(_)
+      | Statement::CreateIndex(_)
+      | Statement::CreateView { .. }
+      | Statement::AlterTable { .. }
+      | Statement::AlterIndex { .. }
+      | Statement::AlterView { .. }
+  )
+}
+
+// COPY FROM STDIN needs its data sent separately, so it can't run as a plain query
+pub fn is_copy_from_stdin(statement: &Statement) -> bool {
+  matches!(statement, Statement::Copy { to: false, target: CopyTarget::Stdin, .. })
+}
+
+// plain reads that a replica can serve. locking reads, `SELECT ... INTO`, and ctes that
+// modify data all have to run on the primary
+pub fn reads_only(statement: &Statement) -> bool {
+  fn query_reads_only(query: &Query) -> bool {
+    query.locks.is_empty()
+      && query.with.as_ref().map_or(true, |with| with.cte_tables.iter().all(|cte| query_reads_only(&cte.query)))
+      && set_reads_only(&query.body)
+  }
+  fn set_reads_only(body: &SetExpr) -> bool {
+    match body {
+      SetExpr::Select(select) => select.into.is_none(),
+      SetExpr::Query(query) => query_reads_only(query),
+      SetExpr::SetOperation { left, right, .. } => set_reads_only(left) && set_reads_only(right),
+      SetExpr::Values(_) | SetExpr::Table(_) => true,
+      _ => false,
+    }
+  }
+  matches!(statement, Statement::Query(query) if query_reads_only(query))
+}
+
+// the execution type from get_execution_type, unless the statement_policy setting overrides it
+pub fn get_execution_type_with_policy(
+  statement: Statement,
+  confirmed: bool,
+  policy: Option<&StatementPolicy>,
+) -> ExecutionType {
+  if confirmed {
+    return get_execution_type(statement, confirmed);
+  }
+  StatementKind::of(&statement)
+    .and_then(|kind| policy?.get(&kind).cloned())
+    .unwrap_or_else(|| get_execution_type(statement, confirmed))
+}
+
+// what running the query would do, without running it: the statement as it will be sent,
+// and how it will be executed and why
+pub fn dry_run(
+  query: String,
+  dialect: &dyn Dialect,
+  policy: Option<&StatementPolicy>,
+  auto_limit: Option<u64>,
+) -> Result<String, DbError> {
+  let (_, statement) = get_first_query(query.clone(), dialect)?;
+  let execution_type = get_execution_type_with_policy(statement.clone(), false, policy);
+  let runs_as = match execution_type {
+    ExecutionType::Normal => "right away",
+    ExecutionType::Confirm => "after asking for confirmation",
+    ExecutionType::Transaction => "in a transaction that waits for a commit or rollback",
+  };
+  let reason = match (
+    StatementKind::of(&statement).filter(|kind| policy.is_some_and(|policy| policy.contains_key(kind))),
+    unfiltered_write_target(&statement),
+  ) {
+    (Some(kind), _) => format!("statement_policy.{} is set", kind.name()),
+    (None, Some(table)) => format!("it writes to every row of {}", table),
+    (None, None) => format!("the default for {} statements", statement_type_string(&statement).to_uppercase()),
+  };
+  let limited = match execution_type {
+    ExecutionType::Normal => auto_limit.and_then(|limit| limit_query(&query, &statement, dialect, limit)),
+    _ => None,
+  };
+  let mut lines = vec![
+    format!("statement: {}", statement_type_string(&statement).to_uppercase()),
+    format!("runs {} ({})", runs_as, reason),
+  ];
+  match limited {
+    Some(limited) => {
+      lines.push("auto_limit adds a LIMIT, so it executes:".to_owned());
+      lines.push(limited);
+    },
+    None => {
+      lines.push("executes:".to_owned());
+      lines.push(query);
+    },
+  }
+  Ok(lines.join("\n"))
+}
+
 pub fn get_headers<DB: Database + ValueParser>(row: &DB::Row) -> Headers {
   row
     .columns()
@@ -243,7 +942,17 @@ pub fn row_to_json<DB: Database + ValueParser>(row: &DB::Row) -> HashMap<String,
 pub fn vec_to_string<T: std::string::ToString>(vec: Vec<T>) -> String {
   let mut content = String::new();
   for (i, elem) in vec.iter().enumerate() {
-    content.push_str(&elem.to_string());
+    // elements are quoted the way postgres quotes them, so the cell can be split back apart
+    let elem = elem.to_string();
+    if elem.is_empty()
+      || elem.eq_ignore_ascii_case("null")
+      || elem.trim() != elem
+      || elem.contains([',', '{', '}', '"', '\\'])
+    {
+      content.push_str(&format!("\"{}\"", elem.replace('\\', "\\\\").replace('"', "\\\"")));
+    } else {
+      content.push_str(&elem);
+    }
     if i != vec.len() - 1 {
       content.push_str(", ");
     }
@@ -251,10 +960,472 @@ pub fn vec_to_string<T: std::string::ToString>(vec: Vec<T>) -> String {
   "{ ".to_owned() + &*content + &*" }".to_owned()
 }
 
+/// Splits an array (`{a,b}`) or record (`(a,b)`) literal into its top-level
+/// elements, unquoting them. Nested arrays are returned as their literal text.
+pub fn parse_array_literal(literal: &str) -> Option<Vec<String>> {
+  let literal = literal.trim();
+  let inner = literal
+    .strip_prefix('{')
+    .and_then(|l| l.strip_suffix('}'))
+    .or_else(|| literal.strip_prefix('(').and_then(|l| l.strip_suffix(')')))?;
+  if inner.trim().is_empty() {
+    return Some(vec![]);
+  }
+  let mut elements = vec![];
+  let mut element = String::new();
+  let mut quoted = false;
+  let mut depth = 0;
+  let mut chars = inner.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' if quoted => element.extend(chars.next()),
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        chars.next();
+        element.push('"');
+      },
+      '"' => quoted = !quoted,
+      '{' | '(' if !quoted => {
+        depth += 1;
+        element.push(c);
+      },
+      '}' | ')' if !quoted => {
+        depth -= 1;
+        element.push(c);
+      },
+      ',' if !quoted && depth == 0 => elements.push(std::mem::take(&mut element).trim().to_owned()),
+      c => element.push(c),
+    }
+  }
+  if quoted || depth != 0 {
+    return None;
+  }
+  elements.push(element.trim().to_owned());
+  Some(elements)
+}
+
 pub fn row_to_vec<DB: Database + ValueParser>(row: &DB::Row) -> Vec<String> {
   row.columns().iter().map(|col| DB::parse_value(row, col).unwrap().string).collect()
 }
 
+// returns the table a query reads from, if it reads from exactly one table
+pub fn get_source_table(statement: &Statement) -> Option<String> {
+  let Statement::Query(query) = statement else {
+    return None;
+  };
+  let SetExpr::Select(select) = query.body.as_ref() else {
+    return None;
+  };
+  match select.from.as_slice() {
+    [from] if from.joins.is_empty() => {
+      match &from.relation {
+        TableFactor::Table { name, .. } => Some(name.to_string()),
+        _ => None,
+      }
+    },
+    _ => None,
+  }
+}
+
+pub fn is_numeric_type(type_name: &str) -> bool {
+  let type_name = type_name.to_uppercase();
+  let base_type = type_name.split(['(', ' ']).next().unwrap_or_default();
+  matches!(
+    base_type,
+    "INT"
+      | "INT2"
+      | "INT4"
+      | "INT8"
+      | "INTEGER"
+      | "TINYINT"
+      | "SMALLINT"
+      | "MEDIUMINT"
+      | "BIGINT"
+      | "SERIAL"
+      | "SMALLSERIAL"
+      | "BIGSERIAL"
+      | "REAL"
+      | "FLOAT"
+      | "FLOAT4"
+      | "FLOAT8"
+      | "DOUBLE"
+      | "NUMERIC"
+      | "DECIMAL"
+      | "OID"
+  )
+}
+
+// numbers and booleans are only written bare when they parse as one, so anything
+// else (NaN and infinity included) is quoted and left to the database to cast
+fn sql_literal<DB: DatabaseQueries>(value: &str, type_name: &str) -> String {
+  if value == "NULL" || is_bare_literal(value, type_name) {
+    value.to_owned()
+  } else {
+    DB::quote_literal(value)
+  }
+}
+
+fn is_bare_literal(value: &str, type_name: &str) -> bool {
+  let upper_type = type_name.to_uppercase();
+  if is_numeric_type(type_name) {
+    value.parse::<f64>().is_ok_and(f64::is_finite)
+  } else if upper_type == "BOOL" || upper_type == "BOOLEAN" {
+    value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false")
+  } else {
+    false
+  }
+}
+
+// NULL never matches anything in an IN list, so NULLs are matched with IS NULL instead,
+// and repeated values are left out
+pub fn in_list<DB: DatabaseQueries>(column: &str, values: &[&str], type_name: &str) -> String {
+  let column = DB::quote_identifier(column);
+  let mut seen = HashSet::new();
+  let literals = values
+    .iter()
+    .filter(|value| **value != "NULL" && seen.insert(**value))
+    .map(|value| sql_literal::<DB>(value, type_name))
+    .collect::<Vec<String>>();
+  match (literals.is_empty(), values.contains(&"NULL")) {
+    (true, true) => format!("{} IS NULL", column),
+    (false, true) => format!("({} IN ({}) OR {} IS NULL)", column, literals.join(", "), column),
+    // an empty list isn't valid sql, and IN (NULL) matches nothing just the same
+    (true, false) => format!("{} IN (NULL)", column),
+    (false, false) => format!("{} IN ({})", column, literals.join(", ")),
+  }
+}
+
+// NULL values and the string "NULL" can't be distinguished once the rows are
+// parsed, so "NULL" is always written as a NULL literal
+pub fn rows_to_insert_statements<DB: DatabaseQueries>(table: &str, headers: &Headers, rows: &[Vec<String>]) -> String {
+  let columns = headers.iter().map(|h| DB::quote_identifier(&h.name)).collect::<Vec<String>>().join(", ");
+  rows
+    .iter()
+    .map(|row| {
+      let values = row
+        .iter()
+        .zip(headers.iter())
+        .map(|(value, header)| sql_literal::<DB>(value, &header.type_name))
+        .collect::<Vec<String>>()
+        .join(", ");
+      format!("INSERT INTO {} ({}) VALUES ({});", table, columns, values)
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+// the rows as a table of plain text, the way psql prints them, with each column
+// padded to its widest value. values with several lines are put on one
+pub fn rows_to_text(headers: &Headers, rows: &[Vec<String>]) -> String {
+  let flatten = |value: &str| value.replace('\n', "\\n");
+  let widths = headers
+    .iter()
+    .enumerate()
+    .map(|(i, header)| {
+      rows
+        .iter()
+        .filter_map(|row| row.get(i))
+        .map(|value| flatten(value).chars().count())
+        .fold(header.name.chars().count(), usize::max)
+    })
+    .collect::<Vec<usize>>();
+  let line = |values: Vec<String>| {
+    values
+      .iter()
+      .zip(widths.iter())
+      .map(|(value, width)| format!("{:width$}", value, width = width))
+      .collect::<Vec<String>>()
+      .join(" | ")
+      .trim_end()
+      .to_owned()
+  };
+  let mut lines = vec![
+    line(headers.iter().map(|h| h.name.clone()).collect()),
+    widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<String>>().join("-+-"),
+  ];
+  lines.extend(rows.iter().map(|row| line(row.iter().map(|value| flatten(value)).collect())));
+  lines.join("\n") + "\n"
+}
+
+// the rows as csv with a header line. fields with commas, quotes, or newlines are quoted
+pub fn rows_to_csv(headers: &Headers, rows: &[Vec<String>]) -> String {
+  let field = |value: &str| {
+    if value.contains([',', '"', '\n', '\r']) {
+      format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+      value.to_owned()
+    }
+  };
+  let line = |values: Vec<&str>| values.into_iter().map(field).collect::<Vec<String>>().join(",");
+  let mut lines = vec![line(headers.iter().map(|h| h.name.as_str()).collect())];
+  lines.extend(rows.iter().map(|row| line(row.iter().map(|value| value.as_str()).collect())));
+  lines.join("\n") + "\n"
+}
+
+// splits text pasted from a spreadsheet or csv file into rows. tab-separated
+// text is split as is; otherwise it is read as csv, where quoted fields can
+// contain commas, newlines, and doubled quotes
+pub fn parse_tabular_text(text: &str) -> Vec<Vec<String>> {
+  let text = text.replace("\r\n", "\n");
+  let text = text.trim_end_matches('\n');
+  if text.contains('\t') {
+    return text.split('\n').map(|line| line.split('\t').map(|v| v.to_owned()).collect()).collect();
+  }
+  let mut rows = vec![];
+  let mut row = vec![];
+  let mut field = String::new();
+  let mut quoted = false;
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        field.push('"');
+        chars.next();
+      },
+      '"' if quoted => quoted = false,
+      '"' if field.is_empty() => quoted = true,
+      ',' if !quoted => row.push(std::mem::take(&mut field)),
+      '\n' if !quoted => {
+        row.push(std::mem::take(&mut field));
+        rows.push(std::mem::take(&mut row));
+      },
+      c => field.push(c),
+    }
+  }
+  row.push(field);
+  rows.push(row);
+  rows
+}
+
+// reads (name, type) pairs out of the results of a driver's preview_columns_query
+pub fn column_types(columns: &Rows) -> Vec<(String, String)> {
+  let index_of = |names: &[&str]| columns.headers.iter().position(|h| names.contains(&h.name.to_lowercase().as_str()));
+  let (Some(name), Some(type_name)) = (index_of(&["column_name", "name"]), index_of(&["data_type", "type"])) else {
+    return vec![];
+  };
+  let mut rows = columns.rows.iter().collect::<Vec<_>>();
+  if let Some(position) = index_of(&["ordinal_position", "cid"]) {
+    rows.sort_by_key(|row| row.get(position).and_then(|p| p.parse::<i64>().ok()));
+  }
+  rows.iter().filter_map(|row| Some((row.get(name)?.clone(), row.get(type_name)?.clone()))).collect()
+}
+
+fn pasted_literal<DB: DatabaseQueries>(value: &str, type_name: &str) -> String {
+  let trimmed = value.trim();
+  if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("null") {
+    "NULL".to_owned()
+  } else if is_bare_literal(trimmed, type_name) {
+    trimmed.to_uppercase()
+  } else {
+    DB::quote_literal(value)
+  }
+}
+
+// the operators offered when filtering a table's rows. the null checks don't take a value
+pub const FILTER_OPERATORS: [&str; 9] = ["=", "<>", "<", "<=", ">", ">=", "like", "is null", "is not null"];
+
+// a WHERE clause predicate comparing a column to a typed value, which is quoted
+// according to the column's type
+pub fn filter_predicate<DB: DatabaseQueries>(
+  column: &str,
+  type_name: &str,
+  operator: &str,
+  value: &str,
+) -> Result<String, String> {
+  let column_ref = DB::quote_identifier(column);
+  let trimmed = value.trim();
+  match operator {
+    "is null" | "is not null" => Ok(format!("{} {}", column_ref, operator)),
+    _ if !FILTER_OPERATORS.contains(&operator) => Err(format!("{} is not a supported operator.", operator)),
+    _ if trimmed.is_empty() => Err(format!("Enter a value to compare {} to.", column)),
+    _ if trimmed.eq_ignore_ascii_case("null") => Err("Use \"is null\" to match NULL values.".to_owned()),
+    "like" => Ok(format!("{} like {}", column_ref, DB::quote_literal(value))),
+    _ if is_numeric_type(type_name) && trimmed.parse::<f64>().is_err() => {
+      Err(format!("{} is a number column ({}), but {} is not a number.", column, type_name, trimmed))
+    },
+    _ => Ok(format!("{} {} {}", column_ref, operator, pasted_literal::<DB>(value, type_name))),
+  }
+}
+
+pub fn filtered_rows_query(table: &str, predicate: &str) -> String {
+  format!("select * from {} where {} limit 100", table, predicate)
+}
+
+// the rows a foreign key value points to, found through the cached schema. None when
+// the column isn't part of a foreign key or the value is NULL
+pub fn referenced_rows_query<DB: DatabaseQueries>(
+  schema: &SchemaCache,
+  dialect: &dyn Dialect,
+  table: &str,
+  header: &Header,
+  value: &str,
+) -> Option<String> {
+  if value == "NULL" {
+    return None;
+  }
+  let (table_schema, table_name) = parse_table_name(table, dialect)?;
+  let column = schema.columns(table_schema.as_deref(), &table_name)?.iter().find(|c| c.name == header.name)?;
+  let reference = column.references.first()?;
+  // sqlite leaves the column out when the key refers to the other table's primary key
+  let foreign_column = match reference.column.as_str() {
+    "" => {
+      let mut keys = schema.columns(Some(&reference.schema), &reference.table)?.iter().filter(|c| c.primary_key);
+      match (keys.next(), keys.next()) {
+        (Some(key), None) => key.name.clone(),
+        _ => return None,
+      }
+    },
+    column => column.to_owned(),
+  };
+  let foreign_table = match reference.schema.as_str() {
+    "" => DB::quote_identifier(&reference.table),
+    foreign_schema => format!("{}.{}", DB::quote_identifier(foreign_schema), DB::quote_identifier(&reference.table)),
+  };
+  let predicate =
+    format!("{} = {}", DB::quote_identifier(&foreign_column), sql_literal::<DB>(value, &header.type_name));
+  Some(filtered_rows_query(&foreign_table, &predicate))
+}
+
+// builds one multi-row INSERT out of pasted rows. when the first row names the
+// table's columns, it is used as the column list; otherwise the values are
+// matched to the table's columns in order
+pub fn insert_rows_statement<DB: DatabaseQueries>(
+  table: &str,
+  columns: &[(String, String)],
+  rows: &[Vec<String>],
+) -> Result<String, String> {
+  if columns.is_empty() {
+    return Err(format!("No columns were found for {}.", table));
+  }
+  let Some(first) = rows.first() else {
+    return Err("There are no rows to insert.".to_owned());
+  };
+  let find_column = |name: &str| columns.iter().find(|(c, _)| c.eq_ignore_ascii_case(name.trim()));
+  let (target_columns, rows) = match first.iter().map(|name| find_column(name)).collect::<Option<Vec<_>>>() {
+    Some(header) => (header, &rows[1..]),
+    None => {
+      let width = rows.iter().map(|r| r.len()).max().unwrap_or_default();
+      if width > columns.len() {
+        return Err(format!("The rows have {} values, but {} only has {} columns.", width, table, columns.len()));
+      }
+      (columns[..width].iter().collect(), rows)
+    },
+  };
+  if rows.is_empty() {
+    return Err("There are no rows to insert.".to_owned());
+  }
+  let column_list =
+    target_columns.iter().map(|(name, _)| DB::quote_identifier(name)).collect::<Vec<String>>().join(", ");
+  let values = rows
+    .iter()
+    .map(|row| {
+      let literals = target_columns
+        .iter()
+        .enumerate()
+        .map(|(i, (_, type_name))| pasted_literal::<DB>(row.get(i).map_or("", |v| v.as_str()), type_name))
+        .collect::<Vec<String>>();
+      format!("  ({})", literals.join(", "))
+    })
+    .collect::<Vec<String>>()
+    .join(",\n");
+  Ok(format!("INSERT INTO {} ({}) VALUES\n{};", table, column_list, values))
+}
+
+// splits a table name as written in a query (ex. `public."Users"`) into its
+// unquoted schema and table parts
+pub fn parse_table_name(table: &str, dialect: &dyn Dialect) -> Option<(Option<String>, String)> {
+  let name = Parser::new(dialect).try_with_sql(table).ok()?.parse_object_name(false).ok()?;
+  match name.0.as_slice() {
+    [table] => Some((None, table.value.clone())),
+    [.., schema, table] => Some((Some(schema.value.clone()), table.value.clone())),
+    _ => None,
+  }
+}
+
+// matches a single row by its primary key. returns None if the table has no
+// primary key or one of the key columns isn't part of the results
+pub fn primary_key_predicate<DB: DatabaseQueries>(
+  headers: &Headers,
+  row: &[String],
+  key_columns: &[String],
+) -> Option<String> {
+  if key_columns.is_empty() {
+    return None;
+  }
+  key_columns
+    .iter()
+    .map(|key| {
+      let index = headers.iter().position(|h| &h.name == key)?;
+      Some(format!("{} = {}", DB::quote_identifier(key), sql_literal::<DB>(&row[index], &headers[index].type_name)))
+    })
+    .collect::<Option<Vec<String>>>()
+    .map(|predicates| predicates.join(" AND "))
+}
+
+// a None value sets the cell to NULL, so the string "NULL" can still be written as text.
+// numbers and booleans have to parse as one, since they're written bare
+pub fn update_cell_statement<DB: DatabaseQueries>(
+  table: &str,
+  headers: &Headers,
+  row: &[String],
+  column: usize,
+  value: Option<&str>,
+  key_columns: &[String],
+) -> Result<String, String> {
+  let predicate = primary_key_predicate::<DB>(headers, row, key_columns)
+    .ok_or_else(|| format!("Can't edit this cell: no primary key of {} was found in the results.", table))?;
+  let header = headers.get(column).ok_or_else(|| "Can't edit this cell: the column wasn't found.".to_owned())?;
+  let literal = match value {
+    None => "NULL".to_owned(),
+    Some(value) if is_bare_literal(value, &header.type_name) => value.to_owned(),
+    // NaN and infinity parse, but are only valid quoted
+    Some(value) if is_numeric_type(&header.type_name) && value.parse::<f64>().is_err() => {
+      return Err(format!("{} is a number column ({}), but {} is not a number.", header.name, header.type_name, value));
+    },
+    Some(value) if matches!(header.type_name.to_uppercase().as_str(), "BOOL" | "BOOLEAN") => {
+      return Err(format!("{} is a boolean column, so enter true or false.", header.name));
+    },
+    Some(value) => DB::quote_literal(value),
+  };
+  Ok(format!("UPDATE {} SET {} = {} WHERE {};", table, DB::quote_identifier(&header.name), literal, predicate))
+}
+
+pub fn delete_row_statement<DB: DatabaseQueries>(
+  table: &str,
+  headers: &Headers,
+  row: &[String],
+  key_columns: &[String],
+) -> Option<String> {
+  let predicate = primary_key_predicate::<DB>(headers, row, key_columns)?;
+  Some(format!("DELETE FROM {} WHERE {};", table, predicate))
+}
+
+// the planner's estimate of how many rows a query returns, from the output of a plain EXPLAIN.
+// postgres puts it on the top plan node ("rows=N"), and mysql estimates the rows examined
+// for each table in a "rows" column, so those are multiplied together
+pub fn estimated_rows(plan: &Rows) -> Option<u64> {
+  if let Some(column) = plan.headers.iter().position(|h| h.name.eq_ignore_ascii_case("rows")) {
+    return plan
+      .rows
+      .iter()
+      .filter_map(|row| row.get(column)?.parse::<u64>().ok())
+      .reduce(|total, rows| total.saturating_mul(rows));
+  }
+  let (_, estimate) = plan.rows.first()?.first()?.split_once("rows=")?;
+  estimate.chars().take_while(|c| c.is_ascii_digit()).collect::<String>().parse().ok()
+}
+
+// what rainfrog's sessions are called on the server, unless the application_name setting says otherwise
+pub fn default_application_name() -> String {
+  format!("rainfrog/{}", env!("CARGO_PKG_VERSION"))
+}
+
+// a quoted like pattern matching a user-provided search term anywhere, with its escape
+// clause. % and _ in the term match themselves rather than acting as wildcards
+pub fn like_pattern<DB: DatabaseQueries>(search: Option<&str>) -> String {
+  let escaped = search.unwrap_or("").trim().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+  format!("{} escape {}", DB::quote_literal(&format!("%{}%", escaped)), DB::quote_literal("\\"))
+}
+
 pub fn get_keywords() -> Vec<String> {
   keywords::ALL_KEYWORDS.iter().map(|k| k.to_string()).collect()
 }
@@ -7,8 +7,8 @@ use std::{
 use serde_json;
 use sqlparser::ast::Statement;
 use sqlx::{
-  mysql::{MySql, MySqlConnectOptions, MySqlQueryResult},
-  Column, Database, Row, ValueRef,
+  mysql::{MySql, MySqlConnectOptions, MySqlConnection, MySqlQueryResult, MySqlSslMode},
+  Column, Connection, Database, Row, ValueRef,
 };
 
 use super::{vec_to_string, Value};
@@ -24,9 +24,19 @@ impl super::BuildConnectionOptions for MySql {
     args: crate::cli::Cli,
   ) -> color_eyre::eyre::Result<<Self::Connection as sqlx::Connection>::Options> {
     match args.connection_url {
-      Some(url) => Ok(MySqlConnectOptions::from_str(&url)?),
+      Some(url) => {
+        let candidates = crate::cli::expand_host_list(&url)?
+          .iter()
+          .map(|url| {
+            let opts = MySqlConnectOptions::from_str(url)?;
+            let host = crate::cli::unbracket_host(opts.get_host()).to_owned();
+            with_ssl_options(opts.host(&host), &args.ssl)
+          })
+          .collect::<color_eyre::eyre::Result<Vec<_>>>()?;
+        select_host(candidates)
+      },
       None => {
-        let mut opts = MySqlConnectOptions::new();
+        let mut opts = with_ssl_options(MySqlConnectOptions::new(), &args.ssl)?;
 
         // Username
         if let Some(user) = args.user {
@@ -54,23 +64,22 @@ impl super::BuildConnectionOptions for MySql {
         }
 
         // Host
-        if let Some(host) = args.host {
-          opts = opts.host(&host);
-        } else {
-          let mut host = String::new();
-          print!("host (ex. localhost): ");
-          io::stdout().flush()?;
-          io::stdin().read_line(&mut host)?;
-          let host = host.trim();
-          if !host.is_empty() {
-            opts = opts.host(host);
-          }
-        }
+        let host = match (args.host, &args.socket) {
+          (Some(host), _) => host,
+          (None, Some(_)) => String::new(),
+          (None, None) => {
+            let mut host = String::new();
+            print!("host (ex. localhost): ");
+            io::stdout().flush()?;
+            io::stdin().read_line(&mut host)?;
+            host.trim().to_owned()
+          },
+        };
 
         // Port
         if let Some(port) = args.port {
           opts = opts.port(port);
-        } else {
+        } else if args.socket.is_none() {
           let mut port = String::new();
           print!("port (ex. 3306): ");
           io::stdout().flush()?;
@@ -95,15 +104,130 @@ impl super::BuildConnectionOptions for MySql {
           }
         }
 
-        Ok(opts)
+        if let Some(socket) = &args.socket {
+          opts = opts.socket(socket);
+        }
+
+        if host.is_empty() {
+          return Ok(opts);
+        }
+        let candidates = crate::cli::parse_host_list(&host)?
+          .into_iter()
+          .map(|spec| {
+            let opts = opts.clone().host(&spec.host);
+            match spec.port {
+              Some(port) => opts.port(port),
+              None => opts,
+            }
+          })
+          .collect();
+        select_host(candidates)
       },
     }
   }
+
+  // program_name is a connection attribute, which sqlx doesn't send
+  fn with_settings(
+    opts: <Self::Connection as sqlx::Connection>::Options,
+    ssl: &crate::cli::SslOptions,
+    application_name: Option<&str>,
+  ) -> color_eyre::eyre::Result<<Self::Connection as sqlx::Connection>::Options> {
+    with_ssl_options(opts, ssl)
+  }
+
+  fn describe_connection(opts: &<Self::Connection as sqlx::Connection>::Options) -> String {
+    let server = opts.get_socket().map_or(opts.get_host().to_owned(), |socket| socket.display().to_string());
+    match opts.get_database() {
+      Some(database) => format!("{}/{}", server, database),
+      None => server,
+    }
+  }
+
+  fn connection_params(opts: &<Self::Connection as sqlx::Connection>::Options) -> Vec<(String, String)> {
+    vec![
+      ("host".to_owned(), opts.get_host().to_owned()),
+      ("port".to_owned(), opts.get_port().to_string()),
+      ("database".to_owned(), opts.get_database().unwrap_or_default().to_owned()),
+      ("user".to_owned(), opts.get_username().to_owned()),
+    ]
+  }
+
+  fn with_connection_params(
+    opts: &<Self::Connection as sqlx::Connection>::Options,
+    params: &[(String, String)],
+  ) -> Result<<Self::Connection as sqlx::Connection>::Options, String> {
+    let mut opts = opts.clone();
+    for (name, value) in params.iter().filter(|(_, value)| !value.trim().is_empty()) {
+      opts = match name.as_str() {
+        "host" => opts.host(value.trim()),
+        "port" => opts.port(super::parse_port(value)?),
+        "database" => opts.database(value.trim()),
+        "user" => opts.username(value.trim()),
+        _ => opts,
+      };
+    }
+    Ok(opts)
+  }
+}
+
+fn with_ssl_options(
+  mut opts: MySqlConnectOptions,
+  ssl: &crate::cli::SslOptions,
+) -> color_eyre::eyre::Result<MySqlConnectOptions> {
+  if let Some(mode) = &ssl.mode {
+    opts = opts.ssl_mode(ssl_mode(mode)?);
+  }
+  if let Some(ca) = &ssl.ca {
+    opts = opts.ssl_ca(ca);
+  }
+  if let Some(cert) = &ssl.cert {
+    opts = opts.ssl_client_cert(cert);
+  }
+  if let Some(key) = &ssl.key {
+    opts = opts.ssl_client_key(key);
+  }
+  Ok(opts)
+}
+
+// postgres' names are accepted too, so one setting can cover both drivers
+fn ssl_mode(mode: &str) -> Result<MySqlSslMode, sqlx::Error> {
+  let mode = match mode.to_lowercase().as_str() {
+    "disable" => "disabled".to_owned(),
+    "prefer" => "preferred".to_owned(),
+    "require" => "required".to_owned(),
+    "verify-ca" => "verify_ca".to_owned(),
+    "verify-full" => "verify_identity".to_owned(),
+    other => other.to_owned(),
+  };
+  MySqlSslMode::from_str(&mode)
+}
+
+// picks the first host in the list that accepts a connection
+fn select_host(mut candidates: Vec<MySqlConnectOptions>) -> color_eyre::eyre::Result<MySqlConnectOptions> {
+  if candidates.len() == 1 {
+    return Ok(candidates.remove(0));
+  }
+  tokio::task::block_in_place(|| {
+    tokio::runtime::Handle::current().block_on(async {
+      let mut errors = vec![];
+      for opts in candidates {
+        match MySqlConnection::connect_with(&opts).await {
+          Ok(conn) => {
+            conn.close().await?;
+            return Ok(opts);
+          },
+          Err(e) => errors.push(format!("{}: {}", opts.get_host(), e)),
+        }
+      }
+      Err(color_eyre::eyre::eyre!("No host accepted the connection ({})", errors.join("; ")))
+    })
+  })
 }
 
 impl super::DatabaseQueries for MySql {
   fn preview_tables_query() -> String {
-    "select table_schema as table_schema, table_name as table_name
+    "select table_schema as table_schema, table_name as table_name,
+      case table_type when 'VIEW' then 'view' else 'table' end as object_type
       from information_schema.tables
       where table_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
       order by table_schema, table_name asc"
@@ -111,16 +235,17 @@ impl super::DatabaseQueries for MySql {
   }
 
   fn preview_rows_query(schema: &str, table: &str) -> String {
-    format!("select * from `{}`.`{}` limit 100", schema, table)
+    format!("select * from {}.{} limit 100", Self::quote_identifier(schema), Self::quote_identifier(table))
   }
 
   fn preview_columns_query(schema: &str, table: &str) -> String {
     format!(
       "select column_name, data_type, is_nullable, column_default, extra, column_comment
         from information_schema.columns
-        where table_schema = '{}' and table_name = '{}'
+        where table_schema = {} and table_name = {}
         order by ordinal_position",
-      schema, table
+      Self::quote_literal(schema),
+      Self::quote_literal(table)
     )
   }
 
@@ -130,10 +255,11 @@ impl super::DatabaseQueries for MySql {
         group_concat(column_name order by ordinal_position) as column_names
         from information_schema.table_constraints
         join information_schema.key_column_usage using (constraint_schema, constraint_name, table_schema, table_name)
-        where table_schema = '{}' and table_name = '{}'
+        where table_schema = {} and table_name = {}
         group by constraint_name, constraint_type, enforced
         order by constraint_type, constraint_name",
-      schema, table
+      Self::quote_literal(schema),
+      Self::quote_literal(table)
     )
   }
 
@@ -141,15 +267,149 @@ impl super::DatabaseQueries for MySql {
     format!(
       "select index_name, column_name, non_unique, seq_in_index, index_type
         from information_schema.statistics
-        where table_schema = '{}' and table_name = '{}'
+        where table_schema = {} and table_name = {}
         order by index_name, seq_in_index",
-      schema, table
+      Self::quote_literal(schema),
+      Self::quote_literal(table)
     )
   }
 
   fn preview_policies_query(_schema: &str, _table: &str) -> String {
     "select 'MySQL does not support row-level security policies' as message".to_owned()
   }
+
+  fn primary_key_query(schema: Option<&str>, table: &str) -> String {
+    format!(
+      "select kcu.column_name as column_name
+        from information_schema.table_constraints tc
+        join information_schema.key_column_usage kcu
+          on kcu.constraint_name = tc.constraint_name
+          and kcu.table_schema = tc.table_schema
+          and kcu.table_name = tc.table_name
+        where tc.constraint_type = 'PRIMARY KEY'
+        and tc.table_schema = {}
+        and tc.table_name = {}
+        order by kcu.ordinal_position",
+      schema.map_or("database()".to_owned(), Self::quote_literal),
+      Self::quote_literal(table)
+    )
+  }
+
+  fn quote_identifier(identifier: &str) -> String {
+    format!("`{}`", identifier.replace('`', "``"))
+  }
+
+  // backslashes start escape sequences in mysql's string literals, so a value ending in
+  // one would otherwise escape the closing quote
+  fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+  }
+
+  fn preview_functions_query() -> String {
+    "select routine_schema as routine_schema, routine_name as routine_name, lower(routine_type) as object_type
+      from information_schema.routines
+      where routine_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      order by routine_schema, routine_name asc"
+      .to_owned()
+  }
+
+  fn preview_function_definition_query(schema: &str, name: &str) -> String {
+    format!(
+      "select routine_name, routine_type, dtd_identifier as returns, routine_definition
+        from information_schema.routines
+        where routine_schema = {} and routine_name = {}",
+      Self::quote_literal(schema),
+      Self::quote_literal(name)
+    )
+  }
+
+  fn preview_settings_query(search: Option<&str>) -> String {
+    format!("show variables where variable_name like {}", super::like_pattern::<Self>(search))
+  }
+
+  fn schema_columns_query() -> String {
+    "select c.table_schema as table_schema, c.table_name as table_name, c.column_name as column_name,
+        c.data_type as data_type, c.column_key as column_key,
+        coalesce(k.referenced_table_schema, '') as foreign_schema,
+        coalesce(k.referenced_table_name, '') as foreign_table,
+        coalesce(k.referenced_column_name, '') as foreign_column
+      from information_schema.columns c
+      left join information_schema.key_column_usage k
+        on k.table_schema = c.table_schema
+        and k.table_name = c.table_name
+        and k.column_name = c.column_name
+        and k.referenced_table_name is not null
+      where c.table_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      order by c.table_schema, c.table_name, c.ordinal_position"
+      .to_owned()
+  }
+
+  fn search_objects_query(search: &str) -> String {
+    let pattern = super::like_pattern::<Self>(Some(search));
+    format!(
+      "select table_schema as table_schema, table_name as table_name,
+        case table_type when 'VIEW' then 'view' else 'table' end as object_type, '' as column_name
+        from information_schema.tables
+        where table_name like {pattern}
+        and table_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      union all
+      select table_schema, table_name, 'column', column_name
+        from information_schema.columns
+        where column_name like {pattern}
+        and table_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      union all
+      select routine_schema, routine_name, 'function', ''
+        from information_schema.routines
+        where routine_name like {pattern}
+        and routine_schema not in ('mysql', 'information_schema', 'performance_schema', 'sys')
+      order by table_schema, table_name, column_name
+      limit 500"
+    )
+  }
+
+  // EXPLAIN ANALYZE (8.0.18+) already prints the tree format. FORMAT=TREE can't
+  // be used for the plain plan, since queries are parsed before they are run
+  fn explain_prefix(analyze: bool) -> String {
+    if analyze {
+      "EXPLAIN ANALYZE".to_owned()
+    } else {
+      "EXPLAIN".to_owned()
+    }
+  }
+
+  fn current_schema_query() -> String {
+    "select database()".to_owned()
+  }
+
+  // only limits read-only selects; mysql has no server-side timeout for other statements
+  fn statement_timeout_statement(timeout_ms: u64) -> Option<String> {
+    Some(format!("set session max_execution_time = {}", timeout_ms))
+  }
+
+  fn preview_activity_query() -> String {
+    "select id as session_id, user, db, command, state, time as seconds, info as query
+      from information_schema.processlist
+      where id <> connection_id()
+      order by time desc"
+      .to_owned()
+  }
+
+  fn kill_session_statement(session_id: &str, cancel_only: bool) -> Option<String> {
+    let id = session_id.parse::<u64>().ok()?;
+    Some(if cancel_only { format!("kill query {}", id) } else { format!("kill {}", id) })
+  }
+
+  fn attach_database_statement(path: &str, alias: &str) -> Option<String> {
+    None
+  }
+
+  fn copy_in(
+    pool: &sqlx::Pool<Self>,
+    statement: String,
+    path: String,
+  ) -> Option<futures::future::BoxFuture<'static, Result<u64, super::DbError>>> {
+    None
+  }
 }
 
 impl super::ValueParser for MySql {
@@ -330,7 +590,7 @@ impl super::ValueParser for MySql {
           row
             .try_get::<chrono::DateTime<chrono::Utc>, usize>(col.ordinal())
             .map_or(Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false }, |received| {
-              Value { parse_error: false, string: received.to_string(), is_null: false }
+              Value { parse_error: false, string: super::format_utc(received), is_null: false }
             }),
         )
       },
@@ -359,6 +619,10 @@ impl super::ValueParser for MySql {
       },
     }
   }
+
+  fn is_utc_type(type_name: &str) -> bool {
+    type_name.eq_ignore_ascii_case("timestamp")
+  }
 }
 
 mod tests {
@@ -369,7 +633,7 @@ mod tests {
   };
 
   use super::*;
-  use crate::database::{get_execution_type, get_first_query, DbError, ExecutionType};
+  use crate::database::{get_execution_type, get_first_query, DatabaseQueries, DbError, ExecutionType};
 
   #[test]
   fn test_get_first_query_mysql() {
@@ -469,4 +733,31 @@ mod tests {
       assert_eq!(get_execution_type(statement, false), expected, "Failed for query: {}", query);
     }
   }
+
+  #[test]
+  fn test_kill_session_statement_mysql() {
+    let dialect = MySqlDialect {};
+    assert!(get_first_query(MySql::preview_activity_query(), &dialect).is_ok());
+    assert_eq!(MySql::kill_session_statement("42", false), Some("kill 42".to_owned()));
+    assert_eq!(MySql::kill_session_statement("42", true), Some("kill query 42".to_owned()));
+    assert!(get_first_query("kill query 42".to_owned(), &dialect).is_ok());
+    assert_eq!(MySql::kill_session_statement("42; drop table users", false), None);
+  }
+
+  #[test]
+  fn test_preview_queries_quote_names_mysql() {
+    let dialect = MySqlDialect {};
+    assert_eq!(MySql::preview_rows_query("app", "odd`name"), "select * from `app`.`odd``name` limit 100");
+    let query = MySql::preview_columns_query("app", "it's");
+    assert!(query.contains("table_name = 'it''s'"));
+    assert!(get_first_query(query, &dialect).is_ok());
+  }
+
+  #[test]
+  fn test_ssl_mode_mysql() {
+    assert!(matches!(ssl_mode("verify_identity"), Ok(MySqlSslMode::VerifyIdentity)));
+    assert!(matches!(ssl_mode("verify-full"), Ok(MySqlSslMode::VerifyIdentity)));
+    assert!(matches!(ssl_mode("Require"), Ok(MySqlSslMode::Required)));
+    assert!(ssl_mode("allow").is_err());
+  }
 }
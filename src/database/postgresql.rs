@@ -12,9 +12,9 @@ use sqlparser::{
   parser::{Parser, ParserError},
 };
 use sqlx::{
-  postgres::{PgConnectOptions, PgQueryResult, Postgres},
+  postgres::{PgConnectOptions, PgConnection, PgPoolCopyExt, PgQueryResult, PgSslMode, Postgres},
   types::Uuid,
-  Column, Database, Either, Row, ValueRef,
+  Column, Connection, Database, Either, Row, ValueRef,
 };
 
 use super::{vec_to_string, Value};
@@ -24,9 +24,20 @@ impl super::BuildConnectionOptions for sqlx::Postgres {
     args: crate::cli::Cli,
   ) -> color_eyre::eyre::Result<<Self::Connection as sqlx::Connection>::Options> {
     match args.connection_url {
-      Some(url) => Ok(PgConnectOptions::from_str(&url)?),
+      Some(url) => {
+        let target_session_attrs = crate::cli::url_query_param(&url, "target_session_attrs");
+        let candidates = crate::cli::expand_host_list(&url)?
+          .iter()
+          .map(|url| {
+            let opts = PgConnectOptions::from_str(url)?;
+            let host = crate::cli::unbracket_host(opts.get_host()).to_owned();
+            with_ssl_options(opts.host(&host), &args.ssl)
+          })
+          .collect::<color_eyre::eyre::Result<Vec<_>>>()?;
+        select_host(candidates, target_session_attrs.as_deref())
+      },
       None => {
-        let mut opts = PgConnectOptions::new();
+        let mut opts = with_ssl_options(PgConnectOptions::new(), &args.ssl)?;
 
         if let Some(user) = args.user {
           opts = opts.username(&user);
@@ -52,22 +63,21 @@ impl super::BuildConnectionOptions for sqlx::Postgres {
           }
         }
 
-        if let Some(host) = args.host {
-          opts = opts.host(&host);
-        } else {
-          let mut host: String = String::new();
-          print!("host (ex. localhost): ");
-          io::stdout().flush().unwrap();
-          io::stdin().read_line(&mut host).unwrap();
-          host = host.trim().to_string();
-          if !host.is_empty() {
-            opts = opts.host(&host);
-          }
-        }
+        let host = match (args.host, &args.socket) {
+          (Some(host), _) => host,
+          (None, Some(_)) => String::new(),
+          (None, None) => {
+            let mut host: String = String::new();
+            print!("host (ex. localhost): ");
+            io::stdout().flush().unwrap();
+            io::stdin().read_line(&mut host).unwrap();
+            host.trim().to_string()
+          },
+        };
 
         if let Some(port) = args.port {
           opts = opts.port(port);
-        } else {
+        } else if args.socket.is_none() {
           let mut port: String = String::new();
           print!("port (ex. 5432): ");
           io::stdout().flush().unwrap();
@@ -91,10 +101,155 @@ impl super::BuildConnectionOptions for sqlx::Postgres {
           }
         }
 
-        Ok(opts)
+        if let Some(socket) = &args.socket {
+          opts = opts.socket(socket);
+        }
+
+        if host.is_empty() {
+          return Ok(opts);
+        }
+        let candidates = crate::cli::parse_host_list(&host)?
+          .into_iter()
+          .map(|spec| {
+            let opts = opts.clone().host(&spec.host);
+            match spec.port {
+              Some(port) => opts.port(port),
+              None => opts,
+            }
+          })
+          .collect();
+        select_host(candidates, None)
       },
     }
   }
+
+  fn with_settings(
+    opts: <Self::Connection as sqlx::Connection>::Options,
+    ssl: &crate::cli::SslOptions,
+    application_name: Option<&str>,
+  ) -> color_eyre::eyre::Result<<Self::Connection as sqlx::Connection>::Options> {
+    Ok(with_application_name(with_ssl_options(opts, ssl)?, application_name))
+  }
+
+  fn describe_connection(opts: &<Self::Connection as sqlx::Connection>::Options) -> String {
+    // postgres connects to the database named after the user when none is given
+    let database = opts.get_database().unwrap_or(opts.get_username());
+    match opts.get_socket() {
+      Some(socket) => format!("{}/{}", socket.display(), database),
+      None => format!("{}/{}", opts.get_host(), database),
+    }
+  }
+
+  fn connection_params(opts: &<Self::Connection as sqlx::Connection>::Options) -> Vec<(String, String)> {
+    vec![
+      ("host".to_owned(), opts.get_host().to_owned()),
+      ("port".to_owned(), opts.get_port().to_string()),
+      ("database".to_owned(), opts.get_database().unwrap_or_default().to_owned()),
+      ("user".to_owned(), opts.get_username().to_owned()),
+    ]
+  }
+
+  fn with_connection_params(
+    opts: &<Self::Connection as sqlx::Connection>::Options,
+    params: &[(String, String)],
+  ) -> Result<<Self::Connection as sqlx::Connection>::Options, String> {
+    let mut opts = opts.clone();
+    for (name, value) in params.iter().filter(|(_, value)| !value.trim().is_empty()) {
+      opts = match name.as_str() {
+        "host" => opts.host(value.trim()),
+        "port" => opts.port(super::parse_port(value)?),
+        "database" => opts.database(value.trim()),
+        "user" => opts.username(value.trim()),
+        _ => opts,
+      };
+    }
+    Ok(opts)
+  }
+}
+
+// picks the first host that accepts a connection and satisfies target_session_attrs,
+// following the same order libpq uses for multi-host connection strings
+fn select_host(
+  mut candidates: Vec<PgConnectOptions>,
+  target_session_attrs: Option<&str>,
+) -> color_eyre::eyre::Result<PgConnectOptions> {
+  let check = match target_session_attrs.unwrap_or("any") {
+    "any" => None,
+    "read-write" => Some(("show transaction_read_only", "off")),
+    "read-only" => Some(("show transaction_read_only", "on")),
+    "primary" => Some(("select pg_is_in_recovery()::text", "false")),
+    "standby" => Some(("select pg_is_in_recovery()::text", "true")),
+    other => return Err(color_eyre::eyre::eyre!("Unsupported target_session_attrs: {}", other)),
+  };
+  if candidates.len() == 1 && check.is_none() {
+    return Ok(candidates.remove(0));
+  }
+  tokio::task::block_in_place(|| {
+    tokio::runtime::Handle::current().block_on(async {
+      let mut errors = vec![];
+      for opts in candidates {
+        let accepted = async {
+          let mut conn = PgConnection::connect_with(&opts).await?;
+          let accepted = match check {
+            Some((query, expected)) => sqlx::query_scalar::<_, String>(query).fetch_one(&mut conn).await? == expected,
+            None => true,
+          };
+          conn.close().await?;
+          Ok::<_, sqlx::Error>(accepted)
+        };
+        match accepted.await {
+          Ok(true) => return Ok(opts),
+          Ok(false) => errors.push(format!("{}: does not match target_session_attrs", opts.get_host())),
+          Err(e) => errors.push(format!("{}: {}", opts.get_host(), e)),
+        }
+      }
+      Err(color_eyre::eyre::eyre!("No host accepted the connection ({})", errors.join("; ")))
+    })
+  })
+}
+
+// names the sessions after rainfrog, or the application_name setting, unless the url
+// (or PGAPPNAME) already named them. an empty setting leaves them unnamed
+fn with_application_name(opts: PgConnectOptions, setting: Option<&str>) -> PgConnectOptions {
+  if opts.get_application_name().is_some() {
+    return opts;
+  }
+  match setting.map_or_else(super::default_application_name, str::to_owned) {
+    name if name.is_empty() => opts,
+    name => opts.application_name(&name),
+  }
+}
+
+fn with_ssl_options(
+  mut opts: PgConnectOptions,
+  ssl: &crate::cli::SslOptions,
+) -> color_eyre::eyre::Result<PgConnectOptions> {
+  if let Some(mode) = &ssl.mode {
+    opts = opts.ssl_mode(ssl_mode(mode)?);
+  }
+  if let Some(ca) = &ssl.ca {
+    opts = opts.ssl_root_cert(ca);
+  }
+  if let Some(cert) = &ssl.cert {
+    opts = opts.ssl_client_cert(cert);
+  }
+  if let Some(key) = &ssl.key {
+    opts = opts.ssl_client_key(key);
+  }
+  Ok(opts)
+}
+
+// mysql's names are accepted too, so one setting can cover both drivers
+fn ssl_mode(mode: &str) -> Result<PgSslMode, sqlx::Error> {
+  let mode = match mode.to_lowercase().as_str() {
+    "disabled" => "disable".to_owned(),
+    "preferred" => "prefer".to_owned(),
+    "required" => "require".to_owned(),
+    "verify_ca" => "verify-ca".to_owned(),
+    "verify_identity" => "verify-full".to_owned(),
+    other => other.to_owned(),
+  };
+  PgSslMode::from_str(&mode)
 }
 
 impl super::HasRowsAffected for PgQueryResult {
@@ -105,39 +260,233 @@ impl super::HasRowsAffected for PgQueryResult {
 
 impl super::DatabaseQueries for Postgres {
   fn preview_tables_query() -> String {
-    "select table_schema, table_name
+    "select table_schema, table_name, case table_type
+        when 'VIEW' then 'view'
+        when 'FOREIGN' then 'foreign table'
+        else 'table'
+      end as object_type,
+      has_schema_privilege(table_schema, 'usage')
+        and has_table_privilege(format('%I.%I', table_schema, table_name), 'select') as can_select,
+      coalesce((select parent.relname::text from pg_inherits
+        join pg_class child on child.oid = pg_inherits.inhrelid
+        join pg_class parent on parent.oid = pg_inherits.inhparent
+        join pg_namespace on pg_namespace.oid = child.relnamespace
+        where child.relispartition
+        and parent.relnamespace = child.relnamespace
+        and pg_namespace.nspname = table_schema
+        and child.relname = table_name), '') as partition_of
       from information_schema.tables
       where table_schema != 'pg_catalog'
       and table_schema != 'information_schema'
-      group by table_schema, table_name
+      group by table_schema, table_name, table_type
+      union all
+      select schemaname, matviewname, 'materialized view',
+      has_schema_privilege(schemaname, 'usage')
+        and has_table_privilege(format('%I.%I', schemaname, matviewname), 'select'),
+      ''
+      from pg_matviews
       order by table_schema, table_name asc"
       .to_owned()
   }
 
   fn preview_rows_query(schema: &str, table: &str) -> String {
-    format!("select * from \"{}\".\"{}\" limit 100", schema, table)
+    format!("select * from {}.{} limit 100", Self::quote_identifier(schema), Self::quote_identifier(table))
   }
 
   fn preview_columns_query(schema: &str, table: &str) -> String {
     format!(
-      "select column_name, * from information_schema.columns where table_schema = '{}' and table_name = '{}'",
-      schema, table
+      "select column_name, * from information_schema.columns where table_schema = {} and table_name = {}",
+      Self::quote_literal(schema),
+      Self::quote_literal(table)
     )
   }
 
   fn preview_constraints_query(schema: &str, table: &str) -> String {
     format!(
-      "select constraint_name, * from information_schema.table_constraints where table_schema = '{}' and table_name = '{}'",
-      schema, table
+      "select constraint_name, * from information_schema.table_constraints where table_schema = {} and table_name = {}",
+      Self::quote_literal(schema),
+      Self::quote_literal(table)
     )
   }
 
   fn preview_indexes_query(schema: &str, table: &str) -> String {
-    format!("select indexname, indexdef, * from pg_indexes where schemaname = '{}' and tablename = '{}'", schema, table)
+    format!(
+      "select indexname, indexdef, * from pg_indexes where schemaname = {} and tablename = {}",
+      Self::quote_literal(schema),
+      Self::quote_literal(table)
+    )
   }
 
   fn preview_policies_query(schema: &str, table: &str) -> String {
-    format!("select * from pg_policies where schemaname = '{}' and tablename = '{}'", schema, table)
+    format!(
+      "select * from pg_policies where schemaname = {} and tablename = {}",
+      Self::quote_literal(schema),
+      Self::quote_literal(table)
+    )
+  }
+
+  fn primary_key_query(schema: Option<&str>, table: &str) -> String {
+    format!(
+      "select kcu.column_name
+        from information_schema.table_constraints tc
+        join information_schema.key_column_usage kcu
+          on kcu.constraint_name = tc.constraint_name
+          and kcu.table_schema = tc.table_schema
+          and kcu.table_name = tc.table_name
+        where tc.constraint_type = 'PRIMARY KEY'
+        and tc.table_schema = {}
+        and tc.table_name = {}
+        order by kcu.ordinal_position",
+      schema.map_or("current_schema()".to_owned(), Self::quote_literal),
+      Self::quote_literal(table)
+    )
+  }
+
+  fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+  }
+
+  // standard_conforming_strings is on by default, so backslashes are just characters
+  fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+  }
+
+  fn preview_functions_query() -> String {
+    "select n.nspname as routine_schema, p.proname as routine_name,
+        case p.prokind when 'p' then 'procedure' else 'function' end as object_type
+      from pg_proc p
+      join pg_namespace n on n.oid = p.pronamespace
+      where n.nspname not in ('pg_catalog', 'information_schema')
+      and p.prokind in ('f', 'p')
+      group by routine_schema, routine_name, object_type
+      order by routine_schema, routine_name asc"
+      .to_owned()
+  }
+
+  fn preview_function_definition_query(schema: &str, name: &str) -> String {
+    format!(
+      "select p.proname, pg_get_function_identity_arguments(p.oid) as arguments, pg_get_functiondef(p.oid) as definition
+        from pg_proc p
+        join pg_namespace n on n.oid = p.pronamespace
+        where n.nspname = {} and p.proname = {}",
+      Self::quote_literal(schema),
+      Self::quote_literal(name)
+    )
+  }
+
+  fn preview_settings_query(search: Option<&str>) -> String {
+    format!(
+      "select category, name, setting, unit, context, short_desc
+        from pg_settings
+        where name ilike {}
+        order by category, name",
+      super::like_pattern::<Self>(search)
+    )
+  }
+
+  // read from pg_catalog, since information_schema's key views are slow on large catalogs
+  fn schema_columns_query() -> String {
+    "select n.nspname as table_schema, c.relname as table_name, a.attname as column_name,
+        pg_catalog.format_type(a.atttypid, null) as data_type,
+        case when pk.conrelid is null then '' else 'PRI' end as column_key,
+        coalesce(rn.nspname, '') as foreign_schema, coalesce(rc.relname, '') as foreign_table,
+        coalesce(ra.attname, '') as foreign_column
+      from pg_catalog.pg_attribute a
+      join pg_catalog.pg_class c on c.oid = a.attrelid
+      join pg_catalog.pg_namespace n on n.oid = c.relnamespace
+      left join pg_catalog.pg_constraint pk
+        on pk.conrelid = c.oid and pk.contype = 'p' and a.attnum = any(pk.conkey)
+      left join pg_catalog.pg_constraint fk
+        on fk.conrelid = c.oid and fk.contype = 'f' and a.attnum = any(fk.conkey)
+      left join pg_catalog.pg_class rc on rc.oid = fk.confrelid
+      left join pg_catalog.pg_namespace rn on rn.oid = rc.relnamespace
+      left join pg_catalog.pg_attribute ra
+        on ra.attrelid = fk.confrelid and ra.attnum = fk.confkey[array_position(fk.conkey, a.attnum)]
+      where a.attnum > 0
+      and not a.attisdropped
+      and c.relkind in ('r', 'p', 'v', 'm', 'f')
+      and n.nspname not in ('pg_catalog', 'information_schema')
+      order by n.nspname, c.relname, a.attnum"
+      .to_owned()
+  }
+
+  fn search_objects_query(search: &str) -> String {
+    let pattern = super::like_pattern::<Self>(Some(search));
+    format!(
+      "select table_schema, table_name, case table_type when 'VIEW' then 'view' else 'table' end as object_type,
+        '' as column_name
+        from information_schema.tables
+        where table_name ilike {pattern}
+        and table_schema not in ('pg_catalog', 'information_schema')
+      union all
+      select table_schema, table_name, 'column', column_name
+        from information_schema.columns
+        where column_name ilike {pattern}
+        and table_schema not in ('pg_catalog', 'information_schema')
+      union all
+      select routine_schema, routine_name, 'function', ''
+        from information_schema.routines
+        where routine_name ilike {pattern}
+        and routine_schema not in ('pg_catalog', 'information_schema')
+      order by table_schema, table_name, column_name
+      limit 500"
+    )
+  }
+
+  fn explain_prefix(analyze: bool) -> String {
+    if analyze {
+      "EXPLAIN ANALYZE".to_owned()
+    } else {
+      "EXPLAIN".to_owned()
+    }
+  }
+
+  fn current_schema_query() -> String {
+    "select current_schema()".to_owned()
+  }
+
+  fn statement_timeout_statement(timeout_ms: u64) -> Option<String> {
+    Some(format!("set statement_timeout = {}", timeout_ms))
+  }
+
+  fn preview_activity_query() -> String {
+    "select pid as session_id, usename as user_name, datname as database_name, state,
+      date_trunc('second', now() - query_start)::text as duration, wait_event_type, query
+      from pg_stat_activity
+      where pid <> pg_backend_pid() and backend_type = 'client backend'
+      order by query_start"
+      .to_owned()
+  }
+
+  fn kill_session_statement(session_id: &str, cancel_only: bool) -> Option<String> {
+    let pid = session_id.parse::<i32>().ok()?;
+    Some(if cancel_only {
+      format!("select pg_cancel_backend({})", pid)
+    } else {
+      format!("select pg_terminate_backend({})", pid)
+    })
+  }
+
+  fn attach_database_statement(path: &str, alias: &str) -> Option<String> {
+    None
+  }
+
+  fn copy_in(
+    pool: &sqlx::Pool<Self>,
+    statement: String,
+    path: String,
+  ) -> Option<futures::future::BoxFuture<'static, Result<u64, super::DbError>>> {
+    let pool = pool.clone();
+    Some(Box::pin(async move {
+      let file = tokio::fs::File::open(&path).await.map_err(|e| Either::Left(sqlx::Error::Io(e)))?;
+      let mut copy = pool.copy_in_raw(&statement).await.map_err(Either::Left)?;
+      // the copy has to be finished or aborted, or the connection errors the next time it's used
+      if let Err(e) = copy.read_from(file).await {
+        copy.abort(e.to_string()).await.map_err(Either::Left)?;
+        return Err(Either::Left(e));
+      }
+      copy.finish().await.map_err(Either::Left)
+    }))
   }
 }
 
@@ -154,7 +503,7 @@ impl super::ValueParser for Postgres {
           row
             .try_get::<chrono::DateTime<chrono::Utc>, usize>(col.ordinal())
             .map_or(Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false }, |received| {
-              Value { parse_error: false, string: received.to_string(), is_null: false }
+              Value { parse_error: false, string: super::format_utc(received), is_null: false }
             }),
         )
       },
@@ -295,13 +644,16 @@ impl super::ValueParser for Postgres {
         let array_type = col_type.to_uppercase().replace("[]", "");
         match array_type.as_str() {
           "TIMESTAMPTZ" => {
-            Some(
-              row
-                .try_get::<Vec<chrono::DateTime<chrono::Utc>>, usize>(col.ordinal())
-                .map_or(Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false }, |received| {
-                  Value { parse_error: false, string: vec_to_string(received), is_null: false }
-                }),
-            )
+            Some(row.try_get::<Vec<chrono::DateTime<chrono::Utc>>, usize>(col.ordinal()).map_or(
+              Value { parse_error: true, string: "_ERROR_".to_string(), is_null: false },
+              |received| {
+                Value {
+                  parse_error: false,
+                  string: vec_to_string(received.into_iter().map(super::format_utc).collect()),
+                  is_null: false,
+                }
+              },
+            ))
           },
           "TIMESTAMP" => {
             Some(
@@ -459,6 +811,10 @@ impl super::ValueParser for Postgres {
       },
     }
   }
+
+  fn is_utc_type(type_name: &str) -> bool {
+    type_name.eq_ignore_ascii_case("timestamptz") || type_name.eq_ignore_ascii_case("timestamptz[]")
+  }
 }
 mod tests {
   use std::sync::Arc;
@@ -466,7 +822,15 @@ mod tests {
   use sqlparser::{ast::Statement, dialect::PostgreSqlDialect, parser::Parser};
 
   use super::*;
-  use crate::database::{get_execution_type, get_first_query, DbError, ExecutionType};
+  use crate::database::{
+    affected_rows_query, classify_connection_error, column_types, delete_row_statement, dry_run, estimated_rows,
+    filter_predicate, get_execution_type, get_execution_type_with_policy, get_first_query, get_source_table, in_list,
+    insert_rows_statement, is_connection_error, is_copy_from_stdin, is_write_statement, like_pattern, limit_query,
+    limit_statement, parse_array_literal, parse_table_name, parse_tabular_text, reads_only, referenced_rows_query,
+    replica_opts, rows_to_csv, rows_to_insert_statements, rows_to_text, unfiltered_write_target, update_cell_statement,
+    vec_to_string, BuildConnectionOptions, ConnectionFailure, DatabaseQueries, DbError, ExecutionType, Header, Rows,
+    SchemaCache, StatementKind, StatementPolicy, ValueParser,
+  };
 
   #[test]
   fn test_get_first_query() {
@@ -549,6 +913,269 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_get_source_table() {
+    let dialect = PostgreSqlDialect {};
+    let test_cases = vec![
+      ("select * from \"public\".\"users\" limit 100", Some("\"public\".\"users\"".to_owned())),
+      ("select id from users where id = 1", Some("users".to_owned())),
+      ("select * from users join posts on posts.user_id = users.id", None),
+      ("select * from users, posts", None),
+      ("select 1", None),
+      ("delete from users", None),
+    ];
+    for (query, expected) in test_cases {
+      let statement = Parser::parse_sql(&dialect, query).unwrap()[0].clone();
+      assert_eq!(get_source_table(&statement), expected, "Failed for query: {}", query);
+    }
+  }
+
+  #[test]
+  fn test_rows_to_insert_statements() {
+    let headers = vec![
+      Header { name: "id".to_owned(), type_name: "INT4".to_owned() },
+      Header { name: "name".to_owned(), type_name: "TEXT".to_owned() },
+      Header { name: "weird \"col\"".to_owned(), type_name: "BOOL".to_owned() },
+    ];
+    let rows = vec![vec!["1".to_owned(), "O'Brien".to_owned(), "true".to_owned()], vec![
+      "2".to_owned(),
+      "NULL".to_owned(),
+      "false".to_owned(),
+    ]];
+    assert_eq!(
+      rows_to_insert_statements::<Postgres>("\"public\".\"users\"", &headers, &rows),
+      "INSERT INTO \"public\".\"users\" (\"id\", \"name\", \"weird \"\"col\"\"\") VALUES (1, 'O''Brien', true);\n\
+       INSERT INTO \"public\".\"users\" (\"id\", \"name\", \"weird \"\"col\"\"\") VALUES (2, NULL, false);"
+    );
+    let headers = vec![Header { name: "n".to_owned(), type_name: "FLOAT8".to_owned() }, Header {
+      name: "path".to_owned(),
+      type_name: "TEXT".to_owned(),
+    }];
+    let rows = vec![vec!["NaN".to_owned(), r"C:\".to_owned()], vec!["-inf".to_owned(), "1e3".to_owned()]];
+    assert_eq!(
+      rows_to_insert_statements::<Postgres>("t", &headers, &rows),
+      "INSERT INTO t (\"n\", \"path\") VALUES ('NaN', 'C:\\');\nINSERT INTO t (\"n\", \"path\") VALUES ('-inf', '1e3');"
+    );
+    // mysql reads a backslash as an escape, so one at the end would swallow the closing quote
+    assert_eq!(
+      rows_to_insert_statements::<sqlx::MySql>("t", &headers, &rows[..1]),
+      r"INSERT INTO t (`n`, `path`) VALUES ('NaN', 'C:\\');"
+    );
+    assert_eq!(sqlx::MySql::quote_literal(r"it's \'"), r"'it''s \\'''");
+    assert_eq!(sqlx::Sqlite::quote_literal(r"it's \"), r"'it''s \'");
+  }
+
+  #[test]
+  fn test_parse_table_name() {
+    let dialect = PostgreSqlDialect {};
+    assert_eq!(parse_table_name("users", &dialect), Some((None, "users".to_owned())));
+    assert_eq!(parse_table_name("public.users", &dialect), Some((Some("public".to_owned()), "users".to_owned())));
+    assert_eq!(
+      parse_table_name("\"My Schema\".\"Users\"", &dialect),
+      Some((Some("My Schema".to_owned()), "Users".to_owned()))
+    );
+    assert_eq!(parse_table_name("", &dialect), None);
+  }
+
+  #[test]
+  fn test_update_cell_statement() {
+    let headers = vec![
+      Header { name: "tenant_id".to_owned(), type_name: "INT4".to_owned() },
+      Header { name: "id".to_owned(), type_name: "UUID".to_owned() },
+      Header { name: "name".to_owned(), type_name: "TEXT".to_owned() },
+    ];
+    let row = vec!["7".to_owned(), "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".to_owned(), "old".to_owned()];
+    let keys = vec!["tenant_id".to_owned(), "id".to_owned()];
+    assert_eq!(
+      update_cell_statement::<Postgres>("public.users", &headers, &row, 2, Some("O'Brien"), &keys),
+      Ok(
+        "UPDATE public.users SET \"name\" = 'O''Brien' \
+         WHERE \"tenant_id\" = 7 AND \"id\" = 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11';"
+          .to_owned()
+      )
+    );
+    let set = |column, value| update_cell_statement::<Postgres>("users", &headers, &row, column, value, &keys);
+    assert!(set(2, Some("NULL")).unwrap().starts_with("UPDATE users SET \"name\" = 'NULL' WHERE"));
+    assert!(set(2, None).unwrap().starts_with("UPDATE users SET \"name\" = NULL WHERE"));
+    assert!(set(0, Some("8")).unwrap().starts_with("UPDATE users SET \"tenant_id\" = 8 WHERE"));
+    // a value that isn't a number can't end the statement early
+    assert!(set(0, Some("1 --")).is_err());
+    assert!(set(0, Some("NaN")).unwrap().starts_with("UPDATE users SET \"tenant_id\" = 'NaN' WHERE"));
+    assert!(update_cell_statement::<Postgres>("users", &headers, &row, 2, Some("x"), &[]).is_err());
+    assert!(update_cell_statement::<Postgres>("users", &headers, &row, 2, Some("x"), &["email".to_owned()]).is_err());
+  }
+
+  #[test]
+  fn test_parse_tabular_text() {
+    assert_eq!(parse_tabular_text("a\tb\r\n1\t\n"), vec![vec!["a", "b"], vec!["1", ""]]);
+    assert_eq!(parse_tabular_text("a,\"b, \"\"c\"\"\"\n\"multi\nline\",2"), vec![vec!["a", "b, \"c\""], vec![
+      "multi\nline",
+      "2"
+    ]]);
+  }
+
+  #[test]
+  fn test_insert_rows_statement() {
+    let header = |name: &str| Header { name: name.to_owned(), type_name: "TEXT".to_owned() };
+    let columns = Rows {
+      headers: vec![header("column_name"), header("ordinal_position"), header("data_type")],
+      rows: vec![
+        vec!["name".to_owned(), "2".to_owned(), "text".to_owned()],
+        vec!["id".to_owned(), "1".to_owned(), "integer".to_owned()],
+        vec!["active".to_owned(), "3".to_owned(), "boolean".to_owned()],
+      ],
+      rows_affected: None,
+    };
+    let columns = column_types(&columns);
+    assert_eq!(columns[0], ("id".to_owned(), "integer".to_owned()));
+    let rows = parse_tabular_text("1\tO'Brien\ttrue\n2\t\tno");
+    assert_eq!(
+      insert_rows_statement::<Postgres>("users", &columns, &rows),
+      Ok(
+        "INSERT INTO users (\"id\", \"name\", \"active\") VALUES\n  (1, 'O''Brien', TRUE),\n  (2, NULL, 'no');"
+          .to_owned()
+      )
+    );
+    let rows = parse_tabular_text("NAME,id\nbob,x1");
+    assert_eq!(
+      insert_rows_statement::<Postgres>("users", &columns, &rows),
+      Ok("INSERT INTO users (\"name\", \"id\") VALUES\n  ('bob', 'x1');".to_owned())
+    );
+    assert!(insert_rows_statement::<Postgres>("users", &columns, &parse_tabular_text("1,2,3,4")).is_err());
+  }
+
+  #[test]
+  fn test_filter_predicate() {
+    assert_eq!(filter_predicate::<Postgres>("id", "integer", ">=", " 10 "), Ok("\"id\" >= 10".to_owned()));
+    assert_eq!(filter_predicate::<Postgres>("name", "text", "=", "O'Brien"), Ok("\"name\" = 'O''Brien'".to_owned()));
+    assert_eq!(filter_predicate::<Postgres>("name", "text", "like", "%a%"), Ok("\"name\" like '%a%'".to_owned()));
+    assert_eq!(filter_predicate::<Postgres>("name", "text", "is null", ""), Ok("\"name\" is null".to_owned()));
+    assert!(filter_predicate::<Postgres>("id", "integer", "=", "abc").is_err());
+    assert!(filter_predicate::<Postgres>("id", "integer", "=", "").is_err());
+    assert!(filter_predicate::<Postgres>("id", "integer", "=", "null").is_err());
+    assert!(filter_predicate::<Postgres>("id", "integer", "; drop", "1").is_err());
+  }
+
+  #[test]
+  fn test_estimated_rows() {
+    let header = |name: &str| Header { name: name.to_owned(), type_name: "TEXT".to_owned() };
+    let plan = |headers: Vec<Header>, rows: Vec<Vec<&str>>| {
+      Rows {
+        headers,
+        rows: rows.into_iter().map(|row| row.into_iter().map(|v| v.to_owned()).collect()).collect(),
+        rows_affected: None,
+      }
+    };
+    let postgres = plan(vec![header("QUERY PLAN")], vec![
+      vec!["Hash Join  (cost=1.09..2.21 rows=1200000 width=8)"],
+      vec!["  ->  Seq Scan on orders  (cost=0.00..1.05 rows=5 width=4)"],
+    ]);
+    assert_eq!(estimated_rows(&postgres), Some(1_200_000));
+    let mysql = plan(vec![header("table"), header("rows")], vec![vec!["orders", "1000"], vec!["users", "3"]]);
+    assert_eq!(estimated_rows(&mysql), Some(3000));
+    let sqlite = plan(vec![header("id"), header("detail")], vec![vec!["2", "SCAN orders"]]);
+    assert_eq!(estimated_rows(&sqlite), None);
+  }
+
+  #[test]
+  fn test_in_list() {
+    assert_eq!(in_list::<Postgres>("id", &["3", "1", "3"], "INT4"), "\"id\" IN (3, 1)");
+    assert_eq!(in_list::<Postgres>("id", &["3", "NULL", "NULL"], "INT4"), "(\"id\" IN (3) OR \"id\" IS NULL)");
+    assert_eq!(in_list::<Postgres>("id", &["NULL"], "INT4"), "\"id\" IS NULL");
+    assert_eq!(in_list::<Postgres>("name", &["a", "O'Brien"], "TEXT"), "\"name\" IN ('a', 'O''Brien')");
+    assert_eq!(in_list::<Postgres>("name", &[], "TEXT"), "\"name\" IN (NULL)");
+  }
+
+  #[test]
+  fn test_limit_statement() {
+    let dialect = PostgreSqlDialect {};
+    let limited = |query: &str| {
+      let statement = Parser::parse_sql(&dialect, query).unwrap().remove(0);
+      limit_statement(&statement, 500).map(|statement| statement.to_string())
+    };
+    assert_eq!(limited("SELECT * FROM users"), Some("SELECT * FROM users LIMIT 500".to_owned()));
+    assert_eq!(
+      limited("SELECT id FROM a UNION SELECT id FROM b ORDER BY id"),
+      Some("SELECT id FROM a UNION SELECT id FROM b ORDER BY id LIMIT 500".to_owned())
+    );
+    assert_eq!(limited("SELECT * FROM users LIMIT 5"), None);
+    assert_eq!(limited("SELECT * FROM users FETCH FIRST 5 ROWS ONLY"), None);
+    assert_eq!(limited("SELECT * INTO backup FROM users"), None);
+    assert_eq!(limited("DELETE FROM users"), None);
+    assert_eq!(limited("SELECT * FROM users FOR UPDATE"), None);
+  }
+
+  #[test]
+  fn test_limit_query() {
+    let dialect = PostgreSqlDialect {};
+    let limited = |query: &str| {
+      let statement = Parser::parse_sql(&dialect, query).unwrap().remove(0);
+      limit_query(query, &statement, &dialect, 500)
+    };
+    assert_eq!(limited("select * from users"), Some("select * from users limit 500".to_owned()));
+    // comments, formatting, and the semicolon stay where they were
+    assert_eq!(
+      limited("-- active ones\nselect *\n  from users where name = 'é' -- names\n;\n"),
+      Some("-- active ones\nselect *\n  from users where name = 'é' limit 500 -- names\n;\n".to_owned())
+    );
+    assert_eq!(limited("select 1 /* one */;"), Some("select 1 limit 500 /* one */;".to_owned()));
+    assert_eq!(limited("select * from users limit 5"), None);
+    assert_eq!(limited("delete from users"), None);
+  }
+
+  #[test]
+  fn test_connection_params() {
+    let opts = PgConnectOptions::new().host("localhost").port(5432).username("postgres").database("app");
+    let mut params = Postgres::connection_params(&opts);
+    assert_eq!(params[1], ("port".to_owned(), "5432".to_owned()));
+    params[1].1 = "5433".to_owned();
+    params[2].1 = "".to_owned();
+    let edited = Postgres::with_connection_params(&opts, &params).unwrap();
+    assert_eq!((edited.get_port(), edited.get_database()), (5433, Some("app")));
+    params[1].1 = "abc".to_owned();
+    assert!(Postgres::with_connection_params(&opts, &params).is_err());
+  }
+
+  #[test]
+  fn test_delete_row_statement() {
+    let headers = vec![Header { name: "id".to_owned(), type_name: "INT8".to_owned() }, Header {
+      name: "name".to_owned(),
+      type_name: "TEXT".to_owned(),
+    }];
+    let row = vec!["42".to_owned(), "bob".to_owned()];
+    assert_eq!(
+      delete_row_statement::<Postgres>("\"Users\"", &headers, &row, &["id".to_owned()]),
+      Some("DELETE FROM \"Users\" WHERE \"id\" = 42;".to_owned())
+    );
+    assert_eq!(delete_row_statement::<Postgres>("\"Users\"", &headers, &row, &[]), None);
+  }
+
+  #[test]
+  fn test_classify_connection_error() {
+    let io_error = |kind| sqlx::Error::Io(std::io::Error::from(kind));
+    assert_eq!(
+      classify_connection_error(&io_error(std::io::ErrorKind::ConnectionRefused)),
+      ConnectionFailure::Unreachable
+    );
+    assert_eq!(classify_connection_error(&io_error(std::io::ErrorKind::TimedOut)), ConnectionFailure::Unreachable);
+    assert_eq!(classify_connection_error(&io_error(std::io::ErrorKind::PermissionDenied)), ConnectionFailure::Other);
+    assert_eq!(
+      classify_connection_error(&sqlx::Error::Io(std::io::Error::other("failed to lookup address information"))),
+      ConnectionFailure::Unreachable
+    );
+    assert_eq!(classify_connection_error(&sqlx::Error::PoolTimedOut), ConnectionFailure::Unreachable);
+    assert_eq!(classify_connection_error(&sqlx::Error::RowNotFound), ConnectionFailure::Other);
+  }
+
+  #[test]
+  fn test_is_connection_error() {
+    let io_error = |kind| DbError::Left(sqlx::Error::Io(std::io::Error::from(kind)));
+    assert!(is_connection_error(&io_error(std::io::ErrorKind::ConnectionReset)));
+    assert!(is_connection_error(&DbError::Left(sqlx::Error::PoolTimedOut)));
+    assert!(!is_connection_error(&DbError::Left(sqlx::Error::RowNotFound)));
+    assert!(!is_connection_error(&DbError::Right(sqlparser::parser::ParserError::ParserError("x".to_owned()))));
+  }
+
   #[test]
   fn test_execution_type_postgres() {
     let dialect = PostgreSqlDialect {};
@@ -560,6 +1187,9 @@ mod tests {
       ("INSERT INTO users (name) VALUES ('John')", ExecutionType::Normal),
       ("EXPLAIN ANALYZE DELETE FROM users WHERE id = 1", ExecutionType::Transaction),
       ("EXPLAIN ANALYZE DROP TABLE users", ExecutionType::Confirm),
+      ("EXPLAIN ANALYZE INSERT INTO users (name) VALUES ('John')", ExecutionType::Transaction),
+      ("EXPLAIN ANALYZE CREATE TABLE t AS SELECT 1", ExecutionType::Confirm),
+      ("EXPLAIN INSERT INTO users (name) VALUES ('John')", ExecutionType::Normal),
       ("EXPLAIN SELECT * FROM users", ExecutionType::Normal),
       ("EXPLAIN ANALYZE SELECT * FROM users WHERE id = 1", ExecutionType::Normal),
     ];
@@ -569,5 +1199,318 @@ mod tests {
       let statement = ast[0].clone();
       assert_eq!(get_execution_type(statement, false), expected, "Failed for query: {}", query);
     }
+    // once confirmed, only writes that can be rolled back still wait for a commit
+    let confirmed_cases = vec![
+      ("DELETE FROM users", ExecutionType::Transaction),
+      ("UPDATE users SET name = 'John'", ExecutionType::Transaction),
+      ("EXPLAIN ANALYZE DELETE FROM users", ExecutionType::Transaction),
+      ("DROP TABLE users", ExecutionType::Normal),
+      ("SELECT * FROM users", ExecutionType::Normal),
+    ];
+    for (query, expected) in confirmed_cases {
+      let statement = Parser::parse_sql(&dialect, query).unwrap()[0].clone();
+      assert_eq!(get_execution_type(statement, true), expected, "Failed for query: {}", query);
+    }
+  }
+
+  #[test]
+  fn test_is_write_statement() {
+    let dialect = PostgreSqlDialect {};
+    let test_cases = vec![
+      ("INSERT INTO users (name) VALUES ('John')", true),
+      ("UPDATE users SET name = 'John' WHERE id = 1", true),
+      ("DELETE FROM users WHERE id = 1", true),
+      ("CREATE TABLE t (id int)", true),
+      ("DROP TABLE users", true),
+      ("SELECT * FROM users", false),
+      ("EXPLAIN ANALYZE DELETE FROM users WHERE id = 1", false),
+      ("SHOW search_path", false),
+    ];
+
+    for (query, expected) in test_cases {
+      let ast = Parser::parse_sql(&dialect, query).unwrap();
+      assert_eq!(is_write_statement(&ast[0]), expected, "Failed for query: {}", query);
+    }
+  }
+
+  #[test]
+  fn test_execution_type_with_policy() {
+    let dialect = PostgreSqlDialect {};
+    let policy = StatementPolicy::from([
+      (StatementKind::Truncate, ExecutionType::Normal),
+      (StatementKind::Delete, ExecutionType::Normal),
+      (StatementKind::DeleteWithoutWhere, ExecutionType::Confirm),
+      (StatementKind::Insert, ExecutionType::Transaction),
+      (StatementKind::Update, ExecutionType::Normal),
+      (StatementKind::Copy, ExecutionType::Confirm),
+    ]);
+    let test_cases = vec![
+      ("TRUNCATE users", ExecutionType::Normal),
+      ("DROP TABLE users", ExecutionType::Confirm),
+      ("DELETE FROM users WHERE id = 1", ExecutionType::Normal),
+      ("DELETE FROM users", ExecutionType::Confirm),
+      ("EXPLAIN ANALYZE DELETE FROM users", ExecutionType::Confirm),
+      ("UPDATE users SET name = 'John' WHERE id = 1", ExecutionType::Normal),
+      ("UPDATE users SET name = 'John'", ExecutionType::Confirm),
+      ("INSERT INTO users (name) VALUES ('John')", ExecutionType::Transaction),
+      ("COPY users FROM STDIN;", ExecutionType::Confirm),
+      ("COPY users TO STDOUT", ExecutionType::Normal),
+      ("SELECT * FROM users", ExecutionType::Normal),
+    ];
+
+    for (query, expected) in test_cases {
+      let ast = Parser::parse_sql(&dialect, query).unwrap();
+      let statement = ast[0].clone();
+      assert_eq!(
+        get_execution_type_with_policy(statement.clone(), false, Some(&policy)),
+        expected,
+        "Failed for query: {}",
+        query
+      );
+      assert_eq!(
+        get_execution_type_with_policy(statement.clone(), true, Some(&policy)),
+        get_execution_type(statement.clone(), true)
+      );
+      assert_eq!(get_execution_type_with_policy(statement.clone(), false, None), get_execution_type(statement, false));
+    }
+  }
+
+  #[test]
+  fn test_rows_to_text_and_csv() {
+    let headers = vec![Header { name: "id".to_owned(), type_name: "int4".to_owned() }, Header {
+      name: "note".to_owned(),
+      type_name: "text".to_owned(),
+    }];
+    let rows = vec![vec!["1".to_owned(), "a, \"b\"".to_owned()], vec!["10".to_owned(), "two\nlines".to_owned()]];
+    assert_eq!(rows_to_text(&headers, &rows), "id | note\n---+-----------\n1  | a, \"b\"\n10 | two\\nlines\n");
+    assert_eq!(rows_to_csv(&headers, &rows), "id,note\n1,\"a, \"\"b\"\"\"\n10,\"two\nlines\"\n");
+  }
+
+  #[test]
+  fn test_affected_rows_query() {
+    let affected_rows_query =
+      |sql: &str| affected_rows_query(&Parser::parse_sql(&PostgreSqlDialect {}, sql).unwrap()[0], 100);
+    assert_eq!(
+      affected_rows_query("delete from users u where u.id > 10 and name like 'a%'"),
+      Some("SELECT * FROM users AS u WHERE u.id > 10 AND name LIKE 'a%' LIMIT 100".to_owned())
+    );
+    assert_eq!(
+      affected_rows_query("update public.users set name = 'a' where id = 1"),
+      Some("SELECT * FROM public.users WHERE id = 1 LIMIT 100".to_owned())
+    );
+    assert_eq!(affected_rows_query("delete from users"), Some("SELECT * FROM users LIMIT 100".to_owned()));
+    assert_eq!(affected_rows_query("delete from users using orders where users.id = orders.user_id"), None);
+    assert_eq!(affected_rows_query("update users set name = o.name from orders o where users.id = o.id"), None);
+    assert_eq!(affected_rows_query("insert into users (id) values (1)"), None);
+  }
+
+  #[test]
+  fn test_is_copy_from_stdin() {
+    let is_copy_from_stdin = |sql: &str| is_copy_from_stdin(&Parser::parse_sql(&PostgreSqlDialect {}, sql).unwrap()[0]);
+    assert!(is_copy_from_stdin("copy users from stdin;"));
+    assert!(is_copy_from_stdin("copy users (id, name) from stdin with (format csv, header);"));
+    assert!(!is_copy_from_stdin("copy users to stdout"));
+    assert!(!is_copy_from_stdin("copy users from '/tmp/users.csv'"));
+    assert!(!is_copy_from_stdin("select * from users"));
+  }
+
+  #[test]
+  fn test_reads_only() {
+    let reads_only = |sql: &str| reads_only(&Parser::parse_sql(&PostgreSqlDialect {}, sql).unwrap()[0]);
+    assert!(reads_only("select * from users"));
+    assert!(reads_only("with recent as (select * from users) select * from recent"));
+    assert!(reads_only("select 1 union select 2"));
+    assert!(!reads_only("select * from users for update"));
+    assert!(!reads_only("select * into archive from users"));
+    assert!(!reads_only("update users set name = 'a'"));
+    // these also decide what's retried after a connection reset, so none of them run twice
+    assert!(!reads_only("with renamed as (update users set name = 'a' returning *) select * from renamed"));
+    assert!(!reads_only("call refresh_stats()"));
+    assert!(!reads_only("grant select on users to reporting"));
+  }
+
+  #[test]
+  fn test_replica_opts() {
+    let primary = PgConnectOptions::new().host("primary").port(5432).database("app");
+    let replicas = replica_opts::<Postgres>(&primary, "replica1, replica2:5433").unwrap();
+    let hosts = replicas.iter().map(|opts| (opts.get_host().to_owned(), opts.get_port())).collect::<Vec<_>>();
+    assert_eq!(hosts, vec![("replica1".to_owned(), 5432), ("replica2".to_owned(), 5433)]);
+    assert_eq!(replicas[0].get_database(), Some("app"));
+    assert!(replica_opts::<Postgres>(&primary, "replica1,,replica2").is_err());
+  }
+
+  #[test]
+  fn test_dry_run() {
+    let dialect = PostgreSqlDialect {};
+    let policy = StatementPolicy::from([(StatementKind::Insert, ExecutionType::Transaction)]);
+    assert_eq!(
+      dry_run("select * from users".to_owned(), &dialect, None, Some(100)).unwrap(),
+      "statement: QUERY\nruns right away (the default for QUERY statements)\nauto_limit adds a LIMIT, so it executes:\nselect * from users limit 100"
+    );
+    assert_eq!(
+      dry_run("delete from users".to_owned(), &dialect, None, Some(100)).unwrap(),
+      "statement: DELETE\nruns after asking for confirmation (it writes to every row of users)\nexecutes:\ndelete from users"
+    );
+    assert_eq!(
+      dry_run("insert into users (name) values ('a')".to_owned(), &dialect, Some(&policy), None).unwrap(),
+      "statement: INSERT\nruns in a transaction that waits for a commit or rollback (statement_policy.insert is set)\nexecutes:\ninsert into users (name) values ('a')"
+    );
+    assert!(dry_run("select 1; select 2".to_owned(), &dialect, None, None).is_err());
+  }
+
+  #[test]
+  fn test_unfiltered_write_target() {
+    let dialect = PostgreSqlDialect {};
+    let test_cases = vec![
+      ("UPDATE users SET name = 'John'", Some("users")),
+      ("DELETE FROM public.users", Some("public.users")),
+      ("EXPLAIN ANALYZE DELETE FROM \"Users\"", Some("\"Users\"")),
+      ("UPDATE users SET name = 'John' WHERE id = 1", None),
+      ("DELETE FROM users WHERE id = 1", None),
+      ("DELETE FROM users USING posts", None),
+      ("EXPLAIN DELETE FROM users", None),
+      ("SELECT * FROM users", None),
+    ];
+
+    for (query, expected) in test_cases {
+      let ast = Parser::parse_sql(&dialect, query).unwrap();
+      assert_eq!(unfiltered_write_target(&ast[0]).as_deref(), expected, "Failed for query: {}", query);
+      let execution_type = get_execution_type(ast[0].clone(), false);
+      assert_eq!(execution_type == ExecutionType::Confirm, expected.is_some(), "Failed for query: {}", query);
+    }
+  }
+
+  #[test]
+  fn test_array_literal_round_trip() {
+    let elements = vec!["a".to_owned(), "b, c".to_owned(), "".to_owned(), "say \"hi\"".to_owned(), "NULL".to_owned()];
+    let cell = vec_to_string(elements.clone());
+    assert_eq!(cell, "{ a, \"b, c\", \"\", \"say \\\"hi\\\"\", \"NULL\" }");
+    assert_eq!(parse_array_literal(&cell), Some(elements));
+    assert_eq!(vec_to_string(vec![1, 2, 3]), "{ 1, 2, 3 }");
+    assert_eq!(parse_array_literal("(1,\"x \"\"y\"\"\")"), Some(vec!["1".to_owned(), "x \"y\"".to_owned()]));
+    assert_eq!(parse_array_literal("{\"unterminated}"), None);
+    assert_eq!(parse_array_literal("not an array"), None);
+  }
+
+  #[test]
+  fn test_like_pattern() {
+    let dialect = PostgreSqlDialect {};
+    assert_eq!(like_pattern::<Postgres>(Some(" my_table ")), r"'%my\_table%' escape '\'");
+    assert_eq!(like_pattern::<Postgres>(Some(r"100%\")), r"'%100\%\\%' escape '\'");
+    assert_eq!(like_pattern::<Postgres>(None), r"'%%' escape '\'");
+    assert_eq!(like_pattern::<sqlx::MySql>(Some("my_table")), r"'%my\\_table%' escape '\\'");
+    assert!(get_first_query(Postgres::search_objects_query("it's_"), &dialect).is_ok());
+  }
+
+  #[test]
+  fn test_preview_queries_quote_names() {
+    let dialect = PostgreSqlDialect {};
+    assert_eq!(
+      Postgres::preview_rows_query("My Schema", "odd\"name"),
+      "select * from \"My Schema\".\"odd\"\"name\" limit 100"
+    );
+    let query = Postgres::preview_indexes_query("public", "it's");
+    assert!(query.contains("tablename = 'it''s'"));
+    assert!(get_first_query(query, &dialect).is_ok());
+  }
+
+  #[test]
+  fn test_with_application_name() {
+    let name = |url: &str, setting: Option<&str>| {
+      with_application_name(PgConnectOptions::from_str(url).unwrap(), setting).get_application_name().map(str::to_owned)
+    };
+    assert_eq!(name("postgres://localhost", None), Some(format!("rainfrog/{}", env!("CARGO_PKG_VERSION"))));
+    assert_eq!(name("postgres://localhost", Some("reports")), Some("reports".to_owned()));
+    assert_eq!(name("postgres://localhost", Some("")), None);
+    // the url's name is kept over both the default and the setting
+    assert_eq!(name("postgres://localhost?application_name=etl", None), Some("etl".to_owned()));
+    assert_eq!(name("postgres://localhost?application_name=etl", Some("reports")), Some("etl".to_owned()));
+  }
+
+  #[test]
+  fn test_is_utc_type() {
+    assert!(Postgres::is_utc_type("TIMESTAMPTZ"));
+    assert!(Postgres::is_utc_type("TIMESTAMPTZ[]"));
+    assert!(!Postgres::is_utc_type("TIMESTAMP"));
+    assert!(!Postgres::is_utc_type("TEXT"));
+    assert!(sqlx::MySql::is_utc_type("TIMESTAMP"));
+    assert!(!sqlx::MySql::is_utc_type("DATETIME"));
+    assert!(!sqlx::Sqlite::is_utc_type("DATETIME"));
+  }
+
+  fn schema_rows(rows: &[[&str; 8]]) -> Rows {
+    Rows {
+      headers: vec![],
+      rows: rows.iter().map(|row| row.iter().map(|v| v.to_string()).collect()).collect(),
+      rows_affected: None,
+    }
+  }
+
+  #[test]
+  fn test_schema_cache() {
+    let rows = schema_rows(&[
+      ["public", "users", "id", "integer", "PRI", "", "", ""],
+      ["public", "users", "email", "text", "", "", "", ""],
+      ["public", "orders", "id", "integer", "PRI", "", "", ""],
+      ["public", "orders", "user_id", "integer", "", "public", "users", "id"],
+      ["public", "orders", "user_id", "integer", "", "archive", "users", "id"],
+      ["archive", "orders", "id", "integer", "", "", "", ""],
+    ]);
+    let objects = schema_rows(&[["public", "users", "table", "", "", "", "", ""]]);
+    let cache = SchemaCache::from_rows(objects, &rows);
+    assert_eq!(cache.objects().rows.len(), 1);
+    let users = cache.columns(None, "users").unwrap();
+    assert_eq!(users.iter().map(|c| c.name.as_str()).collect::<Vec<&str>>(), vec!["id", "email"]);
+    assert!(users[0].primary_key && !users[1].primary_key);
+    // a column in two foreign keys is listed once, with both references
+    let orders = cache.columns(Some("public"), "orders").unwrap();
+    assert_eq!(orders.len(), 2);
+    assert_eq!(orders[1].references.len(), 2);
+    assert_eq!(orders[1].references[0].table, "users");
+    // orders is in two schemas, so it needs one to be found
+    assert!(cache.columns(None, "orders").is_none());
+    assert!(!cache.columns(Some("archive"), "orders").unwrap()[0].primary_key);
+    assert_eq!(cache.names(), vec!["archive", "email", "id", "orders", "public", "user_id", "users"]);
+    assert!(get_first_query(Postgres::schema_columns_query(), &PostgreSqlDialect {}).is_ok());
+  }
+
+  #[test]
+  fn test_referenced_rows_query() {
+    let dialect = PostgreSqlDialect {};
+    let objects = schema_rows(&[]);
+    let rows = schema_rows(&[
+      ["public", "users", "id", "integer", "PRI", "", "", ""],
+      ["public", "orders", "user_id", "integer", "", "public", "users", "id"],
+      ["public", "orders", "note", "text", "", "", "", ""],
+      ["", "items", "order_id", "INTEGER", "", "", "orders", ""],
+      ["", "orders", "id", "INTEGER", "PRI", "", "", ""],
+    ]);
+    let cache = SchemaCache::from_rows(objects, &rows);
+    let header = |name: &str, type_name: &str| Header { name: name.to_owned(), type_name: type_name.to_owned() };
+    assert_eq!(
+      referenced_rows_query::<Postgres>(&cache, &dialect, "public.orders", &header("user_id", "INT4"), "7"),
+      Some("select * from \"public\".\"users\" where \"id\" = 7 limit 100".to_owned())
+    );
+    assert_eq!(referenced_rows_query::<Postgres>(&cache, &dialect, "orders", &header("user_id", "INT4"), "NULL"), None);
+    assert_eq!(
+      referenced_rows_query::<Postgres>(&cache, &dialect, "public.orders", &header("note", "TEXT"), "a"),
+      None
+    );
+    // sqlite can leave out the referenced column when it's the primary key
+    assert_eq!(
+      referenced_rows_query::<sqlx::Sqlite>(&cache, &dialect, "items", &header("order_id", "INTEGER"), "3"),
+      Some("select * from \"orders\" where \"id\" = 3 limit 100".to_owned())
+    );
+  }
+
+  #[test]
+  fn test_ssl_options() {
+    assert!(matches!(ssl_mode("verify-full"), Ok(PgSslMode::VerifyFull)));
+    assert!(matches!(ssl_mode("VERIFY_IDENTITY"), Ok(PgSslMode::VerifyFull)));
+    assert!(matches!(ssl_mode("required"), Ok(PgSslMode::Require)));
+    assert!(ssl_mode("strict").is_err());
+    let ssl = crate::cli::SslOptions { mode: Some("verify-ca".to_owned()), ..Default::default() };
+    let opts = with_ssl_options(PgConnectOptions::new(), &ssl).unwrap();
+    assert!(matches!(opts.get_ssl_mode(), PgSslMode::VerifyCa));
   }
 }
@@ -43,37 +43,203 @@ impl super::BuildConnectionOptions for sqlx::Sqlite {
       },
     }
   }
+
+  // sqlite is a local file, so there's no tls and no server to name sessions on
+  fn with_settings(
+    opts: <Self::Connection as sqlx::Connection>::Options,
+    ssl: &crate::cli::SslOptions,
+    application_name: Option<&str>,
+  ) -> color_eyre::eyre::Result<<Self::Connection as sqlx::Connection>::Options> {
+    Ok(opts)
+  }
+
+  fn describe_connection(opts: &<Self::Connection as sqlx::Connection>::Options) -> String {
+    opts.get_filename().display().to_string()
+  }
+
+  fn connection_params(opts: &<Self::Connection as sqlx::Connection>::Options) -> Vec<(String, String)> {
+    vec![("file".to_owned(), opts.get_filename().display().to_string())]
+  }
+
+  fn with_connection_params(
+    opts: &<Self::Connection as sqlx::Connection>::Options,
+    params: &[(String, String)],
+  ) -> Result<<Self::Connection as sqlx::Connection>::Options, String> {
+    match params.iter().find(|(name, value)| name == "file" && !value.trim().is_empty()) {
+      Some((_, file)) => Ok(opts.clone().filename(file.trim())),
+      None => Ok(opts.clone()),
+    }
+  }
+}
+
+// tables in the main database are listed without a schema
+fn schema_prefix(schema: &str) -> String {
+  match schema {
+    "" => "".to_owned(),
+    schema => format!("{}.", <Sqlite as super::DatabaseQueries>::quote_identifier(schema)),
+  }
 }
 
 impl super::DatabaseQueries for Sqlite {
   fn preview_tables_query() -> String {
-    "select '' as table_schema, name as table_name
-      from sqlite_master
-      where type = 'table'
+    // the main database has no schema name, unless other databases are attached. virtual
+    // tables (fts5, rtree) are listed, but not the shadow tables that store them
+    "select case
+        when schema = 'main' and not exists (select 1 from pragma_database_list where name not in ('main', 'temp'))
+        then '' else schema
+      end as table_schema, name as table_name, type as object_type
+      from pragma_table_list
+      where type in ('table', 'view', 'virtual')
+      and schema != 'temp'
       and name not like 'sqlite_%'
       order by name asc"
       .to_owned()
   }
 
-  fn preview_rows_query(_schema: &str, table: &str) -> String {
-    format!("select * from \"{}\" limit 100", table)
+  fn preview_rows_query(schema: &str, table: &str) -> String {
+    format!("select * from {}{} limit 100", schema_prefix(schema), Self::quote_identifier(table))
   }
 
-  fn preview_columns_query(_schema: &str, table: &str) -> String {
-    format!("pragma table_info(\"{}\")", table)
+  fn preview_columns_query(schema: &str, table: &str) -> String {
+    format!("pragma {}table_info({})", schema_prefix(schema), Self::quote_identifier(table))
   }
 
-  fn preview_constraints_query(_schema: &str, table: &str) -> String {
-    format!("pragma foreign_key_list(\"{}\")", table)
+  fn preview_constraints_query(schema: &str, table: &str) -> String {
+    format!("pragma {}foreign_key_list({})", schema_prefix(schema), Self::quote_identifier(table))
   }
 
-  fn preview_indexes_query(_schema: &str, table: &str) -> String {
-    format!("pragma index_list(\"{}\")", table)
+  fn preview_indexes_query(schema: &str, table: &str) -> String {
+    format!("pragma {}index_list({})", schema_prefix(schema), Self::quote_identifier(table))
   }
 
   fn preview_policies_query(_schema: &str, _table: &str) -> String {
     "select 'SQLite does not support row-level security policies' as message".to_owned()
   }
+
+  fn primary_key_query(schema: Option<&str>, table: &str) -> String {
+    format!(
+      "select name from pragma_table_info({}, {}) where pk > 0 order by pk",
+      Self::quote_literal(table),
+      Self::quote_literal(schema.filter(|s| !s.is_empty()).unwrap_or("main"))
+    )
+  }
+
+  fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+  }
+
+  fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+  }
+
+  // sqlite has no stored functions or procedures
+  fn preview_functions_query() -> String {
+    "select '' as routine_schema, '' as routine_name, '' as object_type limit 0".to_owned()
+  }
+
+  fn preview_function_definition_query(_schema: &str, name: &str) -> String {
+    format!("select {} as name, 'sqlite does not support stored functions' as definition", Self::quote_literal(name))
+  }
+
+  fn preview_settings_query(search: Option<&str>) -> String {
+    format!(
+      "select * from (
+        select 'application_id' as name, application_id as setting from pragma_application_id
+        union all select 'auto_vacuum', auto_vacuum from pragma_auto_vacuum
+        union all select 'cache_size', cache_size from pragma_cache_size
+        union all select 'encoding', encoding from pragma_encoding
+        union all select 'foreign_keys', foreign_keys from pragma_foreign_keys
+        union all select 'journal_mode', journal_mode from pragma_journal_mode
+        union all select 'page_count', page_count from pragma_page_count
+        union all select 'page_size', page_size from pragma_page_size
+        union all select 'synchronous', synchronous from pragma_synchronous
+        union all select 'user_version', user_version from pragma_user_version
+      ) where name like {} order by name",
+      super::like_pattern::<Self>(search)
+    )
+  }
+
+  fn schema_columns_query() -> String {
+    "with tables as (
+        select case
+            when schema = 'main' and not exists (select 1 from pragma_database_list where name not in ('main', 'temp'))
+            then '' else schema
+          end as table_schema, schema as database_name, name
+          from pragma_table_list
+          where type in ('table', 'view', 'virtual')
+          and schema != 'temp'
+          and name not like 'sqlite_%'
+      )
+      select t.table_schema, t.name as table_name, c.name as column_name, c.type as data_type,
+        case when c.pk > 0 then 'PRI' else '' end as column_key,
+        case when f.\"table\" is null then '' else t.table_schema end as foreign_schema,
+        coalesce(f.\"table\", '') as foreign_table, coalesce(f.\"to\", '') as foreign_column
+        from tables t join pragma_table_info(t.name, t.database_name) c
+        left join pragma_foreign_key_list(t.name, t.database_name) f on f.\"from\" = c.name
+        order by t.table_schema, t.name, c.cid"
+      .to_owned()
+  }
+
+  // sqlite has no information_schema, so columns come from each table's table_info
+  fn search_objects_query(search: &str) -> String {
+    let pattern = super::like_pattern::<Self>(Some(search));
+    format!(
+      "with objects as (
+        select case
+            when schema = 'main' and not exists (select 1 from pragma_database_list where name not in ('main', 'temp'))
+            then '' else schema
+          end as table_schema, schema as database_name, name, type
+          from pragma_table_list
+          where type in ('table', 'view', 'virtual')
+          and schema != 'temp'
+          and name not like 'sqlite_%'
+      )
+      select table_schema, name as table_name, type as object_type, '' as column_name
+        from objects
+        where name like {pattern}
+      union all
+      select o.table_schema, o.name, 'column', c.name
+        from objects o join pragma_table_info(o.name, o.database_name) c
+        where c.name like {pattern}
+      order by table_schema, table_name, column_name
+      limit 500"
+    )
+  }
+
+  // sqlite can't explain a query by running it
+  fn explain_prefix(analyze: bool) -> String {
+    "EXPLAIN QUERY PLAN".to_owned()
+  }
+
+  fn current_schema_query() -> String {
+    "select 'main'".to_owned()
+  }
+
+  // sqlite runs in process, so there's no server to enforce a timeout
+  fn statement_timeout_statement(timeout_ms: u64) -> Option<String> {
+    None
+  }
+
+  // sqlite is embedded, so there are no other server sessions to list
+  fn preview_activity_query() -> String {
+    "select 'sqlite has no server sessions' as message".to_owned()
+  }
+
+  fn kill_session_statement(session_id: &str, cancel_only: bool) -> Option<String> {
+    None
+  }
+
+  fn attach_database_statement(path: &str, alias: &str) -> Option<String> {
+    Some(format!("attach database {} as {}", Self::quote_literal(path), Self::quote_identifier(alias)))
+  }
+
+  fn copy_in(
+    pool: &sqlx::Pool<Self>,
+    statement: String,
+    path: String,
+  ) -> Option<futures::future::BoxFuture<'static, Result<u64, super::DbError>>> {
+    None
+  }
 }
 
 impl super::HasRowsAffected for SqliteQueryResult {
@@ -216,6 +382,11 @@ impl super::ValueParser for Sqlite {
       },
     }
   }
+
+  // sqlite has no type for timestamps with a time zone
+  fn is_utc_type(_type_name: &str) -> bool {
+    false
+  }
 }
 
 mod tests {
@@ -226,7 +397,7 @@ mod tests {
   };
 
   use super::*;
-  use crate::database::{get_execution_type, get_first_query, DbError, ExecutionType};
+  use crate::database::{attach_alias, get_execution_type, get_first_query, DatabaseQueries, DbError, ExecutionType};
 
   #[test]
   fn test_get_first_query_sqlite() {
@@ -326,4 +497,45 @@ mod tests {
       assert_eq!(get_execution_type(statement, false), expected, "Failed for query: {}", query);
     }
   }
+
+  #[test]
+  fn test_attach_database() {
+    assert_eq!(attach_alias("/data/sales.db", &[]), "sales");
+    assert_eq!(attach_alias("sales.db", &["sales".to_owned()]), "sales_2");
+    assert_eq!(attach_alias("./my-archive.sqlite3", &[]), "my_archive");
+    assert_eq!(attach_alias("main.db", &[]), "main_db");
+    assert_eq!(Sqlite::attach_database_statement("it's.db", "its").unwrap(), "attach database 'it''s.db' as \"its\"");
+    assert_eq!(Sqlite::preview_rows_query("", "t"), "select * from \"t\" limit 100");
+    assert_eq!(Sqlite::preview_rows_query("sales", "t"), "select * from \"sales\".\"t\" limit 100");
+  }
+
+  #[test]
+  fn test_preview_queries_quote_names_sqlite() {
+    assert_eq!(Sqlite::preview_rows_query("", "odd\"name"), "select * from \"odd\"\"name\" limit 100");
+    assert_eq!(Sqlite::preview_indexes_query("my db", "t"), "pragma \"my db\".index_list(\"t\")");
+    assert!(Sqlite::primary_key_query(None, "it's").contains("pragma_table_info('it''s', 'main')"));
+  }
+
+  #[tokio::test]
+  async fn test_virtual_tables_listed() {
+    use sqlx::{Connection, Executor, SqliteConnection};
+    let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+    conn
+      .execute("create virtual table notes using fts5(body); create table users (id integer primary key);")
+      .await
+      .unwrap();
+    let names = |rows: Vec<sqlx::sqlite::SqliteRow>| {
+      rows.iter().map(|row| (row.get::<String, _>(1), row.get::<String, _>(2))).collect::<Vec<_>>()
+    };
+    // notes' shadow tables (notes_data, notes_idx, ...) stay out of the menu
+    let tables = conn.fetch_all(Sqlite::preview_tables_query().as_str()).await.unwrap();
+    assert_eq!(names(tables), vec![
+      ("notes".to_owned(), "virtual".to_owned()),
+      ("users".to_owned(), "table".to_owned())
+    ]);
+    let columns = conn.fetch_all(Sqlite::schema_columns_query().as_str()).await.unwrap();
+    assert_eq!(names(columns), vec![("notes".to_owned(), "body".to_owned()), ("users".to_owned(), "id".to_owned())]);
+    let found = conn.fetch_all(Sqlite::search_objects_query("note").as_str()).await.unwrap();
+    assert_eq!(names(found), vec![("notes".to_owned(), "virtual".to_owned())]);
+  }
 }
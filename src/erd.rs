@@ -0,0 +1,211 @@
+use crate::{config::ErdFormat, database::SchemaColumn};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKey {
+  pub table: String,
+  pub column: String,
+  pub foreign_schema: String,
+  pub foreign_table: String,
+  // empty when the key refers to the other table's primary key without naming it (sqlite)
+  pub foreign_column: String,
+}
+
+impl ForeignKey {
+  // every reference from the tables' columns, in table and column order
+  pub fn from_tables(tables: &[(&str, &[SchemaColumn])]) -> Vec<Self> {
+    tables
+      .iter()
+      .flat_map(|(table, columns)| {
+        columns.iter().flat_map(move |column| {
+          column.references.iter().map(move |reference| {
+            ForeignKey {
+              table: table.to_string(),
+              column: column.name.clone(),
+              foreign_schema: reference.schema.clone(),
+              foreign_table: reference.table.clone(),
+              foreign_column: reference.column.clone(),
+            }
+          })
+        })
+      })
+      .collect()
+  }
+}
+
+// a schema's tables and the foreign keys between them, as an er diagram
+pub fn render(
+  format: ErdFormat,
+  schema: &str,
+  tables: &[(&str, &[SchemaColumn])],
+  foreign_keys: &[ForeignKey],
+) -> String {
+  match format {
+    ErdFormat::Dbml => dbml(schema, tables, foreign_keys),
+    ErdFormat::Dot => dot(schema, tables, foreign_keys),
+  }
+}
+
+fn foreign_column<'a>(key: &'a ForeignKey, schema: &str, tables: &'a [(&str, &[SchemaColumn])]) -> &'a str {
+  if !key.foreign_column.is_empty() || key.foreign_schema != schema {
+    return &key.foreign_column;
+  }
+  tables
+    .iter()
+    .find(|(table, _)| *table == key.foreign_table)
+    .and_then(|(_, columns)| columns.iter().find(|column| column.primary_key))
+    .map_or(&key.foreign_column, |column| &column.name)
+}
+
+fn dbml_name(schema: &str, name: &str) -> String {
+  let quote = |name: &str| format!("\"{}\"", name.replace('"', "\\\""));
+  if schema.is_empty() {
+    quote(name)
+  } else {
+    format!("{}.{}", quote(schema), quote(name))
+  }
+}
+
+fn dbml(schema: &str, tables: &[(&str, &[SchemaColumn])], foreign_keys: &[ForeignKey]) -> String {
+  let mut lines = vec![];
+  for (table, columns) in tables {
+    lines.push(format!("Table {} {{", dbml_name(schema, table)));
+    for column in columns.iter() {
+      // types with spaces or parentheses, like "character varying(255)", have to be quoted
+      let type_name = if column.type_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        column.type_name.clone()
+      } else {
+        format!("\"{}\"", column.type_name.replace('"', "\\\""))
+      };
+      let settings = if column.primary_key { " [pk]" } else { "" };
+      lines.push(format!("  {} {}{}", dbml_name("", &column.name), type_name, settings));
+    }
+    lines.push("}".to_owned());
+    lines.push("".to_owned());
+  }
+  for key in foreign_keys {
+    lines.push(format!(
+      "Ref: {}.{} > {}.{}",
+      dbml_name(schema, &key.table),
+      dbml_name("", &key.column),
+      dbml_name(&key.foreign_schema, &key.foreign_table),
+      dbml_name("", foreign_column(key, schema, tables))
+    ));
+  }
+  lines.join("\n").trim_end().to_owned() + "\n"
+}
+
+fn dot_id(name: &str) -> String {
+  format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn html_escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// each table is a node with a row per column, and each foreign key an edge between the two columns.
+// columns are referred to by position, since ports can't hold every character a name can
+fn dot(schema: &str, tables: &[(&str, &[SchemaColumn])], foreign_keys: &[ForeignKey]) -> String {
+  let port = |table: &str, column: &str| {
+    tables
+      .iter()
+      .find(|(name, _)| *name == table)
+      .and_then(|(_, columns)| columns.iter().position(|c| c.name == column))
+      .map_or("".to_owned(), |i| format!(":c{}", i))
+  };
+  let mut lines = vec![
+    format!("digraph {} {{", dot_id(if schema.is_empty() { "main" } else { schema })),
+    "  rankdir=LR;".to_owned(),
+    "  node [shape=plaintext];".to_owned(),
+  ];
+  for (table, columns) in tables {
+    let rows = columns
+      .iter()
+      .enumerate()
+      .map(|(i, column)| {
+        let name =
+          if column.primary_key { format!("<u>{}</u>", html_escape(&column.name)) } else { html_escape(&column.name) };
+        format!("<tr><td port=\"c{}\" align=\"left\">{} <i>{}</i></td></tr>", i, name, html_escape(&column.type_name))
+      })
+      .collect::<String>();
+    lines.push(format!(
+      "  {} [label=<<table border=\"0\" cellborder=\"1\" cellspacing=\"0\"><tr><td><b>{}</b></td></tr>{}</table>>];",
+      dot_id(table),
+      html_escape(table),
+      rows
+    ));
+  }
+  for key in foreign_keys {
+    // tables in other schemas show up as plain nodes named with their schema
+    let (target, target_port) = if key.foreign_schema == schema {
+      (dot_id(&key.foreign_table), port(&key.foreign_table, foreign_column(key, schema, tables)))
+    } else {
+      (dot_id(&format!("{}.{}", key.foreign_schema, key.foreign_table)), "".to_owned())
+    };
+    lines.push(format!("  {}{} -> {}{};", dot_id(&key.table), port(&key.table, &key.column), target, target_port));
+  }
+  lines.push("}".to_owned());
+  lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::database::ColumnReference;
+
+  fn column(name: &str, type_name: &str, primary_key: bool) -> SchemaColumn {
+    SchemaColumn { name: name.to_owned(), type_name: type_name.to_owned(), primary_key, references: vec![] }
+  }
+
+  #[test]
+  fn test_render() {
+    let users = vec![column("id", "integer", true), column("email", "character varying", false)];
+    let orders = vec![column("id", "integer", true), column("user_id", "integer", false)];
+    let tables = vec![("orders", orders.as_slice()), ("users", users.as_slice())];
+    let foreign_keys = vec![ForeignKey {
+      table: "orders".to_owned(),
+      column: "user_id".to_owned(),
+      foreign_schema: "public".to_owned(),
+      foreign_table: "users".to_owned(),
+      foreign_column: "".to_owned(),
+    }];
+    assert_eq!(
+      render(ErdFormat::Dbml, "public", &tables, &foreign_keys),
+      [
+        "Table \"public\".\"orders\" {",
+        "  \"id\" integer [pk]",
+        "  \"user_id\" integer",
+        "}",
+        "",
+        "Table \"public\".\"users\" {",
+        "  \"id\" integer [pk]",
+        "  \"email\" \"character varying\"",
+        "}",
+        "",
+        "Ref: \"public\".\"orders\".\"user_id\" > \"public\".\"users\".\"id\"",
+        "",
+      ]
+      .join("\n")
+    );
+    let dot = render(ErdFormat::Dot, "public", &tables, &foreign_keys);
+    assert!(dot.starts_with("digraph \"public\" {\n"));
+    assert!(dot.contains("<td port=\"c1\" align=\"left\">email <i>character varying</i></td>"));
+    assert!(dot.contains("  \"orders\":c1 -> \"users\":c0;\n"));
+  }
+
+  #[test]
+  fn test_foreign_keys_from_tables() {
+    let mut user_id = column("user_id", "integer", false);
+    user_id.references =
+      vec![ColumnReference { schema: "public".to_owned(), table: "users".to_owned(), column: "id".to_owned() }];
+    let orders = vec![column("id", "integer", true), user_id];
+    let users = vec![column("id", "integer", true)];
+    let tables = vec![("orders", orders.as_slice()), ("users", users.as_slice())];
+    assert_eq!(ForeignKey::from_tables(&tables), vec![ForeignKey {
+      table: "orders".to_owned(),
+      column: "user_id".to_owned(),
+      foreign_schema: "public".to_owned(),
+      foreign_table: "users".to_owned(),
+      foreign_column: "id".to_owned(),
+    }]);
+  }
+}
@@ -10,6 +10,7 @@ pub mod cli;
 pub mod components;
 pub mod config;
 pub mod database;
+pub mod erd;
 pub mod focus;
 pub mod popups;
 pub mod tui;
@@ -23,9 +24,11 @@ use std::{
 };
 
 use clap::Parser;
-use cli::{extract_driver_from_url, prompt_for_driver, Cli, Driver};
-use color_eyre::eyre::{self, Result};
-use database::{BuildConnectionOptions, DatabaseQueries, HasRowsAffected, ValueParser};
+use cli::{error_summary, extract_driver_from_url, prompt_for_driver, prompt_for_retry, Cli, Driver, StartupFailure};
+use color_eyre::eyre::{self, Result, WrapErr};
+use database::{
+  classify_connection_error, BuildConnectionOptions, ConnectionFailure, DatabaseQueries, HasRowsAffected, ValueParser,
+};
 use sqlx::{postgres::PgConnectOptions, Connection, Database, Executor, MySql, Pool, Postgres, Sqlite};
 
 use crate::{
@@ -33,7 +36,7 @@ use crate::{
   utils::{initialize_logging, initialize_panic_handler, version},
 };
 
-async fn run_app<DB>(mut args: Cli) -> Result<()>
+async fn run_app<DB>(mut args: Cli, config: config::Config, initial_query: Option<String>) -> Result<()>
 where
   DB: Database + BuildConnectionOptions + ValueParser + DatabaseQueries,
   DB::QueryResult: HasRowsAffected,
@@ -41,8 +44,60 @@ where
   for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
 {
   let mouse_mode = args.mouse_mode.take();
-  let connection_opts = DB::build_connection_opts(args)?;
-  let mut app = App::<'_, DB>::new(connection_opts, mouse_mode)?;
+  let (connection_opts, config) = loop {
+    // the global ssl setting is used to pick a host, and the connection's own once it's picked
+    let build_args = Cli { ssl: args.ssl.or_settings(config.settings.ssl.as_ref()), ..args.clone() };
+    let opts = DB::build_connection_opts(build_args).wrap_err(StartupFailure::Config)?;
+    let config = config.for_connection(&DB::describe_connection(&opts)).wrap_err(StartupFailure::Config)?;
+    let ssl = args.ssl.or_settings(config.settings.ssl.as_ref());
+    let opts =
+      DB::with_settings(opts, &ssl, config.settings.application_name.as_deref()).wrap_err(StartupFailure::Config)?;
+    let error = match DB::Connection::connect_with(&opts).await {
+      Ok(conn) => {
+        conn.close().await?;
+        break (opts, config);
+      },
+      Err(e) => e,
+    };
+    let failure = classify_connection_error(&error);
+    eprintln!("could not connect: {}\n  ({})", failure.hint(), error);
+    // a url can't be partially re-entered, so only the individual options are re-prompted
+    if args.connection_url.is_some() || !prompt_for_retry()? {
+      let failure = match failure {
+        ConnectionFailure::Authentication => StartupFailure::Authentication,
+        _ => StartupFailure::Connection,
+      };
+      return Err(eyre::Report::from(error).wrap_err(failure));
+    }
+    match failure {
+      ConnectionFailure::Authentication => {
+        args.user = None;
+        args.password = None;
+      },
+      ConnectionFailure::UnknownDatabase => args.database = None,
+      ConnectionFailure::Unreachable => {},
+      ConnectionFailure::Other => {
+        args.user = None;
+        args.password = None;
+        args.database = None;
+      },
+    }
+    // a socket is re-entered as a socket, and the host and port are only asked for without one
+    if matches!(failure, ConnectionFailure::Unreachable | ConnectionFailure::Other) {
+      args.socket = match args.socket {
+        Some(_) => cli::prompt_for_socket()?,
+        None => None,
+      };
+      if args.socket.is_none() {
+        args.host = None;
+        args.port = None;
+      }
+    }
+  };
+  let connection_name = DB::describe_connection(&connection_opts);
+  let mut app = App::<'_, DB>::new(connection_opts, mouse_mode, connection_name, config)
+    .wrap_err(StartupFailure::Config)?
+    .initial_query(initial_query, args.run);
   app.run().await?;
   Ok(())
 }
@@ -53,17 +108,20 @@ async fn tokio_main() -> Result<()> {
   initialize_panic_handler()?;
 
   let mut args = Cli::parse();
+  // read once, and resolved again with the connection's overrides once it's known
+  let config = config::Config::new().wrap_err(StartupFailure::Config)?;
+  let initial_query = cli::read_initial_query(&args).wrap_err(StartupFailure::Config)?;
   let driver = if let Some(driver) = args.driver.take() {
     driver
   } else if let Some(ref url) = args.connection_url {
-    extract_driver_from_url(url)?
+    extract_driver_from_url(url).wrap_err(StartupFailure::Config)?
   } else {
     prompt_for_driver()?
   };
   match driver {
-    Driver::Postgres => run_app::<Postgres>(args).await,
-    Driver::Mysql => run_app::<MySql>(args).await,
-    Driver::Sqlite => run_app::<Sqlite>(args).await,
+    Driver::Postgres => run_app::<Postgres>(args, config, initial_query).await,
+    Driver::Mysql => run_app::<MySql>(args, config, initial_query).await,
+    Driver::Sqlite => run_app::<Sqlite>(args, config, initial_query).await,
   }
 }
 
@@ -71,6 +129,14 @@ async fn tokio_main() -> Result<()> {
 async fn main() -> Result<()> {
   if let Err(e) = tokio_main().await {
     eprintln!("{} error: Something went wrong", env!("CARGO_PKG_NAME"));
+    // only startup failures get a summary and their own exit code. nothing needs restoring
+    // before the tui starts, so exiting right away skips no cleanup
+    if let Some((code, summary)) = error_summary(&e) {
+      eprintln!("{:?}", e);
+      // the last line is for scripts, which can also branch on the exit code
+      eprintln!("{}", summary);
+      std::process::exit(code);
+    }
     Err(e)
   } else {
     Ok(())
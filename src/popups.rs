@@ -1,29 +1,69 @@
 use async_trait::async_trait;
 use color_eyre::eyre::Result;
 use crossterm::event::KeyEvent;
+use ratatui::text::Line;
 use sqlparser::ast::Statement;
 
 use crate::{
+  action::Action,
   app::AppState,
   database::{DbError, Rows},
 };
 
+pub mod attach_database;
+pub mod cell_viewer;
+pub mod column_picker;
+pub mod confirm_kill;
+pub mod confirm_paste;
+pub mod confirm_preview;
 pub mod confirm_query;
+pub mod confirm_quit;
 pub mod confirm_tx;
+pub mod copy_from_file;
+pub mod edit_cell;
+pub mod filter_rows;
+pub mod fuzzy_finder;
+pub mod histogram;
+pub mod insert_rows;
+pub mod json_viewer;
+pub mod jump_to_row;
+pub mod message;
+pub mod object_search;
+pub mod reconnect;
+pub mod registers;
 
 // since popups are meant to overlay the entire app and capture
 // all input, we have a payload representing when a popup is exited
 // and some action by the main thread is desired. easier than making
 // it work with Actions for now.
+#[allow(clippy::large_enum_variant)]
 pub enum PopUpPayload {
   SetDataTable(Option<Result<Rows, DbError>>, Option<Statement>),
   ConfirmQuery(String),
+  Query(String),
+  InsertRows(String, Vec<Vec<String>>), // (table, rows)
+  CopyData(String),
+  SetVisibleColumns(Vec<usize>),
+  JumpToRow(usize),
+  Action(Action),
+  Cancel,
+}
+
+// a scrollable list shown in a larger popup instead of the call to action text
+pub struct PopUpBody {
+  pub title: String,
+  pub lines: Vec<Line<'static>>,
+  pub selected: usize,
 }
 
 #[async_trait(?Send)]
 pub trait PopUp<DB: sqlx::Database> {
   #[allow(unused_variables)]
-  async fn handle_key_events(&self, key: KeyEvent, app_state: &mut AppState<'_, DB>) -> Result<Option<PopUpPayload>>;
+  async fn handle_key_events(
+    &mut self,
+    key: KeyEvent,
+    app_state: &mut AppState<'_, DB>,
+  ) -> Result<Option<PopUpPayload>>;
 
   #[allow(unused_variables)]
   fn get_cta_text(&self, app_state: &AppState<'_, DB>) -> String {
@@ -34,4 +74,14 @@ pub trait PopUp<DB: sqlx::Database> {
   fn get_actions_text(&self, app_state: &AppState<'_, DB>) -> String {
     "".to_string()
   }
+
+  // shown in red instead of yellow, for actions that are especially hard to undo
+  fn is_warning(&self) -> bool {
+    false
+  }
+
+  #[allow(unused_variables)]
+  fn get_body(&self, app_state: &AppState<'_, DB>) -> Option<PopUpBody> {
+    None
+  }
 }
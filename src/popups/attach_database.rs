@@ -0,0 +1,53 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+use crate::action::Action;
+
+// asks for the path of a database file to attach
+#[derive(Debug, Default)]
+pub struct AttachDatabase<DB: sqlx::Database> {
+  path: String,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> AttachDatabase<DB> {
+  pub fn new() -> Self {
+    Self { path: String::new(), phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for AttachDatabase<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter if !self.path.trim().is_empty() => {
+        Ok(Some(PopUpPayload::Action(Action::AttachDatabase(self.path.trim().to_owned()))))
+      },
+      KeyCode::Backspace => {
+        self.path.pop();
+        Ok(None)
+      },
+      KeyCode::Char(c) => {
+        self.path.push(c);
+        Ok(None)
+      },
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    format!("Attach database file: {}_", self.path)
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "type the file path | [<enter>] attach | [<esc>] cancel".to_string()
+  }
+}
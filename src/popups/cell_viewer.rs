@@ -0,0 +1,82 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{style::Stylize, text::Line};
+
+use super::{PopUp, PopUpBody, PopUpPayload};
+
+// shows the whole of a field that's cut off in the table, a line at a time
+#[derive(Debug)]
+pub struct CellViewer<DB: sqlx::Database> {
+  lines: Vec<String>,
+  selected: usize,
+  phantom: PhantomData<DB>,
+}
+
+// long lines are broken up, since the popup body doesn't wrap
+const LINE_WIDTH: usize = 80;
+
+impl<DB: sqlx::Database> CellViewer<DB> {
+  pub fn new(value: &str) -> Self {
+    Self { lines: wrap_lines(value, LINE_WIDTH), selected: 0, phantom: PhantomData }
+  }
+}
+
+fn wrap_lines(value: &str, width: usize) -> Vec<String> {
+  value
+    .lines()
+    .flat_map(|line| {
+      let chars = line.chars().collect::<Vec<char>>();
+      match chars.len() {
+        0 => vec![String::new()],
+        _ => chars.chunks(width).map(|chunk| chunk.iter().collect()).collect(),
+      }
+    })
+    .collect()
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for CellViewer<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    let last = self.lines.len().saturating_sub(1);
+    match key.code {
+      KeyCode::Esc | KeyCode::Enter => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Char('j') | KeyCode::Down => self.selected = (self.selected + 1).min(last),
+      KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+      KeyCode::Char('G') | KeyCode::End => self.selected = last,
+      KeyCode::Char('g') | KeyCode::Home => self.selected = 0,
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let lines = self
+      .lines
+      .iter()
+      .enumerate()
+      .map(|(i, line)| if i == self.selected { Line::from(line.clone()).reversed() } else { Line::from(line.clone()) })
+      .collect();
+    Some(PopUpBody { title: " Field ".to_owned(), lines, selected: self.selected })
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[j|k] scroll | [g|G] top/bottom | [<esc>] close".to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_wrap_lines() {
+    assert_eq!(wrap_lines("abcdef\n\nxy", 4), vec!["abcd", "ef", "", "xy"]);
+    assert_eq!(wrap_lines("", 4), Vec::<String>::new());
+  }
+}
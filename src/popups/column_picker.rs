@@ -0,0 +1,119 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{style::Stylize, text::Line};
+
+use super::{PopUp, PopUpBody, PopUpPayload};
+
+// picks which of the results' columns are shown, and in what order
+#[derive(Debug)]
+pub struct ColumnPicker<DB: sqlx::Database> {
+  columns: Vec<(usize, String, bool)>, // (index in the rows, name, visible)
+  selected: usize,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> ColumnPicker<DB> {
+  // an empty list of visible columns means every column is shown as is.
+  // hidden columns are listed after the visible ones, in their original order
+  pub fn new(headers: Vec<String>, visible: Vec<usize>) -> Self {
+    let visible = if visible.is_empty() { (0..headers.len()).collect() } else { visible };
+    let hidden = (0..headers.len()).filter(|i| !visible.contains(i)).collect::<Vec<usize>>();
+    let columns = visible
+      .iter()
+      .map(|i| (*i, true))
+      .chain(hidden.iter().map(|i| (*i, false)))
+      .filter_map(|(i, shown)| Some((i, headers.get(i)?.clone(), shown)))
+      .collect();
+    Self { columns, selected: 0, phantom: PhantomData }
+  }
+
+  fn visible_columns(&self) -> Vec<usize> {
+    self.columns.iter().filter(|(_, _, visible)| *visible).map(|(i, _, _)| *i).collect()
+  }
+
+  // moves the selected column one place left (up the list) or right, taking the selection with it
+  fn move_selected(&mut self, right: bool) {
+    let target = if right { self.selected + 1 } else { self.selected.wrapping_sub(1) };
+    if target < self.columns.len() {
+      self.columns.swap(self.selected, target);
+      self.selected = target;
+    }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for ColumnPicker<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter => return Ok(Some(PopUpPayload::SetVisibleColumns(self.visible_columns()))),
+      KeyCode::Char('j') | KeyCode::Down => {
+        self.selected = (self.selected + 1).min(self.columns.len().saturating_sub(1));
+      },
+      KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+      KeyCode::Char('h') | KeyCode::Left => self.move_selected(false),
+      KeyCode::Char('l') | KeyCode::Right => self.move_selected(true),
+      KeyCode::Char('a') => self.columns.iter_mut().for_each(|(_, _, visible)| *visible = true),
+      // at least one column always stays visible
+      KeyCode::Char(' ') => {
+        let visible_count = self.visible_columns().len();
+        if let Some((_, _, visible)) = self.columns.get_mut(self.selected) {
+          if !*visible || visible_count > 1 {
+            *visible = !*visible;
+          }
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let lines = self
+      .columns
+      .iter()
+      .enumerate()
+      .map(|(i, (_, name, visible))| {
+        let line = Line::from(format!("[{}] {}", if *visible { "x" } else { " " }, name));
+        let line = if *visible { line } else { line.dim() };
+        if i == self.selected {
+          line.reversed()
+        } else {
+          line
+        }
+      })
+      .collect();
+    Some(PopUpBody { title: " Columns ".to_owned(), lines, selected: self.selected })
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[j|k] select | [<space>] show/hide | [a] show all | [h|l] move left/right | [<enter>] apply | [<esc>] cancel"
+      .to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_column_picker() {
+    let headers = ["id", "name", "email", "created_at"].iter().map(|h| h.to_string()).collect::<Vec<String>>();
+    let picker = ColumnPicker::<sqlx::Postgres>::new(headers.clone(), vec![]);
+    assert_eq!(picker.visible_columns(), vec![0, 1, 2, 3]);
+    let mut picker = ColumnPicker::<sqlx::Postgres>::new(headers, vec![2, 0]);
+    assert_eq!(picker.columns.iter().map(|(i, _, _)| *i).collect::<Vec<usize>>(), vec![2, 0, 1, 3]);
+    assert_eq!(picker.visible_columns(), vec![2, 0]);
+    picker.move_selected(true);
+    assert_eq!((picker.visible_columns(), picker.selected), (vec![0, 2], 1));
+    picker.move_selected(false);
+    picker.move_selected(false);
+    assert_eq!((picker.visible_columns(), picker.selected), (vec![2, 0], 0));
+  }
+}
@@ -0,0 +1,50 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+
+#[derive(Debug)]
+pub struct ConfirmKill<DB: sqlx::Database> {
+  session_id: String,
+  statement: String,
+  cancel_only: bool,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> ConfirmKill<DB> {
+  pub fn new(session_id: String, statement: String, cancel_only: bool) -> Self {
+    Self { session_id, statement, cancel_only, phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for ConfirmKill<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char('Y') => Ok(Some(PopUpPayload::ConfirmQuery(self.statement.to_owned()))),
+      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    if self.cancel_only {
+      format!("Are you sure you want to cancel the running query of session {}? ({})", self.session_id, self.statement)
+    } else {
+      format!(
+        "Are you sure you want to kill session {}? Its open transaction will be rolled back. ({})",
+        self.session_id, self.statement
+      )
+    }
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[Y]es to confirm | [N]o to cancel".to_string()
+  }
+}
@@ -0,0 +1,47 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+use crate::action::Action;
+
+// asks before a large paste goes into the editor, since it's usually an accident
+#[derive(Debug)]
+pub struct ConfirmPaste<DB: sqlx::Database> {
+  text: String,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> ConfirmPaste<DB> {
+  pub fn new(text: String) -> Self {
+    Self { text, phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for ConfirmPaste<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char('Y') => Ok(Some(PopUpPayload::Action(Action::InsertIntoEditor(std::mem::take(&mut self.text))))),
+      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    format!(
+      "Are you sure you want to paste {} lines ({} characters) into the editor?",
+      self.text.lines().count(),
+      self.text.chars().count()
+    )
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[Y]es to paste | [N]o to cancel".to_string()
+  }
+}
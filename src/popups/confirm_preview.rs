@@ -0,0 +1,57 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+use crate::action::Action;
+
+// asked before a preview from the menu replaces results that have marked rows
+#[derive(Debug)]
+pub struct ConfirmPreview<DB: sqlx::Database> {
+  preview: Action,
+  marked: usize,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> ConfirmPreview<DB> {
+  pub fn new(preview: Action, marked: usize) -> Self {
+    Self { preview, marked, phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for ConfirmPreview<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char('Y') => {
+        app_state.preview_confirmed = true;
+        Ok(Some(PopUpPayload::Action(self.preview.clone())))
+      },
+      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    let target = match &self.preview {
+      Action::MenuPreview(_, schema, table) if schema.is_empty() => table.clone(),
+      Action::MenuPreview(_, schema, table) => format!("{}.{}", schema, table),
+      _ => "the preview".to_owned(),
+    };
+    format!(
+      "The results have {} marked {}, which will be lost. Replace them with {}?",
+      self.marked,
+      if self.marked == 1 { "row" } else { "rows" },
+      target
+    )
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[Y]es to replace | [N]o to keep them".to_string()
+  }
+}
@@ -1,4 +1,7 @@
-use std::marker::PhantomData;
+use std::{
+  marker::PhantomData,
+  sync::{Arc, Mutex},
+};
 
 use async_trait::async_trait;
 use crossterm::event::{KeyCode, KeyEvent};
@@ -10,26 +13,48 @@ use super::{PopUp, PopUpPayload};
 use crate::{
   action::Action,
   app::DbTask,
-  database::{statement_type_string, Rows},
+  database::{statement_type_string, unfiltered_write_target, Rows},
 };
 
 #[derive(Debug)]
 pub struct ConfirmQuery<DB: sqlx::Database> {
   pending_query: String,
   statement_type: Statement,
+  row_count: Arc<Mutex<Option<String>>>, // of the table an unfiltered UPDATE or DELETE writes to, once counted
+  estimated_rows: Option<u64>,           // the planner's estimate for a SELECT
   phantom: PhantomData<DB>,
 }
 
 impl<DB: sqlx::Database> ConfirmQuery<DB> {
   pub fn new(pending_query: String, statement_type: Statement) -> Self {
-    Self { pending_query, statement_type, phantom: PhantomData }
+    Self { pending_query, statement_type, row_count: Arc::default(), estimated_rows: None, phantom: PhantomData }
+  }
+
+  pub fn row_count(mut self, row_count: Arc<Mutex<Option<String>>>) -> Self {
+    self.row_count = row_count;
+    self
+  }
+
+  pub fn estimated_rows(mut self, estimated_rows: u64) -> Self {
+    self.estimated_rows = Some(estimated_rows);
+    self
+  }
+}
+
+// rounds big counts the way people say them, ex. 1.2M
+fn approximate_count(count: u64) -> String {
+  match count {
+    0..1_000 => count.to_string(),
+    1_000..1_000_000 => format!("{:.1}K", count as f64 / 1e3),
+    1_000_000..1_000_000_000 => format!("{:.1}M", count as f64 / 1e6),
+    _ => format!("{:.1}B", count as f64 / 1e9),
   }
 }
 
 #[async_trait(?Send)]
 impl<DB: sqlx::Database> PopUp<DB> for ConfirmQuery<DB> {
   async fn handle_key_events(
-    &self,
+    &mut self,
     key: crossterm::event::KeyEvent,
     app_state: &mut crate::app::AppState<'_, DB>,
   ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
@@ -41,6 +66,30 @@ impl<DB: sqlx::Database> PopUp<DB> for ConfirmQuery<DB> {
   }
 
   fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    if let Some(estimated_rows) = self.estimated_rows {
+      return format!(
+        "The planner expects ~{} rows from this query. Are you sure you want to run it?",
+        approximate_count(estimated_rows)
+      );
+    }
+    if let Some(table) = unfiltered_write_target(&self.statement_type) {
+      let (verb, effect) = match &self.statement_type {
+        Statement::Update { .. } => ("UPDATE", "change"),
+        Statement::Explain { statement, .. } if matches!(statement.as_ref(), Statement::Update { .. }) => {
+          ("EXPLAIN ANALYZE UPDATE", "change")
+        },
+        Statement::Explain { .. } => ("EXPLAIN ANALYZE DELETE", "delete"),
+        _ => ("DELETE", "delete"),
+      };
+      let rows = match self.row_count.lock().unwrap().as_ref() {
+        Some(count) => format!("all {} rows", count),
+        None => "every row".to_owned(),
+      };
+      return format!(
+        "WARNING: this {} has no WHERE clause and will {} {} in {}. It runs in a transaction, so you can still roll it back. Are you absolutely sure?",
+        verb, effect, rows, table
+      );
+    }
     match self.statement_type.clone() {
       Statement::Explain { statement, .. } => {
         format!(
@@ -60,4 +109,8 @@ impl<DB: sqlx::Database> PopUp<DB> for ConfirmQuery<DB> {
   fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
     "[Y]es to confirm | [N]o to cancel".to_string()
   }
+
+  fn is_warning(&self) -> bool {
+    unfiltered_write_target(&self.statement_type).is_some()
+  }
 }
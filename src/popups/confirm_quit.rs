@@ -0,0 +1,111 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+use crate::{
+  action::Action,
+  app::{DbTask, WriteOutcome},
+  database::Rows,
+};
+
+// asked on quit while a query is running or a transaction is waiting to be committed,
+// so that neither is dropped without the user knowing
+#[derive(Debug, Default)]
+pub struct ConfirmQuit<DB: sqlx::Database> {
+  // why committing failed. the transaction is gone by then, so quitting loses nothing more
+  commit_error: Option<String>,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> ConfirmQuit<DB> {
+  pub fn new() -> Self {
+    Self { commit_error: None, phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for ConfirmQuit<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Char('C') if matches!(app_state.query_task, Some(DbTask::TxPending(..))) => {
+        if let Some(DbTask::TxPending(tx, results)) = app_state.query_task.take() {
+          let rows_affected = match results.results {
+            Ok(Rows { rows_affected, .. }) => rows_affected,
+            Err(_) => None,
+          };
+          match tx.commit().await {
+            Ok(_) => app_state.record_write(&results.statement_type, rows_affected, WriteOutcome::Committed),
+            Err(e) => {
+              log::error!("{}", e);
+              app_state.record_write(&results.statement_type, rows_affected, WriteOutcome::Failed);
+              self.commit_error = Some(e.to_string());
+              return Ok(None);
+            },
+          };
+        }
+        Ok(Some(PopUpPayload::Action(Action::Quit)))
+      },
+      KeyCode::Char('R') => {
+        match app_state.query_task.take() {
+          Some(DbTask::TxPending(tx, results)) => {
+            let rows_affected = match results.results {
+              Ok(Rows { rows_affected, .. }) => rows_affected,
+              Err(_) => None,
+            };
+            let outcome = match tx.rollback().await {
+              Ok(_) => WriteOutcome::RolledBack,
+              Err(e) => {
+                log::error!("{}", e);
+                WriteOutcome::Failed
+              },
+            };
+            app_state.record_write(&results.statement_type, rows_affected, outcome);
+          },
+          // an aborted transaction is rolled back when it's dropped
+          Some(DbTask::Query(task)) | Some(DbTask::TxCommit(task)) => task.abort(),
+          Some(DbTask::TxStart(task)) => task.abort(),
+          Some(DbTask::Estimate(task, ..)) => task.abort(),
+          None => {},
+        }
+        Ok(Some(PopUpPayload::Action(Action::Quit)))
+      },
+      KeyCode::Char('N') | KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    if let Some(e) = &self.commit_error {
+      return format!("The commit failed, so nothing was committed: {}", e);
+    }
+    match app_state.query_task {
+      Some(DbTask::TxPending(..)) => {
+        "A transaction is waiting to be committed. Commit or roll it back before quitting?"
+      },
+      Some(DbTask::TxCommit(_)) => "A transaction is being committed. Abort it and quit?",
+      _ => "A query is still running. Abort it and quit?",
+    }
+    .to_string()
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    if self.commit_error.is_some() {
+      return "[R] quit anyway | [N]o to keep working".to_string();
+    }
+    match app_state.query_task {
+      Some(DbTask::TxPending(..)) => "[C]ommit and quit | [R]oll back and quit | [N]o to keep working",
+      _ => "[R] abort and quit | [N]o to keep working",
+    }
+    .to_string()
+  }
+
+  fn is_warning(&self) -> bool {
+    self.commit_error.is_some()
+  }
+}
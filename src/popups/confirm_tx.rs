@@ -2,32 +2,42 @@ use std::marker::PhantomData;
 
 use async_trait::async_trait;
 use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{style::Stylize, text::Line};
 use sqlparser::ast::Statement;
 use sqlx::Either;
 use tokio::sync::mpsc::UnboundedSender;
 
-use super::{PopUp, PopUpPayload};
+use super::{PopUp, PopUpBody, PopUpPayload};
 use crate::{
   action::Action,
-  app::DbTask,
-  database::{statement_type_string, Rows},
+  app::{DbTask, WriteOutcome, AFFECTED_PREVIEW_ROWS},
+  database::{rows_to_text, statement_type_string, Rows},
 };
 
 #[derive(Debug, Default)]
 pub struct ConfirmTx<DB: sqlx::Database> {
+  // the line of the affected rows' preview that's selected, while it's shown
+  preview: Option<usize>,
   phantom: PhantomData<DB>,
 }
 
 impl<DB: sqlx::Database> ConfirmTx<DB> {
   pub fn new() -> Self {
-    Self { phantom: PhantomData }
+    Self { preview: None, phantom: PhantomData }
+  }
+}
+
+fn affected_preview<'a, DB: sqlx::Database>(app_state: &'a crate::app::AppState<'_, DB>) -> Option<&'a Rows> {
+  match &app_state.query_task {
+    Some(DbTask::TxPending(_, results)) => results.affected_preview.as_ref(),
+    _ => None,
   }
 }
 
 #[async_trait(?Send)]
 impl<DB: sqlx::Database> PopUp<DB> for ConfirmTx<DB> {
   async fn handle_key_events(
-    &self,
+    &mut self,
     key: crossterm::event::KeyEvent,
     app_state: &mut crate::app::AppState<'_, DB>,
   ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
@@ -44,6 +54,15 @@ impl<DB: sqlx::Database> PopUp<DB> for ConfirmTx<DB> {
             },
             _ => panic!("inconsistent key codes"),
           };
+          let rows_affected = match results.results {
+            Ok(Rows { rows_affected, .. }) => rows_affected,
+            Err(_) => None,
+          };
+          app_state.record_write(&results.statement_type, rows_affected, match (&result, rolled_back) {
+            (Err(_), _) => WriteOutcome::Failed,
+            (Ok(_), true) => WriteOutcome::RolledBack,
+            (Ok(_), false) => WriteOutcome::Committed,
+          });
           Ok(Some(PopUpPayload::SetDataTable(
             match result {
               Ok(_) => {
@@ -70,10 +89,50 @@ impl<DB: sqlx::Database> PopUp<DB> for ConfirmTx<DB> {
           Ok(None)
         }
       },
+      KeyCode::Char('P') if affected_preview(app_state).is_some() => {
+        self.preview = match self.preview {
+          Some(_) => None,
+          None => Some(0),
+        };
+        Ok(None)
+      },
+      KeyCode::Char('j') | KeyCode::Down if self.preview.is_some() => {
+        // the header and its underline come before the rows
+        let last = affected_preview(app_state).map_or(0, |rows| rows.rows.len() + 1);
+        self.preview = self.preview.map(|selected| (selected + 1).min(last));
+        Ok(None)
+      },
+      KeyCode::Char('k') | KeyCode::Up if self.preview.is_some() => {
+        self.preview = self.preview.map(|selected| selected.saturating_sub(1));
+        Ok(None)
+      },
       _ => Ok(None),
     }
   }
 
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let selected = self.preview?;
+    let rows = affected_preview(app_state)?;
+    let Some(DbTask::TxPending(_, results)) = &app_state.query_task else {
+      return None;
+    };
+    let lines = rows_to_text(&rows.headers, &rows.rows)
+      .lines()
+      .enumerate()
+      .map(|(i, line)| if i == selected { Line::from(line.to_owned()).reversed() } else { Line::from(line.to_owned()) })
+      .collect();
+    let count = match rows.rows.len() as u64 {
+      AFFECTED_PREVIEW_ROWS => format!("the first {}", AFFECTED_PREVIEW_ROWS),
+      n => n.to_string(),
+    };
+    let title = format!(
+      " {} rows to {}, as they were before it ran ",
+      count,
+      statement_type_string(&results.statement_type).to_uppercase()
+    );
+    Some(PopUpBody { title, lines, selected })
+  }
+
   fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
     if let Some(DbTask::TxPending(tx, results)) = &app_state.query_task {
       let rows_affected = match results.results {
@@ -107,6 +166,10 @@ impl<DB: sqlx::Database> PopUp<DB> for ConfirmTx<DB> {
   }
 
   fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
-    "[Y]es to confirm | [N]o to cancel".to_string()
+    match (affected_preview(app_state), self.preview) {
+      (Some(_), Some(_)) => "[Y]es to confirm | [N]o to cancel | [j|k] scroll | [P] hide the rows".to_string(),
+      (Some(_), None) => "[Y]es to confirm | [N]o to cancel | [P]review the affected rows".to_string(),
+      _ => "[Y]es to confirm | [N]o to cancel".to_string(),
+    }
   }
 }
@@ -0,0 +1,90 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::{PopUp, PopUpPayload};
+use crate::{
+  action::SelectedRow,
+  database::{update_cell_statement, DatabaseQueries},
+};
+
+#[derive(Debug)]
+pub struct EditCell<DB: sqlx::Database + DatabaseQueries> {
+  row: SelectedRow,
+  column: usize,
+  key_columns: Vec<String>,
+  // None is NULL, which is different from typing the text "NULL"
+  value: Option<String>,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database + DatabaseQueries> EditCell<DB> {
+  pub fn new(row: SelectedRow, column: usize, key_columns: Vec<String>) -> Self {
+    let value = row.values.get(column).filter(|value| *value != "NULL").cloned();
+    Self { row, column, key_columns, value, phantom: PhantomData }
+  }
+
+  fn statement(&self) -> Result<String, String> {
+    self.statement_for(self.value.as_deref())
+  }
+
+  fn statement_for(&self, value: Option<&str>) -> Result<String, String> {
+    update_cell_statement::<DB>(
+      &self.row.table,
+      &self.row.headers,
+      &self.row.values,
+      self.column,
+      value,
+      &self.key_columns,
+    )
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database + DatabaseQueries> PopUp<DB> for EditCell<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter => Ok(self.statement().ok().map(PopUpPayload::Query)),
+      KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+        self.value = None;
+        Ok(None)
+      },
+      KeyCode::Backspace => {
+        if let Some(value) = self.value.as_mut() {
+          value.pop();
+        }
+        Ok(None)
+      },
+      KeyCode::Char(c) => {
+        self.value.get_or_insert_with(String::new).push(c);
+        Ok(None)
+      },
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    match self.statement() {
+      Ok(statement) => statement,
+      Err(e) => e,
+    }
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    match (self.statement(), &self.value) {
+      (Ok(_), None) => "type a value to replace NULL | [<enter>] run in a transaction | [<esc>] cancel".to_string(),
+      (Ok(_), Some(_)) => {
+        "type the new value | [<ctrl+n>] NULL | [<enter>] run in a transaction | [<esc>] cancel".to_string()
+      },
+      // setting NULL always works when the row can be matched, so anything else is about the row
+      (Err(_), _) if self.statement_for(None).is_err() => "[<esc>] close".to_string(),
+      (Err(_), _) => "fix the value | [<ctrl+n>] NULL | [<esc>] cancel".to_string(),
+    }
+  }
+}
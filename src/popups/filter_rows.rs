@@ -0,0 +1,149 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  style::{Style, Stylize},
+  text::{Line, Span},
+};
+
+use super::{PopUp, PopUpBody, PopUpPayload};
+use crate::{
+  action::Action,
+  database::{filter_predicate, filtered_rows_query, DatabaseQueries, FILTER_OPERATORS},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+  Column,
+  Operator,
+  Value,
+}
+
+// builds a WHERE clause for a table's rows out of a column, an operator, and a value
+#[derive(Debug)]
+pub struct FilterRows<DB: sqlx::Database + DatabaseQueries> {
+  table: String,
+  columns: Vec<(String, String)>, // (name, type)
+  column: usize,
+  operator: usize,
+  value: String,
+  field: Field,
+  error: Option<String>,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database + DatabaseQueries> FilterRows<DB> {
+  pub fn new(table: String, columns: Vec<(String, String)>) -> Self {
+    Self {
+      table,
+      columns,
+      column: 0,
+      operator: 0,
+      value: String::new(),
+      field: Field::Column,
+      error: None,
+      phantom: PhantomData,
+    }
+  }
+
+  fn takes_value(&self) -> bool {
+    !FILTER_OPERATORS[self.operator].starts_with("is ")
+  }
+
+  fn predicate(&self) -> Result<String, String> {
+    let (name, type_name) = &self.columns[self.column];
+    filter_predicate::<DB>(name, type_name, FILTER_OPERATORS[self.operator], &self.value)
+  }
+
+  fn next_field(&mut self) {
+    self.field = match self.field {
+      Field::Column => Field::Operator,
+      Field::Operator if self.takes_value() => Field::Value,
+      _ => Field::Column,
+    };
+  }
+
+  fn previous_field(&mut self) {
+    self.field = match self.field {
+      Field::Column if self.takes_value() => Field::Value,
+      Field::Column | Field::Value => Field::Operator,
+      Field::Operator => Field::Column,
+    };
+  }
+
+  // moves the column or operator selection, wrapping around
+  fn cycle(&mut self, forwards: bool) {
+    let (selected, count) = match self.field {
+      Field::Column => (&mut self.column, self.columns.len()),
+      Field::Operator => (&mut self.operator, FILTER_OPERATORS.len()),
+      Field::Value => return,
+    };
+    *selected = if forwards { (*selected + 1) % count } else { (*selected + count - 1) % count };
+  }
+
+  fn field_line(&self, field: Field, label: &str, value: String) -> Line<'static> {
+    let line = Line::from(vec![Span::styled(format!("{:>10} ", label), Style::new().yellow()), Span::raw(value)]);
+    if field == self.field {
+      line.reversed()
+    } else {
+      line
+    }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database + DatabaseQueries> PopUp<DB> for FilterRows<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    self.error = None;
+    match (key.code, self.field) {
+      (KeyCode::Esc, _) => return Ok(Some(PopUpPayload::Cancel)),
+      (KeyCode::Enter, _) => {
+        match self.predicate() {
+          Ok(predicate) => {
+            let query = filtered_rows_query(&self.table, &predicate);
+            return Ok(Some(PopUpPayload::Action(Action::PreviewFiltered(query))));
+          },
+          Err(e) => self.error = Some(e),
+        }
+      },
+      (KeyCode::Tab | KeyCode::Down, _) => self.next_field(),
+      (KeyCode::BackTab | KeyCode::Up, _) => self.previous_field(),
+      (KeyCode::Right, _) => self.cycle(true),
+      (KeyCode::Left, _) => self.cycle(false),
+      (KeyCode::Backspace, Field::Value) => {
+        self.value.pop();
+      },
+      (KeyCode::Char(c), Field::Value) => self.value.push(c),
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let (name, type_name) = &self.columns[self.column];
+    let mut lines = vec![
+      self.field_line(Field::Column, "column", format!("‹ {} › ({})", name, type_name)),
+      self.field_line(Field::Operator, "operator", format!("‹ {} ›", FILTER_OPERATORS[self.operator])),
+    ];
+    if self.takes_value() {
+      lines.push(self.field_line(Field::Value, "value", format!("{}_", self.value)));
+    }
+    lines.push(Line::from(""));
+    // errors wait until the filter is applied, so they don't show up while typing
+    match (&self.error, self.predicate()) {
+      (Some(error), _) => lines.push(Line::from(error.clone()).red()),
+      (None, Ok(predicate)) => lines.push(Line::from(format!("where {}", predicate)).dim()),
+      (None, Err(_)) => {},
+    }
+    Some(PopUpBody { title: format!(" Filter {} ", self.table), lines, selected: 0 })
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[<tab>|↑|↓] field [←|→] change | type a value | [<enter>] apply | [<esc>] cancel".to_string()
+  }
+}
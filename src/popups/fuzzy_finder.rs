@@ -0,0 +1,137 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  style::{Style, Stylize},
+  text::{Line, Span},
+};
+
+use super::{PopUp, PopUpBody, PopUpPayload};
+use crate::{
+  action::{Action, MenuPreview},
+  utils::fuzzy_score,
+};
+
+const MAX_RESULTS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FinderItem {
+  History(Vec<String>),  // (query_lines)
+  Table(String, String), // (schema, table)
+}
+
+impl FinderItem {
+  fn kind(&self) -> &'static str {
+    match self {
+      FinderItem::History(_) => "history",
+      FinderItem::Table(..) => "table",
+    }
+  }
+
+  fn text(&self) -> String {
+    match self {
+      FinderItem::History(lines) => lines.iter().map(|l| l.trim()).collect::<Vec<&str>>().join(" "),
+      FinderItem::Table(schema, table) => format!("{}.{}", schema, table),
+    }
+  }
+
+  fn action(&self) -> Action {
+    match self {
+      FinderItem::History(lines) => Action::HistoryToEditor(lines.clone()),
+      FinderItem::Table(schema, table) => Action::MenuPreview(MenuPreview::Rows, schema.clone(), table.clone()),
+    }
+  }
+}
+
+// searches history and tables at once and jumps to the chosen one
+#[derive(Debug)]
+pub struct FuzzyFinder<DB: sqlx::Database> {
+  items: Vec<FinderItem>,
+  query: String,
+  selected: usize,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> FuzzyFinder<DB> {
+  pub fn new(items: Vec<FinderItem>) -> Self {
+    Self { items, query: String::new(), selected: 0, phantom: PhantomData }
+  }
+
+  fn matches(&self) -> Vec<&FinderItem> {
+    let mut scored =
+      self.items.iter().filter_map(|item| Some((fuzzy_score(&self.query, &item.text())?, item))).collect::<Vec<_>>();
+    // sort_by_key is stable, so ties keep history (most recent first) ahead of tables
+    if !self.query.trim().is_empty() {
+      scored.sort_by_key(|(score, _)| -score);
+    }
+    scored.into_iter().take(MAX_RESULTS).map(|(_, item)| item).collect()
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for FuzzyFinder<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    let match_count = self.matches().len();
+    match (key.code, key.modifiers) {
+      (KeyCode::Esc, _) => return Ok(Some(PopUpPayload::Cancel)),
+      (KeyCode::Enter, _) => {
+        return Ok(self.matches().get(self.selected).map(|item| PopUpPayload::Action(item.action())));
+      },
+      (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+        self.selected = (self.selected + 1).min(match_count.saturating_sub(1));
+      },
+      (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+        self.selected = self.selected.saturating_sub(1);
+      },
+      (KeyCode::Backspace, _) => {
+        self.query.pop();
+        self.selected = 0;
+      },
+      (KeyCode::Char(c), _) => {
+        self.query.push(c);
+        self.selected = 0;
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let mut lines =
+      vec![Line::from(vec![Span::styled("> ", Style::new().yellow()), Span::raw(format!("{}_", self.query))])];
+    lines.extend(self.matches().into_iter().enumerate().map(|(i, item)| {
+      let line =
+        Line::from(vec![Span::styled(format!("{:>8} ", item.kind()), Style::new().dim()), Span::raw(item.text())]);
+      if i == self.selected {
+        line.reversed()
+      } else {
+        line
+      }
+    }));
+    // the query line sits above the results
+    Some(PopUpBody { title: " Find ".to_owned(), lines, selected: self.selected + 1 })
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "type to search | [↑|↓] move | [<enter>] open | [<esc>] close".to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fuzzy_score() {
+    assert!(fuzzy_score("usr", "public.users").is_some());
+    assert!(fuzzy_score("xyz", "public.users").is_none());
+    assert!(fuzzy_score("users", "public.users") > fuzzy_score("users", "public.u_s_e_r_s"));
+    assert!(fuzzy_score("ord", "public.orders") > fuzzy_score("ord", "select * from word_records"));
+    assert!(fuzzy_score("", "anything").is_some());
+  }
+}
@@ -0,0 +1,122 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  style::{Style, Stylize},
+  text::{Line, Span},
+};
+
+use super::{PopUp, PopUpBody, PopUpPayload};
+
+const MAX_BUCKETS: usize = 20;
+const BAR_WIDTH: usize = 50;
+
+// (lower bound, upper bound, count) for equal-width buckets between the min and max.
+// the last bucket includes the max
+fn histogram(values: &[f64], buckets: usize) -> Vec<(f64, f64, usize)> {
+  let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+  let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+  if values.is_empty() {
+    return vec![];
+  }
+  if min == max {
+    return vec![(min, max, values.len())];
+  }
+  let width = (max - min) / buckets as f64;
+  let mut counts = vec![0; buckets];
+  for value in values {
+    let bucket = (((value - min) / width) as usize).min(buckets - 1);
+    counts[bucket] += 1;
+  }
+  counts
+    .into_iter()
+    .enumerate()
+    .map(|(i, count)| (min + width * i as f64, min + width * (i + 1) as f64, count))
+    .collect()
+}
+
+// a quick look at how the loaded values of a numeric column are distributed
+#[derive(Debug)]
+pub struct Histogram<DB: sqlx::Database> {
+  column: String,
+  values: Vec<f64>,
+  skipped: usize,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> Histogram<DB> {
+  // None when none of the values are numbers
+  pub fn new(column: String, values: &[String]) -> Option<Self> {
+    let numbers =
+      values.iter().filter_map(|v| v.trim().parse::<f64>().ok()).filter(|v| v.is_finite()).collect::<Vec<f64>>();
+    if numbers.is_empty() {
+      return None;
+    }
+    Some(Self { column, skipped: values.len() - numbers.len(), values: numbers, phantom: PhantomData })
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for Histogram<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let buckets = histogram(&self.values, MAX_BUCKETS.min((self.values.len() as f64).sqrt().ceil() as usize).max(1));
+    let largest = buckets.iter().map(|(_, _, count)| *count).max().unwrap_or(1).max(1);
+    let mean = self.values.iter().sum::<f64>() / self.values.len() as f64;
+    let (min, max) = (buckets.first().map_or(0.0, |b| b.0), buckets.last().map_or(0.0, |b| b.1));
+    let mut lines = vec![
+      Line::from(format!("{} values, min {}, max {}, mean {:.4}{}", self.values.len(), min, max, mean, match self
+        .skipped
+      {
+        0 => "".to_owned(),
+        n => format!(" ({} null or non-numeric skipped)", n),
+      }))
+      .dim(),
+      Line::from(""),
+    ];
+    let labels = buckets.iter().map(|(low, high, _)| format!("{:.4} – {:.4}", low, high)).collect::<Vec<String>>();
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    lines.extend(buckets.iter().zip(labels).map(|((_, _, count), label)| {
+      let bar = "█".repeat((count * BAR_WIDTH).div_ceil(largest));
+      Line::from(vec![
+        Span::raw(format!("{:>width$} ", label, width = label_width)),
+        Span::styled(bar, Style::new().green()),
+        Span::raw(format!(" {}", count)),
+      ])
+    }));
+    Some(PopUpBody { title: format!(" {} (loaded rows) ", self.column), lines, selected: 0 })
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[<esc>] close".to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_histogram() {
+    let values = [1.0, 2.0, 2.5, 3.0, 9.0, 10.0];
+    assert_eq!(histogram(&values, 3), vec![(1.0, 4.0, 4), (4.0, 7.0, 0), (7.0, 10.0, 2)]);
+    assert_eq!(histogram(&[5.0, 5.0], 3), vec![(5.0, 5.0, 2)]);
+    assert!(histogram(&[], 3).is_empty());
+    let column =
+      Histogram::<sqlx::Postgres>::new("n".to_owned(), &["1".to_owned(), "NULL".to_owned(), " 2.5 ".to_owned()]);
+    let column = column.unwrap();
+    assert_eq!((column.values, column.skipped), (vec![1.0, 2.5], 1));
+    assert!(Histogram::<sqlx::Postgres>::new("n".to_owned(), &["a".to_owned()]).is_none());
+  }
+}
@@ -0,0 +1,53 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+
+// asks which table pasted rows should be inserted into
+#[derive(Debug)]
+pub struct InsertRows<DB: sqlx::Database> {
+  rows: Vec<Vec<String>>,
+  table: String,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> InsertRows<DB> {
+  pub fn new(rows: Vec<Vec<String>>) -> Self {
+    Self { rows, table: String::new(), phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for InsertRows<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc => Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter if !self.table.trim().is_empty() => {
+        Ok(Some(PopUpPayload::InsertRows(self.table.trim().to_owned(), std::mem::take(&mut self.rows))))
+      },
+      KeyCode::Backspace => {
+        self.table.pop();
+        Ok(None)
+      },
+      KeyCode::Char(c) => {
+        self.table.push(c);
+        Ok(None)
+      },
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    format!("Insert {} pasted rows into table: {}_", self.rows.len(), self.table)
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "type the table name | [<enter>] generate INSERT | [<esc>] cancel".to_string()
+  }
+}
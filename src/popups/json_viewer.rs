@@ -0,0 +1,264 @@
+use std::{collections::HashSet, marker::PhantomData};
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  style::{Style, Stylize},
+  text::{Line, Span},
+};
+use serde_json::Value;
+
+use super::{PopUp, PopUpBody, PopUpPayload};
+use crate::database::parse_array_literal;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Node {
+  path: String,
+  depth: usize,
+  label: String,
+  preview: String,
+  is_container: bool,
+}
+
+// an expandable tree of a json cell, where a path or sub-value can be copied
+#[derive(Debug)]
+pub struct JsonViewer<DB: sqlx::Database> {
+  title: String,
+  value: Value,
+  expanded: HashSet<String>,
+  selected: usize,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> JsonViewer<DB> {
+  pub fn new(value: Value) -> Self {
+    Self {
+      title: " JSON ".to_owned(),
+      value,
+      expanded: HashSet::from(["$".to_owned()]),
+      selected: 0,
+      phantom: PhantomData,
+    }
+  }
+
+  // arrays and records are shown as a tree of their elements, indexed like json arrays
+  pub fn from_array_literal(literal: &str) -> Option<Self> {
+    let mut viewer = Self::new(array_tree(literal)?);
+    viewer.title = " Elements ".to_owned();
+    Some(viewer)
+  }
+
+  fn nodes(&self) -> Vec<Node> {
+    let mut nodes = vec![];
+    flatten(&self.value, "$".to_owned(), 0, "$".to_owned(), &self.expanded, &mut nodes);
+    nodes
+  }
+
+  fn selected_value(&self, path: &str) -> Option<&Value> {
+    let mut value = &self.value;
+    for segment in path_segments(path) {
+      value = match segment {
+        Segment::Key(key) => value.get(key)?,
+        Segment::Index(i) => value.get(i)?,
+      };
+    }
+    Some(value)
+  }
+}
+
+fn array_tree(literal: &str) -> Option<Value> {
+  let elements = parse_array_literal(literal)?;
+  Some(Value::Array(
+    elements
+      .into_iter()
+      .map(|element| {
+        // only nested arrays are expanded, since a string in parens isn't necessarily a record
+        match element.starts_with('{') {
+          true => array_tree(&element).unwrap_or(Value::String(element)),
+          false => Value::String(element),
+        }
+      })
+      .collect(),
+  ))
+}
+
+enum Segment {
+  Key(String),
+  Index(usize),
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+  if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+    format!("{}.{}", parent, key)
+  } else {
+    format!("{}[{}]", parent, serde_json::to_string(key).unwrap_or_default())
+  }
+}
+
+// the inverse of child_path
+fn path_segments(path: &str) -> Vec<Segment> {
+  let mut segments = vec![];
+  let mut rest = path.strip_prefix('$').unwrap_or(path);
+  while !rest.is_empty() {
+    if let Some(after_dot) = rest.strip_prefix('.') {
+      let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+      segments.push(Segment::Key(after_dot[..end].to_owned()));
+      rest = &after_dot[end..];
+    } else if rest.starts_with("[\"") {
+      let mut stream = serde_json::Deserializer::from_str(&rest[1..]).into_iter::<String>();
+      let Some(Ok(key)) = stream.next() else {
+        break;
+      };
+      rest = &rest[1 + stream.byte_offset() + 1..];
+      segments.push(Segment::Key(key));
+    } else if let Some(after_bracket) = rest.strip_prefix('[') {
+      let end = after_bracket.find(']').unwrap_or(after_bracket.len());
+      let Ok(i) = after_bracket[..end].parse::<usize>() else {
+        break;
+      };
+      segments.push(Segment::Index(i));
+      rest = after_bracket.get(end + 1..).unwrap_or_default();
+    } else {
+      break;
+    }
+  }
+  segments
+}
+
+fn flatten(
+  value: &Value,
+  path: String,
+  depth: usize,
+  label: String,
+  expanded: &HashSet<String>,
+  nodes: &mut Vec<Node>,
+) {
+  let (preview, children): (String, Vec<(String, String, &Value)>) = match value {
+    Value::Object(map) => {
+      (format!("{{{} keys}}", map.len()), map.iter().map(|(k, v)| (child_path(&path, k), k.clone(), v)).collect())
+    },
+    Value::Array(items) => {
+      (
+        format!("[{} items]", items.len()),
+        items.iter().enumerate().map(|(i, v)| (format!("{}[{}]", path, i), i.to_string(), v)).collect(),
+      )
+    },
+    leaf => (leaf.to_string(), vec![]),
+  };
+  let is_container = value.is_object() || value.is_array();
+  let is_expanded = expanded.contains(&path);
+  nodes.push(Node { path, depth, label, preview, is_container });
+  if is_expanded {
+    for (child_path, child_label, child) in children {
+      flatten(child, child_path, depth + 1, child_label, expanded, nodes);
+    }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for JsonViewer<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    let nodes = self.nodes();
+    let Some(node) = nodes.get(self.selected).cloned() else {
+      return Ok(Some(PopUpPayload::Cancel));
+    };
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Char('j') | KeyCode::Down => self.selected = (self.selected + 1).min(nodes.len().saturating_sub(1)),
+      KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+      KeyCode::Char('g') => self.selected = 0,
+      KeyCode::Char('G') => self.selected = nodes.len().saturating_sub(1),
+      KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter if node.is_container => {
+        if !self.expanded.remove(&node.path) {
+          self.expanded.insert(node.path);
+        }
+      },
+      KeyCode::Char('h') | KeyCode::Left => {
+        if node.is_container && self.expanded.remove(&node.path) {
+          return Ok(None);
+        }
+        // jump to the parent, which is the closest node above with less depth
+        if let Some(parent) = nodes[..self.selected].iter().rposition(|n| n.depth < node.depth) {
+          self.selected = parent;
+        }
+      },
+      KeyCode::Char('p') => return Ok(Some(PopUpPayload::CopyData(node.path))),
+      KeyCode::Char('y') => {
+        if let Some(value) = self.selected_value(&node.path) {
+          let copied = match value {
+            Value::String(s) => s.clone(),
+            value => serde_json::to_string_pretty(value).unwrap_or_default(),
+          };
+          return Ok(Some(PopUpPayload::CopyData(copied)));
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let lines = self
+      .nodes()
+      .into_iter()
+      .enumerate()
+      .map(|(i, node)| {
+        let marker = match (node.is_container, self.expanded.contains(&node.path)) {
+          (true, true) => "▾ ",
+          (true, false) => "▸ ",
+          _ => "  ",
+        };
+        let line = Line::from(vec![
+          Span::raw("  ".repeat(node.depth)),
+          Span::raw(marker),
+          Span::styled(node.label, Style::new().yellow()),
+          Span::raw(": "),
+          Span::styled(node.preview, if node.is_container { Style::new().dim() } else { Style::new() }),
+        ]);
+        if i == self.selected {
+          line.reversed()
+        } else {
+          line
+        }
+      })
+      .collect();
+    Some(PopUpBody { title: self.title.clone(), lines, selected: self.selected })
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[j|k] move [l|<enter>] expand [h] collapse [p] copy path [y] copy value [<esc>] close".to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn test_json_viewer_paths() {
+    let mut viewer = JsonViewer::<sqlx::Postgres>::new(json!({"a": [1, {"b c": true}], "d": null}));
+    let paths = |viewer: &JsonViewer<sqlx::Postgres>| viewer.nodes().into_iter().map(|n| n.path).collect::<Vec<_>>();
+    assert_eq!(paths(&viewer), vec!["$", "$.a", "$.d"]);
+    viewer.expanded.insert("$.a".to_owned());
+    viewer.expanded.insert("$.a[1]".to_owned());
+    assert_eq!(paths(&viewer), vec!["$", "$.a", "$.a[0]", "$.a[1]", "$.a[1][\"b c\"]", "$.d"]);
+    assert_eq!(viewer.selected_value("$.a[1][\"b c\"]"), Some(&json!(true)));
+    assert_eq!(viewer.selected_value("$.a[0]"), Some(&json!(1)));
+    assert_eq!(viewer.selected_value("$.missing"), None);
+  }
+
+  #[test]
+  fn test_array_tree() {
+    assert_eq!(array_tree("{ 1, 2, 3 }"), Some(json!(["1", "2", "3"])));
+    assert_eq!(array_tree("{{1,2},{3,4}}"), Some(json!([["1", "2"], ["3", "4"]])));
+    assert_eq!(array_tree("(1,\"a, b\",)"), Some(json!(["1", "a, b", ""])));
+    assert_eq!(array_tree("{}"), Some(json!([])));
+    assert_eq!(array_tree("plain text"), None);
+  }
+}
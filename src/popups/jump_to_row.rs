@@ -0,0 +1,72 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+
+// scrolls the results straight to a row, counting from 1 like the row numbers do
+#[derive(Debug)]
+pub struct JumpToRow<DB: sqlx::Database> {
+  row_count: usize,
+  input: String,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> JumpToRow<DB> {
+  pub fn new(row_count: usize) -> Self {
+    Self { row_count, input: String::new(), phantom: PhantomData }
+  }
+
+  // the 0-based index of the typed row, if there is such a row
+  fn target_row(&self) -> Option<usize> {
+    self.input.parse::<usize>().ok().filter(|row| (1..=self.row_count).contains(row)).map(|row| row - 1)
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for JumpToRow<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter => return Ok(self.target_row().map(PopUpPayload::JumpToRow)),
+      KeyCode::Backspace => {
+        self.input.pop();
+      },
+      KeyCode::Char(c) if c.is_ascii_digit() => self.input.push(c),
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    format!("Jump to row (1-{}): {}_", self.row_count, self.input)
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "type a row number | [<enter>] jump | [<esc>] cancel".to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_jump_to_row() {
+    let mut jump = JumpToRow::<sqlx::Postgres>::new(250);
+    assert_eq!(jump.target_row(), None);
+    jump.input = "1".to_owned();
+    assert_eq!(jump.target_row(), Some(0));
+    jump.input = "250".to_owned();
+    assert_eq!(jump.target_row(), Some(249));
+    jump.input = "251".to_owned();
+    assert_eq!(jump.target_row(), None);
+    jump.input = "0".to_owned();
+    assert_eq!(jump.target_row(), None);
+  }
+}
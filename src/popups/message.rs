@@ -0,0 +1,41 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::{PopUp, PopUpPayload};
+
+// shows some text until it is dismissed
+#[derive(Debug)]
+pub struct Message<DB: sqlx::Database> {
+  text: String,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> Message<DB> {
+  pub fn new(text: String) -> Self {
+    Self { text, phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for Message<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc | KeyCode::Enter => Ok(Some(PopUpPayload::Cancel)),
+      _ => Ok(None),
+    }
+  }
+
+  fn get_cta_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    self.text.clone()
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[<esc>] close".to_string()
+  }
+}
@@ -0,0 +1,142 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  style::{Style, Stylize},
+  text::{Line, Span},
+};
+
+use super::{PopUp, PopUpBody, PopUpPayload};
+use crate::{
+  action::{Action, MenuPreview},
+  database::DatabaseQueries,
+};
+
+// searches the whole database for tables, views, columns, and functions by name.
+// results are only refreshed on enter, since each search is a query
+#[derive(Debug)]
+pub struct ObjectSearch<DB: sqlx::Database> {
+  search: String,
+  searched: String,
+  results: Vec<Vec<String>>, // (schema, table, object_type, column)
+  selected: usize,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database + DatabaseQueries> ObjectSearch<DB> {
+  pub fn new(search: String, results: Vec<Vec<String>>) -> Self {
+    Self { searched: search.clone(), search, results, selected: 0, phantom: PhantomData }
+  }
+
+  fn field(result: &[String], i: usize) -> String {
+    result.get(i).cloned().unwrap_or_default()
+  }
+
+  // the schema is left out when the driver has none, as with a lone sqlite database
+  fn qualified_name(result: &[String]) -> String {
+    [Self::field(result, 0), Self::field(result, 1), Self::field(result, 3)]
+      .iter()
+      .filter(|part| !part.is_empty())
+      .map(|part| DB::quote_identifier(part))
+      .collect::<Vec<String>>()
+      .join(".")
+  }
+
+  // columns open their table, and functions open their definition
+  fn preview_action(result: &[String]) -> Action {
+    let preview = if Self::field(result, 2) == "function" { MenuPreview::Definition } else { MenuPreview::Rows };
+    Action::MenuPreview(preview, Self::field(result, 0), Self::field(result, 1))
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database + DatabaseQueries> PopUp<DB> for ObjectSearch<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match (key.code, key.modifiers) {
+      (KeyCode::Esc, _) => return Ok(Some(PopUpPayload::Cancel)),
+      (KeyCode::Enter, _) if self.search != self.searched || self.results.is_empty() => {
+        if !self.search.trim().is_empty() {
+          return Ok(Some(PopUpPayload::Action(Action::SearchObjects(self.search.clone()))));
+        }
+      },
+      (KeyCode::Enter, _) => {
+        return Ok(self.results.get(self.selected).map(|result| PopUpPayload::Action(Self::preview_action(result))));
+      },
+      (KeyCode::Tab, _) => {
+        return Ok(
+          self
+            .results
+            .get(self.selected)
+            .map(|result| PopUpPayload::Action(Action::InsertIntoEditor(Self::qualified_name(result)))),
+        );
+      },
+      (KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
+        self.selected = (self.selected + 1).min(self.results.len().saturating_sub(1));
+      },
+      (KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => {
+        self.selected = self.selected.saturating_sub(1);
+      },
+      (KeyCode::Backspace, _) => {
+        self.search.pop();
+      },
+      (KeyCode::Char(c), _) => self.search.push(c),
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let mut lines =
+      vec![Line::from(vec![Span::styled("> ", Style::new().yellow()), Span::raw(format!("{}_", self.search))])];
+    if self.results.is_empty() && !self.searched.is_empty() {
+      lines.push(Line::from(format!("nothing matches '{}'", self.searched)).dim());
+    }
+    lines.extend(self.results.iter().enumerate().map(|(i, result)| {
+      let name = [Self::field(result, 0), Self::field(result, 1), Self::field(result, 3)]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<String>>()
+        .join(".");
+      let line =
+        Line::from(vec![Span::styled(format!("{:>17} ", Self::field(result, 2)), Style::new().dim()), Span::raw(name)]);
+      if i == self.selected {
+        line.reversed()
+      } else {
+        line
+      }
+    }));
+    // the search line sits above the results
+    Some(PopUpBody { title: " Search Database ".to_owned(), lines, selected: self.selected + 1 })
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "type to edit | [<enter>] search, then open | [<tab>] insert name | [↑|↓] move | [<esc>] close".to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_object_search() {
+    let column = ["public", "users", "column", "email"].iter().map(|s| s.to_string()).collect::<Vec<String>>();
+    let function = ["public", "touch", "function", ""].iter().map(|s| s.to_string()).collect::<Vec<String>>();
+    let sqlite_table = ["", "users", "table", ""].iter().map(|s| s.to_string()).collect::<Vec<String>>();
+    assert_eq!(ObjectSearch::<sqlx::Postgres>::qualified_name(&column), "\"public\".\"users\".\"email\"");
+    assert_eq!(ObjectSearch::<sqlx::Sqlite>::qualified_name(&sqlite_table), "\"users\"");
+    assert_eq!(
+      ObjectSearch::<sqlx::Postgres>::preview_action(&column),
+      Action::MenuPreview(MenuPreview::Rows, "public".to_owned(), "users".to_owned())
+    );
+    assert_eq!(
+      ObjectSearch::<sqlx::Postgres>::preview_action(&function),
+      Action::MenuPreview(MenuPreview::Definition, "public".to_owned(), "touch".to_owned())
+    );
+  }
+}
@@ -0,0 +1,82 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  style::{Style, Stylize},
+  text::{Line, Span},
+};
+
+use super::{PopUp, PopUpBody, PopUpPayload};
+use crate::action::Action;
+
+// edits the current connection's parameters before reconnecting with them
+#[derive(Debug)]
+pub struct Reconnect<DB: sqlx::Database> {
+  params: Vec<(String, String)>, // (name, value)
+  selected: usize,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> Reconnect<DB> {
+  pub fn new(params: Vec<(String, String)>) -> Self {
+    Self { params, selected: 0, phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for Reconnect<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    let count = self.params.len().max(1);
+    match key.code {
+      KeyCode::Esc => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter => return Ok(Some(PopUpPayload::Action(Action::Reconnect(self.params.clone())))),
+      KeyCode::Tab | KeyCode::Down => self.selected = (self.selected + 1) % count,
+      KeyCode::BackTab | KeyCode::Up => self.selected = (self.selected + count - 1) % count,
+      KeyCode::Backspace => {
+        if let Some((_, value)) = self.params.get_mut(self.selected) {
+          value.pop();
+        }
+      },
+      KeyCode::Char(c) => {
+        if let Some((_, value)) = self.params.get_mut(self.selected) {
+          value.push(c);
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let mut lines = self
+      .params
+      .iter()
+      .enumerate()
+      .map(|(i, (name, value))| {
+        let line = Line::from(vec![
+          Span::styled(format!("{:>10} ", name), Style::new().yellow()),
+          Span::raw(if i == self.selected { format!("{}_", value) } else { value.clone() }),
+        ]);
+        if i == self.selected {
+          line.reversed()
+        } else {
+          line
+        }
+      })
+      .collect::<Vec<Line>>();
+    // the password can't be edited here, and is never shown
+    if self.params.iter().any(|(name, _)| name == "user") {
+      lines.push(Line::from(format!("{:>10} ******** (unchanged)", "password")).dim());
+    }
+    Some(PopUpBody { title: " Reconnect ".to_owned(), lines, selected: self.selected })
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[<tab>|↑|↓] field | type to edit | [<enter>] reconnect | [<esc>] cancel".to_string()
+  }
+}
@@ -0,0 +1,77 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+  style::{Style, Stylize},
+  text::{Line, Span},
+};
+
+use super::{PopUp, PopUpBody, PopUpPayload};
+use crate::action::Action;
+
+// lists the editor's named registers, and pastes the chosen one
+#[derive(Debug)]
+pub struct RegisterViewer<DB: sqlx::Database> {
+  registers: Vec<(char, String)>, // (name, text)
+  selected: usize,
+  phantom: PhantomData<DB>,
+}
+
+impl<DB: sqlx::Database> RegisterViewer<DB> {
+  pub fn new(registers: Vec<(char, String)>) -> Self {
+    Self { registers, selected: 0, phantom: PhantomData }
+  }
+}
+
+#[async_trait(?Send)]
+impl<DB: sqlx::Database> PopUp<DB> for RegisterViewer<DB> {
+  async fn handle_key_events(
+    &mut self,
+    key: crossterm::event::KeyEvent,
+    app_state: &mut crate::app::AppState<'_, DB>,
+  ) -> color_eyre::eyre::Result<Option<PopUpPayload>> {
+    match key.code {
+      KeyCode::Esc => return Ok(Some(PopUpPayload::Cancel)),
+      KeyCode::Enter => {
+        return Ok(
+          self
+            .registers
+            .get(self.selected)
+            .map(|(_, text)| PopUpPayload::Action(Action::InsertIntoEditor(text.clone()))),
+        );
+      },
+      KeyCode::Char('j') | KeyCode::Down => {
+        self.selected = (self.selected + 1).min(self.registers.len().saturating_sub(1));
+      },
+      KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn get_body(&self, app_state: &crate::app::AppState<'_, DB>) -> Option<PopUpBody> {
+    let lines = self
+      .registers
+      .iter()
+      .enumerate()
+      .map(|(i, (name, text))| {
+        // multi-line yanks are shown on one line, the way vim's :registers does
+        let line = Line::from(vec![
+          Span::styled(format!("\"{} ", name), Style::new().yellow()),
+          Span::raw(text.replace('\n', "^J")),
+        ]);
+        if i == self.selected {
+          line.reversed()
+        } else {
+          line
+        }
+      })
+      .collect();
+    Some(PopUpBody { title: " Registers ".to_owned(), lines, selected: self.selected })
+  }
+
+  fn get_actions_text(&self, app_state: &crate::app::AppState<'_, DB>) -> String {
+    "[j|k] select | [<enter>] paste | [<esc>] close".to_string()
+  }
+}
@@ -1,4 +1,5 @@
 use std::{
+  io::Write,
   ops::{Deref, DerefMut},
   time::Duration,
 };
@@ -7,10 +8,10 @@ use color_eyre::eyre::Result;
 use crossterm::{
   cursor,
   event::{
-    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event as CrosstermEvent,
-    KeyEvent, KeyEventKind, MouseEvent,
+    DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste, EnableFocusChange,
+    EnableMouseCapture, Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent,
   },
-  terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+  terminal::{EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
 };
 use futures::{FutureExt, StreamExt};
 use ratatui::backend::CrosstermBackend as Backend;
@@ -53,6 +54,8 @@ pub struct Tui {
   pub tick_rate: f64,
   pub mouse: bool,
   pub paste: bool,
+  pub focus_change: bool,
+  pub title: Option<String>,
 }
 
 impl Tui {
@@ -65,26 +68,45 @@ impl Tui {
     let task = tokio::spawn(async {});
     let mouse = true;
     let paste = true;
-    Ok(Self { terminal, task, cancellation_token, event_rx, event_tx, frame_rate, tick_rate, mouse, paste })
+    let focus_change = false;
+    let title = None;
+    Ok(Self {
+      terminal,
+      task,
+      cancellation_token,
+      event_rx,
+      event_tx,
+      frame_rate,
+      tick_rate,
+      mouse,
+      paste,
+      focus_change,
+      title,
+    })
   }
 
   pub fn tick_rate(mut self, tick_rate: Option<f64>) -> Self {
-    if tick_rate.is_some() {
-      self.tick_rate = tick_rate.unwrap()
+    if let Some(tick_rate) = tick_rate {
+      self.tick_rate = tick_rate
     };
     self
   }
 
   pub fn frame_rate(mut self, frame_rate: Option<f64>) -> Self {
-    if frame_rate.is_some() {
-      self.frame_rate = frame_rate.unwrap();
+    if let Some(frame_rate) = frame_rate {
+      self.frame_rate = frame_rate;
     }
     self
   }
 
+  pub fn title(mut self, title: Option<String>) -> Self {
+    self.title = title;
+    self
+  }
+
   pub fn mouse(mut self, mouse: Option<bool>) -> Self {
-    if mouse.is_some() {
-      self.mouse = mouse.unwrap();
+    if let Some(mouse) = mouse {
+      self.mouse = mouse;
     }
     self
   }
@@ -94,6 +116,12 @@ impl Tui {
     self
   }
 
+  // reports when the terminal gains or loses focus, for terminals that support it
+  pub fn focus_change(mut self, focus_change: bool) -> Self {
+    self.focus_change = focus_change;
+    self
+  }
+
   pub fn start(&mut self) {
     let tick_delay = std::time::Duration::from_secs_f64(1.0 / self.tick_rate);
     let render_delay = std::time::Duration::from_secs_f64(1.0 / self.frame_rate);
@@ -136,8 +164,9 @@ impl Tui {
                   CrosstermEvent::FocusGained => {
                     _event_tx.send(Event::FocusGained).unwrap();
                   },
+                  // some terminals paste line breaks as carriage returns
                   CrosstermEvent::Paste(s) => {
-                    _event_tx.send(Event::Paste(s)).unwrap();
+                    _event_tx.send(Event::Paste(s.replace("\r\n", "\n").replace('\r', "\n"))).unwrap();
                   },
                 }
               }
@@ -187,6 +216,18 @@ impl Tui {
     if self.paste {
       crossterm::execute!(io(), EnableBracketedPaste)?;
     }
+    if self.focus_change {
+      crossterm::execute!(io(), EnableFocusChange)?;
+    }
+    if let Some(title) = &self.title {
+      // save the current title so it can be restored on exit (xterm's title stack)
+      write!(io(), "\x1b[22;0t")?;
+      crossterm::execute!(io(), SetTitle(title))?;
+      if let Some(cwd) = current_dir_osc7() {
+        write!(io(), "{}", cwd)?;
+        io().flush()?;
+      }
+    }
     self.start();
     Ok(())
   }
@@ -201,6 +242,12 @@ impl Tui {
       if self.mouse {
         crossterm::execute!(io(), DisableMouseCapture)?;
       }
+      if self.focus_change {
+        crossterm::execute!(io(), DisableFocusChange)?;
+      }
+      if self.title.is_some() {
+        write!(io(), "\x1b[23;0t")?;
+      }
       crossterm::execute!(io(), LeaveAlternateScreen, cursor::Show)?;
       crossterm::terminal::disable_raw_mode()?;
     }
@@ -240,3 +287,11 @@ impl Drop for Tui {
     self.exit().unwrap();
   }
 }
+
+// OSC 7 reports the working directory, so new tmux panes and terminal tabs open in the same place
+fn current_dir_osc7() -> Option<String> {
+  let cwd = std::env::current_dir().ok()?;
+  let host = std::env::var("HOSTNAME").unwrap_or_default();
+  let path = cwd.to_str()?.replace('%', "%25").replace(' ', "%20");
+  Some(format!("\x1b]7;file://{}{}\x1b\\", host, path))
+}
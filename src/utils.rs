@@ -1,5 +1,6 @@
-use std::path::PathBuf;
+use std::{io::Write, path::PathBuf};
 
+use base64::Engine;
 use color_eyre::eyre::Result;
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
@@ -20,6 +21,89 @@ lazy_static! {
   pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
 
+// the escape sequence that asks the terminal itself to put the text on the clipboard.
+// tmux only forwards it to the outer terminal when it is wrapped in a passthrough
+pub fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+  tmux_passthrough(format!("\x1b]52;c;{}\x07", base64::engine::general_purpose::STANDARD.encode(text)), in_tmux)
+}
+
+// osc 9 asks the terminal for a desktop notification; terminals without it ignore the sequence
+pub fn osc9_sequence(message: &str, in_tmux: bool) -> String {
+  let message = message.chars().filter(|c| !c.is_control()).collect::<String>();
+  tmux_passthrough(format!("\x1b]9;{}\x07", message), in_tmux)
+}
+
+fn tmux_passthrough(sequence: String, in_tmux: bool) -> String {
+  if in_tmux {
+    format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+  } else {
+    sequence
+  }
+}
+
+// works over ssh and without x11 or wayland, as long as the terminal supports osc 52
+pub fn copy_with_osc52(text: &str) -> Result<()> {
+  let mut stdout = std::io::stdout();
+  stdout.write_all(osc52_sequence(text, std::env::var("TMUX").is_ok()).as_bytes())?;
+  stdout.flush()?;
+  Ok(())
+}
+
+// rings the terminal's bell and/or shows a desktop notification
+pub fn notify(message: &str, bell: bool, desktop: bool) -> Result<()> {
+  let mut stdout = std::io::stdout();
+  if bell {
+    stdout.write_all(b"\x07")?;
+  }
+  if desktop {
+    stdout.write_all(osc9_sequence(message, std::env::var("TMUX").is_ok()).as_bytes())?;
+  }
+  stdout.flush()?;
+  Ok(())
+}
+
+// writes the text to a temporary file, opens it in $VISUAL or $EDITOR (falling
+// back to vi), and returns the file's contents once the editor exits. the
+// caller is responsible for suspending the tui first.
+pub fn edit_in_external_editor(text: &str) -> Result<String> {
+  let editor = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")).unwrap_or_else(|_| "vi".to_owned());
+  let mut command = editor.split_whitespace();
+  let program = command.next().ok_or_else(|| color_eyre::eyre::eyre!("$EDITOR is empty"))?;
+  let path = std::env::temp_dir().join(format!("{}-{}.sql", env!("CARGO_PKG_NAME"), std::process::id()));
+  std::fs::write(&path, text)?;
+  let status = std::process::Command::new(program).args(command).arg(&path).status();
+  let edited = std::fs::read_to_string(&path);
+  std::fs::remove_file(&path)?;
+  if !status?.success() {
+    return Err(color_eyre::eyre::eyre!("{} exited with an error, keeping the original query", editor));
+  }
+  Ok(edited?)
+}
+
+// writes the text to the stdin of the pager setting's command, or $PAGER (falling back to
+// less -S), and waits for it to exit. the caller is responsible for suspending the tui first.
+pub fn pipe_to_pager(text: &str, pager: Option<&str>) -> Result<()> {
+  let pager = pager
+    .map(|pager| pager.to_owned())
+    .or_else(|| std::env::var("PAGER").ok())
+    .filter(|pager| !pager.trim().is_empty())
+    .unwrap_or_else(|| "less -S".to_owned());
+  let mut command = pager.split_whitespace();
+  let program = command.next().ok_or_else(|| color_eyre::eyre::eyre!("the pager is empty"))?;
+  let mut child = std::process::Command::new(program).args(command).stdin(std::process::Stdio::piped()).spawn()?;
+  if let Some(mut stdin) = child.stdin.take() {
+    // the pager can quit before reading everything
+    match stdin.write_all(text.as_bytes()) {
+      Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+      _ => {},
+    }
+  }
+  if !child.wait()?.success() {
+    return Err(color_eyre::eyre::eyre!("{} exited with an error", pager));
+  }
+  Ok(())
+}
+
 fn project_directory() -> Option<ProjectDirs> {
   ProjectDirs::from("dev", "rainfrog", env!("CARGO_PKG_NAME"))
 }
@@ -139,6 +223,33 @@ macro_rules! trace_dbg {
   };
 }
 
+// scores how well the query matches as a case-insensitive subsequence of the text, favoring
+// runs of consecutive characters and matches at word starts. also returns the char positions matched
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+  let text = text.to_lowercase().chars().collect::<Vec<char>>();
+  let mut score = 0;
+  let mut position = 0;
+  let mut matched = vec![];
+  for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+    let found = position + text[position..].iter().position(|c| *c == q)?;
+    score += 1;
+    if matched.last().is_some_and(|p| p + 1 == found) {
+      score += 5;
+    }
+    if found == 0 || !text[found - 1].is_alphanumeric() {
+      score += 3;
+    }
+    matched.push(found);
+    position = found + 1;
+  }
+  // among equal matches, prefer shorter text
+  Some((score * 1000 - text.len() as i64, matched))
+}
+
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+  fuzzy_match(query, text).map(|(score, _)| score)
+}
+
 pub fn version() -> String {
   let author = clap::crate_authors!();
 
@@ -156,3 +267,29 @@ Config directory: {config_dir_path}
 Data directory: {data_dir_path}"
   )
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_osc52_sequence() {
+    assert_eq!(osc52_sequence("select 1", false), "\x1b]52;c;c2VsZWN0IDE=\x07");
+    assert_eq!(osc52_sequence("", true), "\x1bPtmux;\x1b\x1b]52;c;\x07\x1b\\");
+  }
+
+  #[test]
+  fn test_fuzzy_match() {
+    assert_eq!(fuzzy_match("usrprof", "user_profiles").map(|(_, matched)| matched), Some(vec![0, 1, 3, 5, 6, 7, 8]));
+    assert_eq!(fuzzy_match("", "users").map(|(_, matched)| matched), Some(vec![]));
+    assert!(fuzzy_match("prof", "users").is_none());
+    assert!(fuzzy_score("usr", "users") > fuzzy_score("usr", "user_sessions_archive"));
+  }
+
+  #[test]
+  fn test_osc9_sequence() {
+    assert_eq!(osc9_sequence("query finished", false), "\x1b]9;query finished\x07");
+    assert_eq!(osc9_sequence("a\x07b\n", false), "\x1b]9;ab\x07");
+    assert_eq!(osc9_sequence("done", true), "\x1bPtmux;\x1b\x1b]9;done\x07\x1b\\");
+  }
+}
@@ -1,6 +1,6 @@
 // vim emulation for tui_textarea. based on:
 // https://github.com/rhysd/tui-textarea/blob/main/examples/vim.rs
-use std::{env, fmt, fs, io, io::BufRead};
+use std::{collections::BTreeMap, env, fmt, fs, io, io::BufRead};
 
 #[cfg(not(feature = "termux"))]
 use arboard::Clipboard;
@@ -89,23 +89,47 @@ pub enum Transition {
   Nop,
   Mode(Mode),
   Pending(Input),
+  Register(char), // "a through "z selects a register for the next yank or paste
 }
 
+// Named registers, keyed by their lowercase letter
+pub type Registers = BTreeMap<char, String>;
+
 // State of Vim emulation
 #[derive(Default, Clone)]
 pub struct Vim {
   pub mode: Mode,
-  pub pending: Input, // Pending input to handle a sequence with two keys like gg
+  pub pending: Input,         // Pending input to handle a sequence with two keys like gg
+  pub register: Option<char>, // Register selected with " for the next yank or paste
   command_tx: Option<UnboundedSender<Action>>,
 }
 
 impl Vim {
   pub fn new(mode: Mode) -> Self {
-    Self { mode, pending: Input::default(), command_tx: None }
+    Self { mode, pending: Input::default(), register: None, command_tx: None }
   }
 
   pub fn with_pending(self, pending: Input) -> Self {
-    Self { mode: self.mode, pending, command_tx: None }
+    Self { mode: self.mode, pending, register: self.register, command_tx: None }
+  }
+
+  pub fn with_register(self, register: Option<char>) -> Self {
+    Self { register, ..self }
+  }
+
+  // the state after a transition. a selected register carries over into the operator
+  // or visual mode that uses it, and is dropped once a command has used it
+  pub fn after(self, transition: Transition) -> Self {
+    match transition {
+      Transition::Mode(mode) if self.mode != mode => {
+        let register = if matches!(mode, Mode::Operator(_) | Mode::Visual) { self.register } else { None };
+        Vim::new(mode).with_register(register)
+      },
+      Transition::Mode(_) => self.with_register(None),
+      Transition::Nop => self,
+      Transition::Pending(input) => self.with_pending(input),
+      Transition::Register(register) => Self { pending: Input::default(), ..self.with_register(Some(register)) },
+    }
   }
 
   pub fn register_action_handler(&mut self, tx: Option<UnboundedSender<Action>>) -> Result<()> {
@@ -113,7 +137,7 @@ impl Vim {
     Ok(())
   }
 
-  pub fn transition(&self, input: Input, textarea: &mut TextArea<'_>) -> Transition {
+  pub fn transition(&self, input: Input, textarea: &mut TextArea<'_>, registers: &mut Registers) -> Transition {
     if input.key == Key::Null {
       return Transition::Nop;
     }
@@ -121,6 +145,12 @@ impl Vim {
     match self.mode {
       Mode::Normal | Mode::Visual | Mode::Operator(_) => {
         match input {
+          Input { key: Key::Char(register @ ('a'..='z' | 'A'..='Z')), ctrl: false, .. }
+            if !matches!(self.mode, Mode::Operator(_))
+              && matches!(self.pending, Input { key: Key::Char('"'), ctrl: false, .. }) =>
+          {
+            return Transition::Register(register);
+          },
           Input { key: Key::Char('h'), .. } | Input { key: Key::Left, .. } => textarea.move_cursor(CursorMove::Back),
           Input { key: Key::Char('j'), .. } | Input { key: Key::Down, .. } => textarea.move_cursor(CursorMove::Down),
           Input { key: Key::Char('k'), .. } | Input { key: Key::Up, .. } => textarea.move_cursor(CursorMove::Up),
@@ -145,6 +175,11 @@ impl Vim {
             textarea.cancel_selection();
             return Transition::Mode(Mode::Insert);
           },
+          Input { key: Key::Char('p'), .. } if self.register.is_some() => {
+            let register = self.register.unwrap_or_default().to_ascii_lowercase();
+            textarea.insert_str(registers.get(&register).cloned().unwrap_or_default());
+            return Transition::Mode(Mode::Normal);
+          },
           Input { key: Key::Char('p'), .. } => {
             #[cfg(not(feature = "termux"))]
             {
@@ -182,8 +217,7 @@ impl Vim {
                 },
               }
             }
-            textarea.cut();
-            self.send_copy_action_with_text(textarea.yank_text());
+            self.yank(textarea, registers, true, true);
             return Transition::Mode(Mode::Normal);
           },
           Input { key: Key::Char('X'), .. } => {
@@ -195,8 +229,7 @@ impl Vim {
               textarea.start_selection();
               textarea.move_cursor(CursorMove::Back);
             }
-            textarea.cut();
-            self.send_copy_action_with_text(textarea.yank_text());
+            self.yank(textarea, registers, true, true);
             return Transition::Mode(Mode::Normal);
           },
           Input { key: Key::Char('i'), .. } => {
@@ -296,8 +329,7 @@ impl Vim {
                 },
               }
             }
-            textarea.copy();
-            self.send_copy_action_with_text(textarea.yank_text());
+            self.yank(textarea, registers, false, true);
             return Transition::Mode(Mode::Normal);
           },
           Input { key: Key::Char('d'), ctrl: false, .. } if self.mode == Mode::Visual => {
@@ -310,7 +342,7 @@ impl Vim {
                 },
               }
             }
-            textarea.cut();
+            self.yank(textarea, registers, true, false);
             return Transition::Mode(Mode::Normal);
           },
           Input { key: Key::Char('c'), ctrl: false, .. } if self.mode == Mode::Visual => {
@@ -323,16 +355,14 @@ impl Vim {
                 },
               }
             }
-            textarea.cut();
-            self.send_copy_action_with_text(textarea.yank_text());
+            self.yank(textarea, registers, true, true);
             return Transition::Mode(Mode::Insert);
           },
           Input { key: Key::Char('S'), ctrl: false, .. } => {
             textarea.move_cursor(CursorMove::Head);
             textarea.start_selection();
             textarea.move_cursor(CursorMove::End);
-            textarea.cut();
-            self.send_copy_action_with_text(textarea.yank_text());
+            self.yank(textarea, registers, true, true);
             return Transition::Mode(Mode::Insert);
           },
           Input { key: Key::Esc, .. } => {
@@ -345,17 +375,15 @@ impl Vim {
         // Handle the pending operator
         match self.mode {
           Mode::Operator('y') => {
-            textarea.copy();
-            self.send_copy_action_with_text(textarea.yank_text());
+            self.yank(textarea, registers, false, true);
             Transition::Mode(Mode::Normal)
           },
           Mode::Operator('d') => {
-            textarea.cut();
+            self.yank(textarea, registers, true, false);
             Transition::Mode(Mode::Normal)
           },
           Mode::Operator('c') => {
-            textarea.cut();
-            self.send_copy_action_with_text(textarea.yank_text());
+            self.yank(textarea, registers, true, true);
             Transition::Mode(Mode::Insert)
           },
           _ => Transition::Nop,
@@ -383,9 +411,62 @@ impl Vim {
     }
   }
 
+  // copies or cuts the selection. with a register selected, the text goes into it instead, and the
+  // yank buffer and clipboard are left as they were. uppercase registers append to their lowercase one
+  fn yank(&self, textarea: &mut TextArea<'_>, registers: &mut Registers, cut: bool, to_clipboard: bool) {
+    let previous = textarea.yank_text();
+    if cut {
+      textarea.cut();
+    } else {
+      textarea.copy();
+    }
+    match self.register {
+      Some(register) => {
+        let text = textarea.yank_text();
+        textarea.set_yank_text(previous);
+        let entry = registers.entry(register.to_ascii_lowercase()).or_default();
+        if register.is_ascii_uppercase() {
+          entry.push_str(&text);
+        } else {
+          *entry = text;
+        }
+      },
+      None if to_clipboard => self.send_copy_action_with_text(textarea.yank_text()),
+      None => {},
+    }
+  }
+
   fn send_copy_action_with_text(&self, text: String) {
     if let Some(sender) = &self.command_tx {
       sender.send(Action::CopyData(text)).map_or_else(|e| log::error!("{e:?}"), |_| {});
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn press(vim: Vim, keys: &str, textarea: &mut TextArea<'_>, registers: &mut Registers) -> Vim {
+    keys.chars().fold(vim, |vim, c| {
+      let transition = vim.transition(Input { key: Key::Char(c), ..Input::default() }, textarea, registers);
+      vim.after(transition)
+    })
+  }
+
+  #[test]
+  fn test_named_registers() {
+    // yy leaves the cursor on the next line
+    let mut textarea = TextArea::from(["select 1", "from t", "where x"]);
+    let mut registers = Registers::new();
+    textarea.set_yank_text("unnamed");
+    let vim = press(Vim::new(Mode::Normal), "\"ayy\"byy\"Ayy", &mut textarea, &mut registers);
+    assert_eq!(registers.get(&'a').map(|s| s.as_str()), Some("select 1\nwhere x"));
+    assert_eq!(registers.get(&'b').map(|s| s.as_str()), Some("from t\n"));
+    assert_eq!(textarea.yank_text(), "unnamed");
+    assert_eq!((vim.mode, vim.register), (Mode::Normal, None));
+    let vim = press(vim, "gg0\"bp", &mut textarea, &mut registers);
+    assert_eq!(textarea.lines()[0], "from t");
+    assert_eq!(vim.register, None);
+  }
+}